@@ -0,0 +1,192 @@
+//! Builder for [`DMROptions`], validating them once built.
+
+use super::{
+    Capabilities, DMROptions, DMROptionsError, Route, ServiceVersions, UnknownPost,
+    xml::{av_transport::PlaySpeed, protocol_info::ProtocolInfo},
+};
+use ipnet::IpNet;
+use std::{
+    collections::BTreeSet,
+    net::{IpAddr, Ipv4Addr},
+};
+
+/// Defines a setter on [`DMROptionsBuilder`] for each listed field of [`DMROptions`]. Fields listed after `into` accept anything convertible into their type, e.g. a `&str` for a `String` or a bare value for an `Option`.
+macro_rules! setters {
+    (@setter $field:ident, $value:ident: $arg:ty) => {
+        #[doc = concat!("Sets [`", stringify!($field), "`](DMROptions::", stringify!($field), ").")]
+        #[must_use]
+        pub fn $field(mut self, $value: $arg) -> Self {
+            self.options.$field = $value.into();
+            self
+        }
+    };
+    (value { $($field:ident: $ty:ty),+ $(,)? } into { $($into_field:ident: $into_ty:ty),+ $(,)? }) => {
+        impl DMROptionsBuilder {
+            $(setters!(@setter $field, value: $ty);)+
+            $(setters!(@setter $into_field, value: impl Into<$into_ty>);)+
+        }
+    };
+}
+
+/// Builds [`DMROptions`] with fluent setters, starting from the defaults, and validates them in [`build`](DMROptionsBuilder::build).
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::DMROptions;
+///
+/// let options = DMROptions::builder()
+///     .friendly_name("Living Room")
+///     .http_port(8080)
+///     .uuid("12345678-1234-5678-1234-567812345678")
+///     .build()
+///     .expect("Invalid options");
+/// assert_eq!(options.friendly_name, "Living Room");
+/// assert_eq!(options.http_port, 8080);
+///
+/// assert!(DMROptions::builder().friendly_name(" ").build().is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DMROptionsBuilder {
+    options: DMROptions,
+}
+
+setters!(
+    value {
+        ip: IpAddr,
+        ssdp_port: u16,
+        ssdp_port_fallback: bool,
+        ssdp_alive_on_new_search: bool,
+        ssdp_keep_alive_jitter: u8,
+        ssdp_cache_max_age: u32,
+        ssdp_announce_repeats: u8,
+        ssdp_recv_buffer: usize,
+        http_port: u16,
+        http2: bool,
+        enabled_routes: BTreeSet<Route>,
+        unknown_post: UnknownPost,
+        startup_self_check: bool,
+        capabilities: Capabilities,
+        service_versions: ServiceVersions,
+        volume_cache_ms: u64,
+        check_uri_reachable: bool,
+        uri_validation_timeout_ms: u64,
+        volume_max: u16,
+    }
+    into {
+        interfaces: Option<Vec<Ipv4Addr>>,
+        http_bind_ip: Option<IpAddr>,
+        extra_search_targets: Vec<(String, String)>,
+        respond_to_subnets: Option<Vec<IpNet>>,
+        ssdp_keep_alive_ms: Option<u64>,
+        server_string: Option<String>,
+        description_max_age: Option<u64>,
+        advertise_location: Option<String>,
+        description_path: String,
+        supported_play_speeds: Vec<PlaySpeed>,
+        sink_protocol_info: Vec<ProtocolInfo>,
+        uuid: String,
+        uuid_seed: Option<String>,
+        friendly_name: String,
+        model_name: String,
+        model_description: String,
+        model_url: String,
+        manufacturer: String,
+        manufacturer_url: String,
+        serial_number: String,
+    }
+);
+
+impl DMROptionsBuilder {
+    /// Validates the options and returns them.
+    ///
+    /// Unlike [`DMROptions::validate`] alone, this also rejects ports set to `0`, which [`DMR::run`](crate::DMR::run) would replace with ephemeral ones: use a struct literal if that's what you want.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DMROptionsError::Config`] if [`ssdp_port`](DMROptions::ssdp_port) or [`http_port`](DMROptions::http_port) is `0`, if [`friendly_name`](DMROptions::friendly_name) is blank, or any error of [`DMROptions::validate`].
+    pub fn build(self) -> Result<DMROptions, DMROptionsError> {
+        let options = self.options;
+        for (name, port) in [
+            ("ssdp_port", options.ssdp_port),
+            ("http_port", options.http_port),
+        ] {
+            if port == 0 {
+                return Err(DMROptionsError::Config(format!("`{name}` must not be 0")));
+            }
+        }
+        if options.friendly_name.trim().is_empty() {
+            return Err(DMROptionsError::Config(
+                "`friendly_name` must not be empty".to_string(),
+            ));
+        }
+        options.validate()?;
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let options = DMROptions::builder()
+            .ip(Ipv4Addr::LOCALHOST.into())
+            .interfaces(vec![Ipv4Addr::LOCALHOST])
+            .http_port(8080)
+            .server_string("Linux/6 UPnP/1.0 test/1".to_string())
+            .uuid("12345678-1234-5678-1234-567812345678")
+            .friendly_name("Living Room")
+            .build()
+            .expect("Failed to build options");
+        assert_eq!(options.ip, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(options.interfaces, Some(vec![Ipv4Addr::LOCALHOST]));
+        assert_eq!(options.http_port, 8080);
+        assert_eq!(
+            options.server_string.as_deref(),
+            Some("Linux/6 UPnP/1.0 test/1")
+        );
+        assert_eq!(options.uuid, "12345678-1234-5678-1234-567812345678");
+        assert_eq!(options.friendly_name, "Living Room");
+        assert_eq!(options.ssdp_port, DMROptions::default().ssdp_port);
+
+        // The UUID is ignored if derived from a seed
+        DMROptions::builder()
+            .uuid("")
+            .uuid_seed("Living Room".to_string())
+            .build()
+            .expect("Failed to build options with a seed");
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        for (builder, message) in [
+            (
+                DMROptions::builder().ssdp_port(0),
+                "`ssdp_port` must not be 0",
+            ),
+            (
+                DMROptions::builder().http_port(0),
+                "`http_port` must not be 0",
+            ),
+            (DMROptions::builder().uuid("not-a-uuid"), "Invalid `uuid`"),
+            (DMROptions::builder().uuid(""), "Invalid `uuid`"),
+            (
+                DMROptions::builder().friendly_name(""),
+                "`friendly_name` must not be empty",
+            ),
+            (
+                DMROptions::builder().friendly_name(" \t"),
+                "`friendly_name` must not be empty",
+            ),
+            (
+                DMROptions::builder().ssdp_announce_repeats(0),
+                "`ssdp_announce_repeats` must not be 0",
+            ),
+        ] {
+            let DMROptionsError::Config(error) = builder.build().expect_err(message);
+            assert!(error.contains(message), "{error}");
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Optional actions a renderer supports, advertised to controllers and enforced when they're sent anyway.
+
+use super::{
+    UpnpError,
+    state::TransportAction,
+    xml::{AVTransport, RenderingControl},
+};
+use serde::{Deserialize, Serialize};
+
+/// Optional actions supported by the renderer, see [`DMROptions::capabilities`](super::DMROptions::capabilities). All of them are enabled by default; fields missing from the config file stay enabled.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::{Capabilities, state::TransportAction};
+///
+/// let capabilities = Capabilities {
+///     can_seek: false,
+///     ..Default::default()
+/// };
+/// assert!(!capabilities.allows(TransportAction::Seek));
+/// assert!(capabilities.allows(TransportAction::Pause));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(
+    clippy::struct_excessive_bools,
+    reason = "Independent toggles, mirroring the config file"
+)]
+pub struct Capabilities {
+    /// Whether `Seek` is supported. Otherwise, it's answered with [`UpnpError::seek_mode_not_supported`].
+    pub can_seek: bool,
+    /// Whether `Pause` is supported. Otherwise, it's answered with [`UpnpError::transition_not_available`].
+    pub can_pause: bool,
+    /// Whether `SetNextAVTransportURI` is supported, e.g. for gapless playback. Otherwise, it's answered with [`UpnpError::invalid_action`].
+    pub can_set_next_uri: bool,
+    /// Whether `SetVolume` is supported. Otherwise, it's answered with [`UpnpError::invalid_action`].
+    pub can_set_volume: bool,
+    /// Whether `SetMute` is supported. Otherwise, it's answered with [`UpnpError::invalid_action`].
+    pub can_mute: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            can_seek: true,
+            can_pause: true,
+            can_set_next_uri: true,
+            can_set_volume: true,
+            can_mute: true,
+        }
+    }
+}
+
+impl Capabilities {
+    /// Whether `action` may be listed in `CurrentTransportActions`.
+    #[must_use]
+    pub const fn allows(&self, action: TransportAction) -> bool {
+        match action {
+            TransportAction::Seek => self.can_seek,
+            TransportAction::Pause => self.can_pause,
+            _ => true,
+        }
+    }
+
+    /// Checks that an `AVTransport` action is supported.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the fault to answer disabled actions with, see the fields of [`Capabilities`].
+    pub fn check_av_transport(&self, action: &AVTransport) -> Result<(), UpnpError> {
+        match action {
+            AVTransport::Seek(_) if !self.can_seek => Err(UpnpError::seek_mode_not_supported()),
+            AVTransport::Pause(_) if !self.can_pause => Err(UpnpError::transition_not_available()),
+            AVTransport::SetNextAVTransportURI(_) if !self.can_set_next_uri => {
+                Err(UpnpError::invalid_action())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that a `RenderingControl` action is supported.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the fault to answer disabled actions with, see the fields of [`Capabilities`].
+    pub fn check_rendering_control(&self, action: &RenderingControl) -> Result<(), UpnpError> {
+        match action {
+            RenderingControl::SetVolume(_) if !self.can_set_volume => {
+                Err(UpnpError::invalid_action())
+            }
+            RenderingControl::SetMute(_) if !self.can_mute => Err(UpnpError::invalid_action()),
+            _ => Ok(()),
+        }
+    }
+}
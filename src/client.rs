@@ -0,0 +1,69 @@
+//! A minimal HTTP client, for requests the renderer makes itself.
+
+use axum::{
+    body::Bytes,
+    http::{Method, Request, Response, Uri, header},
+};
+use http_body_util::{BodyExt, Full};
+use hyper::client::conn::http1;
+use hyper_util::rt::TokioIo;
+use log::trace;
+use std::io::{Error, ErrorKind, Result};
+use tokio::net::TcpStream;
+
+/// Sends a request over a fresh HTTP/1.1 connection and collects the response.
+pub async fn request(
+    method: Method,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: String,
+) -> Result<Response<Bytes>> {
+    let uri: Uri = url
+        .parse()
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("Invalid URL {url}: {e}")))?;
+    let Some(authority) = uri.authority().cloned() else {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("URL {url} has no host"),
+        ));
+    };
+    if uri.scheme_str() != Some("http") {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unsupported scheme in URL {url}"),
+        ));
+    }
+
+    let port = authority.port_u16().unwrap_or(80);
+    // IPv6 literals are bracketed in URLs, but not when resolved
+    let host = authority.host();
+    let host = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host);
+    let stream = TcpStream::connect((host, port)).await?;
+    let (mut sender, connection) = http1::handshake(TokioIo::new(stream))
+        .await
+        .map_err(Error::other)?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            trace!("HTTP client connection closed: {e}");
+        }
+    });
+
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri.path_and_query().map_or("/", |p| p.as_str()))
+        .header(header::HOST, authority.as_str());
+    for (name, value) in headers {
+        builder = builder.header(*name, *value);
+    }
+    let request = builder
+        .body(Full::new(Bytes::from(body)))
+        .map_err(Error::other)?;
+
+    let response = sender.send_request(request).await.map_err(Error::other)?;
+    let (parts, body) = response.into_parts();
+    let body = body.collect().await.map_err(Error::other)?.to_bytes();
+    Ok(Response::from_parts(parts, body))
+}
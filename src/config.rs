@@ -0,0 +1,228 @@
+//! Loading [`DMROptions`] from configuration files in different formats.
+//!
+//! Each format is behind a feature: `toml` (also enabled by `cli`), `json` and `yaml`.
+
+use super::{ConfigError, DMROptions};
+use serde::de::DeserializeOwned;
+use std::{fmt::Display, path::Path};
+
+/// Formats configuration files can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigFormat {
+    /// TOML, e.g. `config.toml`. Requires the `toml` feature.
+    Toml,
+    /// JSON, e.g. `config.json`. Requires the `json` feature.
+    Json,
+    /// YAML, e.g. `config.yaml` or `config.yml`. Requires the `yaml` feature.
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// All formats, whether enabled or not.
+    pub const ALL: [Self; 3] = [Self::Toml, Self::Json, Self::Yaml];
+
+    /// Determines the format from the extension of `path`, case-insensitively.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use dlna_dmr::ConfigFormat;
+    ///
+    /// assert_eq!(ConfigFormat::from_path("config.TOML"), Some(ConfigFormat::Toml));
+    /// assert_eq!(ConfigFormat::from_path("/etc/dmr/config.yml"), Some(ConfigFormat::Yaml));
+    /// assert_eq!(ConfigFormat::from_path("config"), None);
+    /// ```
+    #[must_use]
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "toml" => Some(Self::Toml),
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    /// The feature enabling the format.
+    #[must_use]
+    pub const fn feature(self) -> &'static str {
+        match self {
+            Self::Toml => "toml",
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+        }
+    }
+
+    /// Whether support for the format is compiled in.
+    #[must_use]
+    pub const fn is_enabled(self) -> bool {
+        match self {
+            Self::Toml => cfg!(feature = "toml"),
+            Self::Json => cfg!(feature = "json"),
+            Self::Yaml => cfg!(feature = "yaml"),
+        }
+    }
+
+    /// Deserializes a configuration in this format into any value, e.g. a table to merge several configurations into before deserializing the options, see [`DMROptions::from_str_with_format`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`ConfigError::Disabled`] if support for the format isn't compiled in, or [`ConfigError::Parse`] if the configuration is malformed.
+    #[cfg_attr(
+        not(any(feature = "toml", feature = "json", feature = "yaml")),
+        allow(
+            unused_variables,
+            clippy::missing_const_for_fn,
+            reason = "Only parsed by the enabled formats"
+        )
+    )]
+    pub fn parse<T: DeserializeOwned>(self, s: &str) -> Result<T, ConfigError> {
+        match self {
+            #[cfg(feature = "toml")]
+            Self::Toml => toml::from_str(s).map_err(|e| ConfigError::Parse(self, e.to_string())),
+            #[cfg(feature = "json")]
+            Self::Json => {
+                serde_json::from_str(s).map_err(|e| ConfigError::Parse(self, e.to_string()))
+            }
+            #[cfg(feature = "yaml")]
+            Self::Yaml => {
+                serde_yaml::from_str(s).map_err(|e| ConfigError::Parse(self, e.to_string()))
+            }
+            #[allow(
+                unreachable_patterns,
+                reason = "Reachable unless all formats are enabled"
+            )]
+            format => Err(ConfigError::Disabled(format)),
+        }
+    }
+}
+
+impl Display for ConfigFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Toml => write!(f, "TOML"),
+            Self::Json => write!(f, "JSON"),
+            Self::Yaml => write!(f, "YAML"),
+        }
+    }
+}
+
+impl DMROptions {
+    /// Loads the options from the configuration file at `path`, whose format is determined by its extension (see [`ConfigFormat::from_path`]). Missing fields take their default values. The options aren't validated, see [`DMROptions::validate`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`ConfigError::UnknownFormat`] if the extension isn't recognized, [`ConfigError::Io`] if the file can't be read, or the errors of [`from_str_with_format`](DMROptions::from_str_with_format).
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let format = ConfigFormat::from_path(path)
+            .ok_or_else(|| ConfigError::UnknownFormat(path.display().to_string()))?;
+        Self::from_str_with_format(&std::fs::read_to_string(path)?, format)
+    }
+
+    /// Parses the options from a configuration in given format. Missing fields take their default values.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`ConfigError::Disabled`] if support for the format isn't compiled in, or [`ConfigError::Parse`] if the configuration is malformed.
+    pub fn from_str_with_format(s: &str, format: ConfigFormat) -> Result<Self, ConfigError> {
+        format.parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Options differing from the defaults in fields of various types.
+    fn sample() -> DMROptions {
+        DMROptions {
+            ip: "192.168.1.20".parse().unwrap(),
+            interfaces: Some(vec!["192.168.1.20".parse().unwrap()]),
+            http_port: 9090,
+            http2: true,
+            extra_search_targets: vec![(
+                "urn:example:service:Foo:1".to_string(),
+                "uuid:1::urn:example:service:Foo:1".to_string(),
+            )],
+            ssdp_keep_alive_ms: Some(60_000),
+            supported_play_speeds: vec![
+                crate::xml::av_transport::PlaySpeed::One,
+                crate::xml::av_transport::PlaySpeed::MinusTwo,
+            ],
+            uuid: "12345678-1234-5678-1234-567812345678".to_string(),
+            friendly_name: "Round Trip".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Serializes the options in given format, if enabled.
+    #[cfg_attr(
+        not(any(feature = "toml", feature = "json", feature = "yaml")),
+        allow(unused_variables, reason = "Only read by the enabled formats")
+    )]
+    fn serialize(options: &DMROptions, format: ConfigFormat) -> Option<String> {
+        match format {
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml => Some(toml::to_string(options).expect("Failed to serialize TOML")),
+            #[cfg(feature = "json")]
+            ConfigFormat::Json => {
+                Some(serde_json::to_string(options).expect("Failed to serialize JSON"))
+            }
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => {
+                Some(serde_yaml::to_string(options).expect("Failed to serialize YAML"))
+            }
+            #[allow(
+                unreachable_patterns,
+                reason = "Reachable unless all formats are enabled"
+            )]
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let options = sample();
+        for format in ConfigFormat::ALL {
+            let Some(config) = serialize(&options, format) else {
+                assert!(!format.is_enabled());
+                assert!(matches!(
+                    DMROptions::from_str_with_format("", format),
+                    Err(ConfigError::Disabled(f)) if f == format
+                ));
+                continue;
+            };
+            let parsed = DMROptions::from_str_with_format(&config, format)
+                .unwrap_or_else(|e| panic!("{e}\n{config}"));
+            assert_eq!(format!("{parsed:?}"), format!("{options:?}"), "{format}");
+        }
+    }
+
+    #[test]
+    fn test_from_file() {
+        for format in ConfigFormat::ALL.into_iter().filter(|f| f.is_enabled()) {
+            let path = format!("tests/test-config.{}", format.feature());
+            let options = DMROptions::from_file(&path).unwrap_or_else(|e| panic!("{path}: {e}"));
+            assert_eq!(options.friendly_name, "Friendly Renderer", "{path}");
+            assert_eq!(options.http_port, DMROptions::default().http_port, "{path}");
+
+            let error = DMROptions::from_str_with_format("friendly_name: [", format)
+                .expect_err("Malformed configuration should be rejected");
+            assert!(
+                matches!(error, ConfigError::Parse(f, _) if f == format),
+                "{error}"
+            );
+        }
+        assert!(matches!(
+            DMROptions::from_file("tests/test-config.ini"),
+            Err(ConfigError::UnknownFormat(_))
+        ));
+        if ConfigFormat::Toml.is_enabled() {
+            assert!(matches!(
+                DMROptions::from_file("tests/missing.toml"),
+                Err(ConfigError::Io(_))
+            ));
+        }
+    }
+}
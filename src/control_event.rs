@@ -0,0 +1,26 @@
+//! A stream of the actions received by the renderer, for UIs and loggers. See [`ControlContext::events`](super::ControlContext::events).
+
+use super::xml::{AVTransport, ConnectionManager, RenderingControl};
+use std::{net::SocketAddr, time::SystemTime};
+
+/// An action received from a controller, published once it's parsed and before it's handled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlEvent {
+    /// The action.
+    pub action: ControlAction,
+    /// The address of the controller, if known. It's `None` if the router is served without connection info.
+    pub peer: Option<SocketAddr>,
+    /// When the action was received.
+    pub received_at: SystemTime,
+}
+
+/// A parsed action of one of the services, see [`ControlEvent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlAction {
+    /// An `AVTransport` action.
+    AVTransport(AVTransport),
+    /// A `RenderingControl` action.
+    RenderingControl(RenderingControl),
+    /// A `ConnectionManager` action.
+    ConnectionManager(ConnectionManager),
+}
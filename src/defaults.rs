@@ -1,32 +1,208 @@
 //! Default values for [`DMROptions`](super::DMROptions).
 
+use super::{
+    Capabilities, Route, ServiceVersions, UnknownPost,
+    xml::{
+        av_transport::PlaySpeed,
+        protocol_info::{DlnaParams, ProtocolInfo},
+    },
+};
+use ipnet::IpNet;
 use local_ip_address::local_ip;
-use std::net::{IpAddr, Ipv4Addr};
-
-/// Default IP, determined by the local machine's IP address.
-pub fn ip() -> Ipv4Addr {
-    let ip = local_ip().expect("Failed to get local IP address");
-    match ip {
-        IpAddr::V4(ip) => ip,
-        IpAddr::V6(_) => panic!("IPv6 is not supported"),
+use log::warn;
+use std::{
+    collections::BTreeSet,
+    fmt::Display,
+    net::{IpAddr, Ipv4Addr},
+};
+
+/// Default IP, determined by the local machine's IP address. Falls back to `127.0.0.1` if it can't be determined.
+pub fn ip() -> IpAddr {
+    ip_or_fallback(local_ip())
+}
+
+/// Extracts the address from the result of a local IP lookup, falling back to `127.0.0.1` with a warning.
+fn ip_or_fallback<E: Display>(lookup: Result<IpAddr, E>) -> IpAddr {
+    let fallback = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    match lookup {
+        Ok(ip) => ip,
+        Err(e) => {
+            warn!(
+                "Failed to get local IP address ({e}); falling back to {fallback}. Set `ip` explicitly to be reachable by controllers"
+            );
+            fallback
+        }
     }
 }
 
+/// Default interfaces SSDP uses, i.e. the one of `ip`, or all of them if `ip` is unspecified.
+pub const fn interfaces() -> Option<Vec<Ipv4Addr>> {
+    None
+}
+
 /// Default SSDP server port.
 pub const fn ssdp_port() -> u16 {
     1900
 }
 
+/// Whether to fall back to an ephemeral SSDP port by default.
+pub const fn ssdp_port_fallback() -> bool {
+    true
+}
+
+/// Default time controllers may cache descriptions, i.e. no cache headers.
+pub const fn description_max_age() -> Option<u64> {
+    None
+}
+
+/// Default base URL advertised to controllers, i.e. derived from the IP and HTTP port.
+pub const fn advertise_location() -> Option<String> {
+    None
+}
+
+/// Default path of the device description.
+pub fn description_path() -> String {
+    Route::DeviceSpec.path().to_string()
+}
+
+/// Whether to run the startup self-check by default.
+pub const fn startup_self_check() -> bool {
+    true
+}
+
+/// Whether to send an `ssdp:alive` burst on searches from new controllers by default.
+pub const fn ssdp_alive_on_new_search() -> bool {
+    true
+}
+
+/// Default additional search targets, i.e. none.
+pub const fn extra_search_targets() -> Vec<(String, String)> {
+    Vec::new()
+}
+
+/// Default subnets whose searches are answered, i.e. all.
+pub const fn respond_to_subnets() -> Option<Vec<IpNet>> {
+    None
+}
+
+/// Default jitter of the interval between `ssdp:alive` announcements, in percent.
+pub const fn ssdp_keep_alive_jitter() -> u8 {
+    10
+}
+
+/// Default time controllers may consider the renderer available after an announcement, in seconds.
+pub const fn ssdp_cache_max_age() -> u32 {
+    1800
+}
+
+/// Default interval between `ssdp:alive` announcements, i.e. derived from the cache max age.
+pub const fn ssdp_keep_alive_ms() -> Option<u64> {
+    None
+}
+
+/// Default number of times announcements are sent on startup and shutdown.
+pub const fn ssdp_announce_repeats() -> u8 {
+    3
+}
+
+/// Default SSDP `SERVER` header, i.e. one naming this crate.
+pub const fn server_string() -> Option<String> {
+    None
+}
+
+/// Default size of the buffer SSDP messages are received into, in bytes.
+pub const fn ssdp_recv_buffer() -> usize {
+    8192
+}
+
 /// Default HTTP server port.
 pub const fn http_port() -> u16 {
     8080
 }
 
+/// Default HTTP bind address, i.e. the advertised `ip`.
+pub const fn http_bind_ip() -> Option<IpAddr> {
+    None
+}
+
+/// Default HTTP/2 support, i.e. HTTP/1.1 only.
+pub const fn http2() -> bool {
+    false
+}
+
+/// Default routes of the HTTP server, i.e. all of them.
+pub fn enabled_routes() -> BTreeSet<Route> {
+    Route::ALL.into()
+}
+
+/// Default answer to POST requests to unknown paths, i.e. `404 Not Found`.
+pub const fn unknown_post() -> UnknownPost {
+    UnknownPost::NotFound
+}
+
+/// Default capabilities, i.e. all optional actions supported.
+pub fn capabilities() -> Capabilities {
+    Capabilities::default()
+}
+
+/// Default versions of the services, i.e. `1` for all of them.
+pub fn service_versions() -> ServiceVersions {
+    ServiceVersions::default()
+}
+
+/// Default playback speeds, i.e. normal speed only.
+pub fn supported_play_speeds() -> Vec<PlaySpeed> {
+    vec![PlaySpeed::One]
+}
+
+/// Default formats the renderer accepts, i.e. common audio and video formats via HTTP, with byte-range seeking.
+pub fn sink_protocol_info() -> Vec<ProtocolInfo> {
+    let dlna = DlnaParams::new();
+    [
+        "video/mp4",
+        "video/mpeg",
+        "video/webm",
+        "video/x-matroska",
+        "audio/mpeg",
+        "audio/mp4",
+        "audio/flac",
+        "audio/wav",
+    ]
+    .into_iter()
+    .map(|content_format| ProtocolInfo::http_get(content_format, &dlna))
+    .collect()
+}
+
+/// Default time the volume and mute are cached, in milliseconds.
+pub const fn volume_cache_ms() -> u64 {
+    500
+}
+
+/// Whether to check that URIs are reachable by default.
+pub const fn check_uri_reachable() -> bool {
+    false
+}
+
+/// Default timeout of the `HEAD` request checking URIs, in milliseconds.
+pub const fn uri_validation_timeout_ms() -> u64 {
+    3000
+}
+
+/// Default maximum of the hardware volume range.
+pub const fn volume_max() -> u16 {
+    100
+}
+
 /// Default UUID of the DMR instance, generated randomly.
 pub fn uuid() -> String {
     uuid::Uuid::new_v4().to_string()
 }
 
+/// Default seed to derive the UUID from, i.e. none.
+pub const fn uuid_seed() -> Option<String> {
+    None
+}
+
 /// Default friendly name of the DMR instance.
 pub fn friendly_name() -> String {
     "Dummy Renderer".to_string()
@@ -61,3 +237,24 @@ pub fn manufacturer_url() -> String {
 pub fn serial_number() -> String {
     "12345678-1234-5678-1234-567812345678".to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_lookup_failed() {
+        assert_eq!(
+            ip_or_fallback(Err("no network interface")),
+            IpAddr::V4(Ipv4Addr::LOCALHOST)
+        );
+    }
+
+    #[test]
+    fn test_ip_lookup() {
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20));
+        assert_eq!(ip_or_fallback::<&str>(Ok(ip)), ip);
+        let ip = IpAddr::V6(std::net::Ipv6Addr::LOCALHOST);
+        assert_eq!(ip_or_fallback::<&str>(Ok(ip)), ip);
+    }
+}
@@ -0,0 +1,233 @@
+//! Error types.
+
+use super::{config::ConfigFormat, xml::soap};
+use quick_xml::DeError;
+use std::fmt::{self, Display};
+
+/// Errors that can occur when setting up or running a DMR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DmrError {
+    /// Invalid configuration, e.g. a malformed template or option.
+    Config(String),
+}
+
+impl Display for DmrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Config(message) => write!(f, "Invalid configuration: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for DmrError {}
+
+/// Errors building [`DMROptions`](crate::DMROptions) with [`DMROptionsBuilder::build`](crate::DMROptionsBuilder::build), i.e. invalid options.
+pub type DMROptionsError = DmrError;
+
+/// Errors that can occur when loading [`DMROptions`](crate::DMROptions) from a configuration file, see [`DMROptions::from_file`](crate::DMROptions::from_file).
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file couldn't be read.
+    Io(std::io::Error),
+    /// The format couldn't be determined from the extension of the file, e.g. `.ini`.
+    UnknownFormat(String),
+    /// Support for the format isn't compiled in, i.e. its feature is disabled.
+    Disabled(ConfigFormat),
+    /// The configuration isn't valid in its format, or doesn't describe valid options.
+    Parse(ConfigFormat, String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Failed to read configuration: {e}"),
+            Self::UnknownFormat(path) => write!(
+                f,
+                "Unknown configuration format of {path:?}: expected a .toml, .json, .yaml or .yml file"
+            ),
+            Self::Disabled(format) => write!(
+                f,
+                "{format} configuration isn't supported: enable the `{}` feature",
+                format.feature()
+            ),
+            Self::Parse(format, message) => {
+                write!(f, "Failed to parse {format} configuration: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// An error parsing a SOAP action, with the name of the action if it could be detected.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::xml::AVTransport;
+///
+/// let xml = r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/">
+///     <s:Body><u:Play xmlns:u="urn:schemas-upnp-org:service:AVTransport:1"/></s:Body>
+/// </s:Envelope>"#;
+/// let error = xml.parse::<AVTransport>().expect_err("Speed is missing");
+/// assert_eq!(error.action.as_deref(), Some("Play"));
+/// assert!(error.to_string().starts_with("Failed to parse Play action: "));
+/// ```
+#[derive(Debug)]
+pub struct ParseError {
+    /// The name of the action, i.e. the first element in the SOAP body, or `None` if it couldn't be detected.
+    pub action: Option<String>,
+    /// The underlying deserialization error.
+    pub error: DeError,
+}
+
+impl ParseError {
+    /// Wraps `error`, which occurred when deserializing `xml`, detecting the action it contains.
+    #[must_use]
+    pub fn new(xml: &str, error: DeError) -> Self {
+        Self {
+            action: soap::action(xml),
+            error,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.action {
+            Some(action) => write!(f, "Failed to parse {action} action: {}", self.error),
+            None => write!(f, "Failed to parse SOAP action: {}", self.error),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// An error reported to control points as a SOAP fault, with one of the error codes defined by the `UPnP` specifications.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpnpError {
+    /// The `UPnP` error code, e.g. `716`.
+    pub code: u16,
+    /// A short human-readable description of the error.
+    pub description: String,
+}
+
+impl UpnpError {
+    /// Creates an error with given code and description.
+    #[must_use]
+    pub fn new(code: u16, description: impl Into<String>) -> Self {
+        Self {
+            code,
+            description: description.into(),
+        }
+    }
+
+    /// `401 Invalid Action`: the action isn't supported by the service.
+    #[must_use]
+    pub fn invalid_action() -> Self {
+        Self::new(401, "Invalid Action")
+    }
+
+    /// `402 Invalid Args`: the arguments of the action are missing, superfluous or malformed.
+    #[must_use]
+    pub fn invalid_args() -> Self {
+        Self::new(402, "Invalid Args")
+    }
+
+    /// `601 Argument Value Out of Range`: an argument of the action is outside of the range allowed by the service, e.g. a `DesiredVolume` above `100`.
+    #[must_use]
+    pub fn argument_value_out_of_range() -> Self {
+        Self::new(601, "Argument Value Out of Range")
+    }
+
+    /// `602 Optional Action Not Implemented`: the action is defined by the service, but not implemented by the device.
+    #[must_use]
+    pub fn optional_action_not_implemented() -> Self {
+        Self::new(602, "Optional Action Not Implemented")
+    }
+
+    /// `701 Transition not available`: the immediate transition from the current transport state to the desired one is not supported, e.g. `Record` on a renderer that can't record.
+    #[must_use]
+    pub fn transition_not_available() -> Self {
+        Self::new(701, "Transition not available")
+    }
+
+    /// `706 Invalid connection reference`: the specified connection of `ConnectionManager` doesn't exist.
+    #[must_use]
+    pub fn invalid_connection_reference() -> Self {
+        Self::new(706, "Invalid connection reference")
+    }
+
+    /// `710 Seek mode not supported`: the specified seek mode, or seeking at all, is not supported.
+    #[must_use]
+    pub fn seek_mode_not_supported() -> Self {
+        Self::new(710, "Seek mode not supported")
+    }
+
+    /// `711 Illegal seek target`: the position to seek to is invalid, or outside of the current track.
+    #[must_use]
+    pub fn illegal_seek_target() -> Self {
+        Self::new(711, "Illegal seek target")
+    }
+
+    /// `712 Play mode not supported`: the specified play mode, e.g. shuffle, is not supported.
+    #[must_use]
+    pub fn play_mode_not_supported() -> Self {
+        Self::new(712, "Play mode not supported")
+    }
+
+    /// `713 Record quality not supported`: the specified record quality mode is not supported.
+    #[must_use]
+    pub fn record_quality_not_supported() -> Self {
+        Self::new(713, "Record quality not supported")
+    }
+
+    /// `714 Illegal MIME-type`: the specified resource has a MIME type not supported by the renderer, or couldn't be retrieved.
+    #[must_use]
+    pub fn illegal_mime_type() -> Self {
+        Self::new(714, "Illegal MIME-type")
+    }
+
+    /// `716 Resource not found`: the specified resource can't be found or accessed.
+    #[must_use]
+    pub fn resource_not_found() -> Self {
+        Self::new(716, "Resource not found")
+    }
+
+    /// `717 Play speed not supported`: the specified playback speed is not supported.
+    #[must_use]
+    pub fn play_speed_not_supported() -> Self {
+        Self::new(717, "Play speed not supported")
+    }
+
+    /// `718 Invalid InstanceID`: the specified instance of the service doesn't exist.
+    #[must_use]
+    pub fn invalid_instance_id() -> Self {
+        Self::new(718, "Invalid InstanceID")
+    }
+}
+
+impl Display for UpnpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "UPnP error {}: {}", self.code, self.description)
+    }
+}
+
+impl std::error::Error for UpnpError {}
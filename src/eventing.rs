@@ -0,0 +1,602 @@
+//! GENA eventing: subscriptions of controllers to the state variable changes of the services.
+//!
+//! Controllers `SUBSCRIBE` to the event URL of a service, e.g. `/AVTransport/event`, renew their subscription with its `SID` before it times out, and `UNSUBSCRIBE` once they're done. In the meantime, events are delivered to them with `NOTIFY` requests, numbered by `SEQ`. See section 4 of the [`UPnP` Device Architecture](https://openconnectivity.org/upnp-specs/UPnP-arch-DeviceArchitecture-v1.1.pdf).
+
+use super::{Route, client, response::XML_CONTENT_TYPE};
+use axum::{
+    http::{HeaderMap, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use log::{debug, warn};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+use tokio::{
+    sync::{Mutex as AsyncMutex, OwnedMutexGuard},
+    task::JoinSet,
+    time::{Instant, sleep},
+};
+
+/// Time a subscription lasts unless renewed, if the controller doesn't request one.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_mins(30);
+
+/// How many times an event is sent to all callbacks of a subscription before giving up and dropping the subscription.
+const NOTIFY_ATTEMPTS: u8 = 3;
+
+/// Delay between the attempts to deliver an event.
+const NOTIFY_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// A controller subscribed to the events of a service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    /// The route of the service subscribed to, e.g. [`Route::AVTransport`].
+    pub route: Route,
+    /// URLs the events are delivered to, tried in order.
+    pub callbacks: Vec<String>,
+    /// When the subscription expires, unless renewed.
+    pub expires: Instant,
+    /// The `SEQ` of the next event, `0` for the initial one.
+    pub seq: u32,
+}
+
+/// A subscription along with the lock serializing the deliveries of its events.
+#[derive(Debug)]
+struct Entry {
+    subscription: Subscription,
+    /// Held while delivering an event, so that events are delivered one at a time, in `SEQ` order.
+    delivery: Arc<AsyncMutex<()>>,
+}
+
+/// A new subscription, whose initial event (`SEQ` 0) is yet to be delivered with [`notify`](NewSubscription::notify). Other events wait until then.
+#[derive(Debug)]
+pub struct NewSubscription {
+    /// The `SID` of the subscription.
+    pub sid: String,
+    delivery: OwnedMutexGuard<()>,
+}
+
+impl NewSubscription {
+    /// Delivers the initial event, see [`notify`](self::notify). Returns whether it was delivered.
+    pub async fn notify(self, subscriptions: &Subscriptions, body: &str) -> bool {
+        let delivered = deliver(subscriptions, &self.sid, body).await;
+        drop(self.delivery);
+        delivered
+    }
+}
+
+/// Subscriptions of controllers to the events of the services, by `SID`. Expired subscriptions are dropped as soon as they're looked up.
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    subscriptions: Mutex<HashMap<String, Entry>>,
+}
+
+impl Subscriptions {
+    /// Locks the subscriptions, dropping the expired ones.
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Entry>> {
+        let mut subscriptions = self
+            .subscriptions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        subscriptions.retain(|sid, Entry { subscription, .. }| {
+            let live = subscription.expires > now;
+            if !live {
+                debug!("Subscription {sid} expired");
+            }
+            live
+        });
+        subscriptions
+    }
+
+    /// Subscribes given callbacks to the events of a service for `timeout`, returning the new `SID`.
+    pub fn subscribe(&self, route: Route, callbacks: Vec<String>, timeout: Duration) -> String {
+        self.subscribe_new(route, callbacks, timeout).sid
+    }
+
+    /// Subscribes like [`subscribe`](Subscriptions::subscribe), holding back other events until the initial one is delivered.
+    fn subscribe_new(
+        &self,
+        route: Route,
+        callbacks: Vec<String>,
+        timeout: Duration,
+    ) -> NewSubscription {
+        let sid = format!("uuid:{}", uuid::Uuid::new_v4());
+        debug!(
+            "New subscription {sid} to {} for {callbacks:?}",
+            route.path()
+        );
+        let delivery = Arc::new(AsyncMutex::new(()));
+        let guard = Arc::clone(&delivery)
+            .try_lock_owned()
+            .expect("A new lock is free");
+        self.lock().insert(
+            sid.clone(),
+            Entry {
+                subscription: Subscription {
+                    route,
+                    callbacks,
+                    expires: Instant::now() + timeout,
+                    seq: 0,
+                },
+                delivery,
+            },
+        );
+        NewSubscription {
+            sid,
+            delivery: guard,
+        }
+    }
+
+    /// Extends a subscription to the events of a service by `timeout` from now. Returns `false` if there's no such subscription, e.g. because it expired.
+    pub fn renew(&self, route: Route, sid: &str, timeout: Duration) -> bool {
+        self.lock()
+            .get_mut(sid)
+            .filter(|entry| entry.subscription.route == route)
+            .map(|entry| entry.subscription.expires = Instant::now() + timeout)
+            .is_some()
+    }
+
+    /// Cancels a subscription to the events of a service. Returns `false` if there's no such subscription, e.g. because it expired.
+    pub fn unsubscribe(&self, route: Route, sid: &str) -> bool {
+        let mut subscriptions = self.lock();
+        if subscriptions
+            .get(sid)
+            .is_some_and(|entry| entry.subscription.route == route)
+        {
+            subscriptions.remove(sid);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The callbacks and `SEQ` of the next event of a subscription, advancing its `SEQ`. After `u32::MAX`, it wraps to `1`, as `0` is reserved for the initial event.
+    fn next_event(&self, sid: &str) -> Option<(Vec<String>, u32)> {
+        self.lock().get_mut(sid).map(|Entry { subscription, .. }| {
+            let seq = subscription.seq;
+            subscription.seq = seq.checked_add(1).unwrap_or(1);
+            (subscription.callbacks.clone(), seq)
+        })
+    }
+
+    /// The lock serializing the deliveries of a subscription's events.
+    fn delivery(&self, sid: &str) -> Option<Arc<AsyncMutex<()>>> {
+        self.lock()
+            .get(sid)
+            .map(|entry| Arc::clone(&entry.delivery))
+    }
+
+    /// The subscription with given `SID`, unless it expired.
+    #[must_use]
+    pub fn get(&self, sid: &str) -> Option<Subscription> {
+        self.lock().get(sid).map(|entry| entry.subscription.clone())
+    }
+
+    /// The live subscriptions to the events of a service, with their `SID`.
+    #[must_use]
+    pub fn of(&self, route: Route) -> Vec<(String, Subscription)> {
+        self.lock()
+            .iter()
+            .filter(|(_, entry)| entry.subscription.route == route)
+            .map(|(sid, entry)| (sid.clone(), entry.subscription.clone()))
+            .collect()
+    }
+}
+
+/// Answers a request to the event URL of a service: `SUBSCRIBE` with `CALLBACK` and `NT` subscribes, `SUBSCRIBE` with `SID` renews and `UNSUBSCRIBE` with `SID` cancels a subscription.
+///
+/// Requests missing the required headers, with an invalid `CALLBACK` or `NT`, or with an unknown `SID` are answered with `412 Precondition Failed`, while those mixing `SID` with `CALLBACK` or `NT` are answered with `400 Bad Request`. A new subscription is returned along with the response, so that the initial event can be sent once the response carrying its `SID` is.
+pub fn respond(
+    subscriptions: &Subscriptions,
+    route: Route,
+    method: &Method,
+    headers: &HeaderMap,
+) -> (Response, Option<NewSubscription>) {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let sid = header("SID");
+    let new_subscription = header("CALLBACK").is_some() || header("NT").is_some();
+    let status = match (method.as_str(), sid) {
+        ("SUBSCRIBE" | "UNSUBSCRIBE", Some(_)) if new_subscription => StatusCode::BAD_REQUEST,
+        ("SUBSCRIBE", Some(sid)) => {
+            let timeout = parse_timeout(header("TIMEOUT"));
+            if subscriptions.renew(route, sid, timeout) {
+                return (subscribed(sid, timeout), None);
+            }
+            StatusCode::PRECONDITION_FAILED
+        }
+        ("SUBSCRIBE", None) => {
+            let callbacks = header("CALLBACK").and_then(parse_callbacks);
+            if let Some(callbacks) = callbacks
+                && header("NT") == Some("upnp:event")
+            {
+                let timeout = parse_timeout(header("TIMEOUT"));
+                let subscription = subscriptions.subscribe_new(route, callbacks, timeout);
+                return (subscribed(&subscription.sid, timeout), Some(subscription));
+            }
+            StatusCode::PRECONDITION_FAILED
+        }
+        ("UNSUBSCRIBE", sid) => {
+            if sid.is_some_and(|sid| subscriptions.unsubscribe(route, sid)) {
+                StatusCode::OK
+            } else {
+                StatusCode::PRECONDITION_FAILED
+            }
+        }
+        _ => StatusCode::METHOD_NOT_ALLOWED,
+    };
+    (status.into_response(), None)
+}
+
+/// Delivers an event to a subscription, trying its callbacks in order. If none accepts it after a few attempts, the subscription is dropped. Returns whether the event was delivered.
+///
+/// Events of the same subscription are delivered one at a time, in the order they're numbered, so that a later event never overtakes one that is still being retried.
+pub async fn notify(subscriptions: &Subscriptions, sid: &str, body: &str) -> bool {
+    let Some(delivery) = subscriptions.delivery(sid) else {
+        return false;
+    };
+    let _delivery = delivery.lock().await;
+    deliver(subscriptions, sid, body).await
+}
+
+/// Delivers an event like [`notify`], with the delivery lock of the subscription held.
+async fn deliver(subscriptions: &Subscriptions, sid: &str, body: &str) -> bool {
+    let Some((callbacks, seq)) = subscriptions.next_event(sid) else {
+        return false;
+    };
+    let seq = seq.to_string();
+    let headers = [
+        ("Content-Type", XML_CONTENT_TYPE),
+        ("NT", "upnp:event"),
+        ("NTS", "upnp:propchange"),
+        ("SID", sid),
+        ("SEQ", &seq),
+    ];
+    let notify = Method::from_bytes(b"NOTIFY").expect("NOTIFY is a valid method");
+    for attempt in 1..=NOTIFY_ATTEMPTS {
+        for callback in &callbacks {
+            match client::request(notify.clone(), callback, &headers, body.to_string()).await {
+                Ok(response) if response.status().is_success() => return true,
+                Ok(response) => debug!(
+                    "Event {seq} of {sid} rejected by {callback}: {}",
+                    response.status()
+                ),
+                Err(e) => debug!("Failed to deliver event {seq} of {sid} to {callback}: {e}"),
+            }
+        }
+        if attempt < NOTIFY_ATTEMPTS {
+            sleep(NOTIFY_RETRY_DELAY).await;
+        }
+    }
+    warn!("Dropping subscription {sid}, as its callbacks {callbacks:?} don't accept events");
+    subscriptions.lock().remove(sid);
+    false
+}
+
+/// Delivers an event to all subscriptions to a service concurrently, see [`notify`]. Returns how many subscriptions it was delivered to.
+pub async fn publish(subscriptions: &Arc<Subscriptions>, route: Route, body: &str) -> usize {
+    let mut deliveries = JoinSet::new();
+    for (sid, _) in subscriptions.of(route) {
+        let subscriptions = Arc::clone(subscriptions);
+        let body = body.to_string();
+        deliveries.spawn(async move { notify(&subscriptions, &sid, &body).await });
+    }
+    deliveries
+        .join_all()
+        .await
+        .into_iter()
+        .filter(|delivered| *delivered)
+        .count()
+}
+
+/// Answers a successful `SUBSCRIBE` with the `SID` and the actual `TIMEOUT` of the subscription.
+fn subscribed(sid: &str, timeout: Duration) -> Response {
+    (
+        StatusCode::OK,
+        [
+            ("SID", sid.to_string()),
+            ("TIMEOUT", format!("Second-{}", timeout.as_secs())),
+        ],
+    )
+        .into_response()
+}
+
+/// Parses the `CALLBACK` header, i.e. one or more `http` URLs each enclosed in angle brackets.
+fn parse_callbacks(header: &str) -> Option<Vec<String>> {
+    let callbacks = header
+        .split('>')
+        .map(str::trim)
+        .filter(|callback| !callback.is_empty())
+        .map(|callback| {
+            callback
+                .strip_prefix('<')
+                .filter(|url| url.starts_with("http://"))
+                .map(ToString::to_string)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (!callbacks.is_empty()).then_some(callbacks)
+}
+
+/// Parses the `TIMEOUT` header, e.g. `Second-1800`, falling back to [`DEFAULT_TIMEOUT`] if it's missing, `infinite` or invalid.
+fn parse_timeout(header: Option<&str>) -> Duration {
+    header
+        .and_then(|timeout| {
+            let (prefix, seconds) = timeout.trim().split_at_checked(7)?;
+            prefix.eq_ignore_ascii_case("Second-").then_some(seconds)
+        })
+        .and_then(|seconds| seconds.parse().ok())
+        .filter(|seconds| *seconds > 0)
+        .map_or(DEFAULT_TIMEOUT, Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, routing::any};
+    use tokio::{
+        net::TcpListener,
+        sync::mpsc::{UnboundedReceiver, unbounded_channel},
+    };
+
+    #[test]
+    fn test_parse_callbacks() {
+        assert_eq!(
+            parse_callbacks("<http://192.168.1.2:4004/events>"),
+            Some(vec!["http://192.168.1.2:4004/events".to_string()])
+        );
+        assert_eq!(
+            parse_callbacks("<http://192.168.1.2/a> <http://192.168.1.3/b>"),
+            Some(vec![
+                "http://192.168.1.2/a".to_string(),
+                "http://192.168.1.3/b".to_string()
+            ])
+        );
+        assert_eq!(parse_callbacks("http://192.168.1.2/a"), None);
+        assert_eq!(parse_callbacks("<https://192.168.1.2/a>"), None);
+        assert_eq!(parse_callbacks(""), None);
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(parse_timeout(Some("Second-300")), Duration::from_mins(5));
+        assert_eq!(parse_timeout(Some("second-60")), Duration::from_mins(1));
+        assert_eq!(parse_timeout(Some("infinite")), DEFAULT_TIMEOUT);
+        assert_eq!(parse_timeout(Some("Second-0")), DEFAULT_TIMEOUT);
+        assert_eq!(parse_timeout(Some("Second-")), DEFAULT_TIMEOUT);
+        assert_eq!(parse_timeout(None), DEFAULT_TIMEOUT);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_lifecycle() {
+        let subscriptions = Subscriptions::default();
+        let callbacks = vec!["http://192.168.1.2/events".to_string()];
+        let sid = subscriptions.subscribe(
+            Route::AVTransport,
+            callbacks.clone(),
+            Duration::from_mins(1),
+        );
+        assert!(sid.starts_with("uuid:"));
+        assert_eq!(
+            subscriptions
+                .get(&sid)
+                .map(|subscription| subscription.callbacks),
+            Some(callbacks)
+        );
+        assert_eq!(subscriptions.of(Route::AVTransport).len(), 1);
+        assert!(subscriptions.of(Route::RenderingControl).is_empty());
+
+        // Renewing extends the subscription from now on
+        tokio::time::advance(Duration::from_secs(50)).await;
+        assert!(!subscriptions.renew(Route::RenderingControl, &sid, Duration::from_mins(1)));
+        assert!(subscriptions.renew(Route::AVTransport, &sid, Duration::from_mins(1)));
+        tokio::time::advance(Duration::from_secs(50)).await;
+        assert!(subscriptions.get(&sid).is_some());
+
+        // Expired subscriptions can't be renewed
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(subscriptions.get(&sid), None);
+        assert!(!subscriptions.renew(Route::AVTransport, &sid, Duration::from_mins(1)));
+
+        let sid = subscriptions.subscribe(Route::AVTransport, Vec::new(), DEFAULT_TIMEOUT);
+        assert!(!subscriptions.unsubscribe(Route::RenderingControl, &sid));
+        assert!(subscriptions.unsubscribe(Route::AVTransport, &sid));
+        assert!(!subscriptions.unsubscribe(Route::AVTransport, &sid));
+    }
+
+    #[test]
+    fn test_respond() {
+        let subscriptions = Subscriptions::default();
+        let subscribe = Method::from_bytes(b"SUBSCRIBE").unwrap();
+        let request = |headers: &[(&'static str, &str)]| {
+            headers
+                .iter()
+                .map(|(name, value)| (name.parse().unwrap(), value.parse().unwrap()))
+                .collect::<HeaderMap>()
+        };
+
+        let (response, new_sid) = respond(
+            &subscriptions,
+            Route::RenderingControl,
+            &subscribe,
+            &request(&[
+                ("CALLBACK", "<http://192.168.1.2/events>"),
+                ("NT", "upnp:event"),
+                ("TIMEOUT", "Second-300"),
+            ]),
+        );
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["TIMEOUT"], "Second-300");
+        let sid = response.headers()["SID"].to_str().unwrap();
+        assert_eq!(
+            new_sid.map(|subscription| subscription.sid).as_deref(),
+            Some(sid)
+        );
+        assert!(subscriptions.get(sid).is_some());
+
+        for (headers, status) in [
+            (vec![("NT", "upnp:event")], StatusCode::PRECONDITION_FAILED),
+            (
+                vec![
+                    ("CALLBACK", "<http://192.168.1.2/events>"),
+                    ("NT", "ssdp:all"),
+                ],
+                StatusCode::PRECONDITION_FAILED,
+            ),
+            (
+                vec![("SID", sid), ("NT", "upnp:event")],
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                vec![("SID", "uuid:unknown")],
+                StatusCode::PRECONDITION_FAILED,
+            ),
+        ] {
+            let (response, new_sid) = respond(
+                &subscriptions,
+                Route::RenderingControl,
+                &subscribe,
+                &request(&headers),
+            );
+            assert_eq!(response.status(), status, "{headers:?}");
+            assert!(new_sid.is_none(), "{headers:?}");
+        }
+
+        let (response, _) = respond(
+            &subscriptions,
+            Route::RenderingControl,
+            &Method::GET,
+            &request(&[("SID", sid)]),
+        );
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[test]
+    fn test_seq_wraps() {
+        let subscriptions = Subscriptions::default();
+        let sid = subscriptions.subscribe(Route::AVTransport, Vec::new(), DEFAULT_TIMEOUT);
+        assert_eq!(subscriptions.next_event(&sid), Some((Vec::new(), 0)));
+        assert_eq!(subscriptions.next_event(&sid), Some((Vec::new(), 1)));
+        if let Some(entry) = subscriptions.lock().get_mut(&sid) {
+            entry.subscription.seq = u32::MAX;
+        }
+        assert_eq!(subscriptions.next_event(&sid), Some((Vec::new(), u32::MAX)));
+        assert_eq!(subscriptions.next_event(&sid), Some((Vec::new(), 1)));
+    }
+
+    /// Serves a callback URL, forwarding the headers and body of the requests it receives.
+    async fn callback_server() -> (String, UnboundedReceiver<(HeaderMap, String)>) {
+        let (sender, receiver) = unbounded_channel();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+        let router = Router::new().route(
+            "/events",
+            any(async move |headers: HeaderMap, body: String| {
+                sender.send((headers, body)).unwrap();
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        (url, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_publish() {
+        let subscriptions = Arc::new(Subscriptions::default());
+        let (url, mut received) = callback_server().await;
+        let sid = subscriptions.subscribe(Route::AVTransport, vec![url], DEFAULT_TIMEOUT);
+        subscriptions.subscribe(Route::RenderingControl, Vec::new(), DEFAULT_TIMEOUT);
+
+        for (seq, body) in ["0", "1"].iter().zip(["initial", "change"]) {
+            assert_eq!(publish(&subscriptions, Route::AVTransport, body).await, 1);
+            let (headers, received_body) = received.recv().await.unwrap();
+            assert_eq!(received_body, body);
+            assert_eq!(headers["SEQ"], *seq);
+            assert_eq!(headers["SID"], sid.as_str());
+            assert_eq!(headers["NT"], "upnp:event");
+            assert_eq!(headers["NTS"], "upnp:propchange");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_initial_event_first() {
+        let subscriptions = Arc::new(Subscriptions::default());
+        let (url, mut received) = callback_server().await;
+        let initial = subscriptions
+            .subscribe_new(Route::AVTransport, vec![url], DEFAULT_TIMEOUT)
+            .notify(&subscriptions, "initial");
+        // Published before the initial event is sent, e.g. while the response is written
+        let change = tokio::spawn({
+            let subscriptions = Arc::clone(&subscriptions);
+            async move { publish(&subscriptions, Route::AVTransport, "change").await }
+        });
+        sleep(Duration::from_millis(50)).await;
+        assert!(!change.is_finished());
+        assert!(initial.await);
+        assert_eq!(change.await.unwrap(), 1);
+
+        for (seq, body) in [("0", "initial"), ("1", "change")] {
+            let (headers, received_body) = received.recv().await.unwrap();
+            assert_eq!(
+                (headers["SEQ"].to_str().unwrap(), received_body.as_str()),
+                (seq, body)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ordered_delivery() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        // A callback rejecting the first event it receives, which is retried
+        let (sender, mut received) = unbounded_channel();
+        let rejected = Arc::new(AtomicBool::new(false));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+        let router = Router::new().route(
+            "/events",
+            any(async move |headers: HeaderMap, body: String| {
+                if rejected.swap(true, Ordering::Relaxed) {
+                    sender.send((headers, body)).unwrap();
+                    StatusCode::OK
+                } else {
+                    StatusCode::SERVICE_UNAVAILABLE
+                }
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let subscriptions = Arc::new(Subscriptions::default());
+        subscriptions.subscribe(Route::AVTransport, vec![url], DEFAULT_TIMEOUT);
+        let first = tokio::spawn({
+            let subscriptions = Arc::clone(&subscriptions);
+            async move { publish(&subscriptions, Route::AVTransport, "first").await }
+        });
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(
+            publish(&subscriptions, Route::AVTransport, "second").await,
+            1
+        );
+        assert_eq!(first.await.unwrap(), 1);
+
+        for (seq, body) in [("0", "first"), ("1", "second")] {
+            let (headers, received_body) = received.recv().await.unwrap();
+            assert_eq!(
+                (headers["SEQ"].to_str().unwrap(), received_body.as_str()),
+                (seq, body)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery() {
+        let subscriptions = Subscriptions::default();
+        // Nothing listens on the port once the listener is dropped
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+        drop(listener);
+        let sid = subscriptions.subscribe(Route::AVTransport, vec![url], DEFAULT_TIMEOUT);
+
+        assert!(!notify(&subscriptions, &sid, "change").await);
+        assert_eq!(subscriptions.get(&sid), None);
+    }
+}
@@ -0,0 +1,269 @@
+//! Forwarding of control commands to follower renderers, for multi-room setups.
+
+use super::{client, response::XML_CONTENT_TYPE, xml::soap::SoapAction};
+use axum::http::{Method, StatusCode};
+use log::{debug, warn};
+use std::{
+    io::{Error, ErrorKind, Result},
+    time::Duration,
+};
+use tokio::time::timeout;
+
+/// Timeout for forwarding a command to a single follower.
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A client mirroring control commands to follower renderers, by posting the same SOAP envelopes to their control URLs.
+///
+/// Return it from [`HTTPServer::followers`](crate::HTTPServer::followers) to forward every command that has been handled successfully by the leader. Failing followers are logged and never affect the leader.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::{FollowerClient, HTTPServer};
+/// use std::sync::LazyLock;
+///
+/// static FOLLOWERS: LazyLock<FollowerClient> = LazyLock::new(|| {
+///     FollowerClient::new()
+///         .av_transport("http://192.168.1.5:8080/AVTransport")
+///         .rendering_control("http://192.168.1.5:8080/RenderingControl")
+/// });
+///
+/// struct Leader;
+///
+/// impl HTTPServer for Leader {
+///     fn followers(&self) -> Option<&FollowerClient> {
+///         Some(&FOLLOWERS)
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FollowerClient {
+    av_transport: Vec<String>,
+    rendering_control: Vec<String>,
+}
+
+impl FollowerClient {
+    /// Creates a client without followers.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            av_transport: Vec::new(),
+            rendering_control: Vec::new(),
+        }
+    }
+
+    /// Adds the `AVTransport` control URL of a follower.
+    #[must_use]
+    pub fn av_transport(mut self, control_url: impl Into<String>) -> Self {
+        self.av_transport.push(control_url.into());
+        self
+    }
+
+    /// Adds the `RenderingControl` control URL of a follower.
+    #[must_use]
+    pub fn rendering_control(mut self, control_url: impl Into<String>) -> Self {
+        self.rendering_control.push(control_url.into());
+        self
+    }
+
+    /// The control URLs of followers for given service type, e.g. [`av_transport::SERVICE_TYPE`](crate::xml::av_transport::SERVICE_TYPE). Unknown service types have no followers.
+    #[must_use]
+    pub fn control_urls(&self, service_type: &str) -> &[String] {
+        match service_type {
+            crate::xml::av_transport::SERVICE_TYPE => &self.av_transport,
+            crate::xml::rendering_control::SERVICE_TYPE => &self.rendering_control,
+            _ => &[],
+        }
+    }
+
+    /// Posts the SOAP `envelope` invoking `action` of `service_type` to all followers concurrently, returning the result for each of [`control_urls`](FollowerClient::control_urls) in order. Failures are also logged as warnings.
+    pub async fn forward(
+        &self,
+        service_type: &str,
+        action: &str,
+        envelope: &str,
+    ) -> Vec<Result<()>> {
+        let soap_action = SoapAction {
+            service_type: service_type.to_string(),
+            action: action.to_string(),
+        }
+        .to_string();
+        let tasks: Vec<_> = self
+            .control_urls(service_type)
+            .iter()
+            .map(|url| {
+                let (url, soap_action, envelope) =
+                    (url.clone(), soap_action.clone(), envelope.to_string());
+                tokio::spawn(async move {
+                    let result = post(&url, &soap_action, envelope).await;
+                    match &result {
+                        Ok(()) => debug!("Forwarded {soap_action} to follower {url}"),
+                        Err(e) => warn!("Failed to forward {soap_action} to follower {url}: {e}"),
+                    }
+                    result
+                })
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            results.push(task.await.unwrap_or_else(|e| Err(Error::other(e))));
+        }
+        results
+    }
+}
+
+/// Posts a SOAP envelope to a single follower.
+async fn post(url: &str, soap_action: &str, envelope: String) -> Result<()> {
+    let headers = [
+        ("Content-Type", XML_CONTENT_TYPE),
+        (SoapAction::HEADER, soap_action),
+    ];
+    let request = client::request(Method::POST, url, &headers, envelope);
+    let response = timeout(FORWARD_TIMEOUT, request)
+        .await
+        .map_err(|_| Error::new(ErrorKind::TimedOut, "Follower timed out"))??;
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(Error::other(format!("Follower answered with {status}")))
+    }
+}
+
+/// Forwards a command in the background if it has been handled successfully, so that the leader never waits for its followers.
+pub fn forward_in_background(
+    followers: Option<&'static FollowerClient>,
+    service_type: &'static str,
+    action: Option<&'static str>,
+    envelope: String,
+    status: StatusCode,
+) {
+    if let (Some(followers), Some(action)) = (followers, action)
+        && status.is_success()
+    {
+        tokio::spawn(async move {
+            followers.forward(service_type, action, &envelope).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ControlContext, DMROptions, HTTPServer, ParseError,
+        response::{action_response, soap_response},
+        xml::{AVTransport, av_transport},
+    };
+    use axum::{Router, http::HeaderMap, response::IntoResponse, routing::post};
+    use std::{fs::read_to_string, net::SocketAddr, sync::Arc};
+    use tokio::{net::TcpListener, sync::mpsc};
+
+    /// A leader that accepts `Play`, forwarding commands to given followers.
+    struct Leader(FollowerClient);
+
+    impl HTTPServer for Leader {
+        async fn post_av_transport(
+            &self,
+            av_transport: std::result::Result<AVTransport, ParseError>,
+            _context: &ControlContext,
+        ) -> impl IntoResponse {
+            match av_transport {
+                Ok(AVTransport::Play(_)) => {
+                    soap_response(action_response(av_transport::SERVICE_TYPE, "Play", &[]))
+                }
+                _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+            }
+        }
+
+        fn followers(&self) -> Option<&FollowerClient> {
+            Some(&self.0)
+        }
+    }
+
+    /// Spawns a mock follower, sending the `SOAPACTION` header and body of each request through the returned channel.
+    async fn mock_follower() -> (SocketAddr, mpsc::UnboundedReceiver<(String, String)>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let router = Router::new().route(
+            "/AVTransport",
+            post(move |headers: HeaderMap, body: String| async move {
+                let soap_action = headers
+                    .get("SOAPACTION")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string();
+                sender.send((soap_action, body)).ok();
+                StatusCode::OK
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        (address, receiver)
+    }
+
+    /// An address nobody listens on.
+    async fn closed_address() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_play_forwarded() {
+        let (follower, mut received) = mock_follower().await;
+        let dead = closed_address().await;
+        let leader: &'static Leader = Box::leak(Box::new(Leader(
+            FollowerClient::new()
+                .av_transport(format!("http://{dead}/AVTransport"))
+                .av_transport(format!("http://{follower}/AVTransport")),
+        )));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let router = leader.router(Arc::new(DMROptions::default()));
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let envelope =
+            read_to_string("tests/AVTransport/Play.xml").expect("Failed to read XML file");
+        let response = client::request(
+            Method::POST,
+            &format!("http://{address}/AVTransport"),
+            &[("Content-Type", XML_CONTENT_TYPE)],
+            envelope.clone(),
+        )
+        .await
+        .expect("Leader request failed");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let (soap_action, body) = timeout(Duration::from_secs(5), received.recv())
+            .await
+            .expect("Follower never received the command")
+            .expect("Follower channel closed");
+        assert_eq!(
+            soap_action,
+            r#""urn:schemas-upnp-org:service:AVTransport:1#Play""#
+        );
+        assert_eq!(body, envelope);
+    }
+
+    #[tokio::test]
+    async fn test_forward_failure() {
+        let dead = closed_address().await;
+        let (follower, _received) = mock_follower().await;
+        let client = FollowerClient::new()
+            .av_transport(format!("http://{dead}/AVTransport"))
+            .av_transport(format!("http://{follower}/AVTransport"));
+        let results = client
+            .forward(av_transport::SERVICE_TYPE, "Stop", "<s:Envelope/>")
+            .await;
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_err());
+        assert!(results[1].is_ok());
+        assert!(
+            client
+                .forward(crate::xml::rendering_control::SERVICE_TYPE, "SetVolume", "")
+                .await
+                .is_empty()
+        );
+    }
+}
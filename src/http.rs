@@ -1,13 +1,425 @@
 //! HTTP-related code.
 
 use super::{
-    DMROptions,
-    xml::{av_transport::AVTransport, rendering_control::RenderingControl},
+    ControlAction, ControlEvent, DMROptions, DmrError, FollowerClient, ParseError, UpnpError,
+    cache::TtlCache,
+    eventing::{self, Subscriptions},
+    follower::forward_in_background,
+    response::{ActionResponse, action_response_with_prefix, soap_fault, soap_response, xml_ok},
+    runtime,
+    state::{
+        Applied, InstanceId, InstanceState, RendererState, TransportAction, TransportState,
+        format_time,
+    },
+    template::{self, DescriptionCache},
+    uri::{check_reachable, validate_scheme},
+    xml::{
+        av_transport::{
+            self, AVTransport, GetCurrentTransportActionsResponse, GetMediaInfoResponse,
+            GetPositionInfoResponse, GetTransportInfoResponse, PlayMode, PlaySpeed, Seek,
+            SetAVTransportURI, SetNextAVTransportURI, SetPlayMode,
+        },
+        connection_manager::{self, ConnectionManager, DEFAULT_CONNECTION_ID},
+        device_description::DeviceDescription,
+        last_change::{EventNamespace, LastChange, property_set},
+        rendering_control::{self, Channel, RenderingControl, scale_volume},
+        soap::{self, DEFAULT_PREFIX, SoapAction},
+    },
 };
-use axum::{Router, http::StatusCode, response::IntoResponse, routing::get};
-use log::info;
-use quick_xml::{DeError, escape::escape};
-use std::{io::Result as IoResult, net::SocketAddrV4, str::FromStr, sync::Arc};
+use axum::{
+    Extension, Router,
+    body::Body,
+    extract::ConnectInfo,
+    http::{
+        Extensions, HeaderMap, HeaderValue, Method, StatusCode, Uri,
+        header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH},
+    },
+    response::IntoResponse,
+    routing::{any, get},
+};
+use http_body_util::BodyExt;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::{conn::auto, graceful::GracefulShutdown},
+    service::TowerToHyperService,
+};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    collections::BTreeSet,
+    future::pending,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{Error, ErrorKind, Result as IoResult},
+    net::SocketAddr,
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    net::TcpListener,
+    sync::{broadcast, oneshot},
+    time::sleep,
+};
+
+/// Where the device description is served along with [`Route::DeviceSpec`], as many controllers expect the `LOCATION` to end in `.xml`.
+pub const DESCRIPTION_XML_PATH: &str = "/description.xml";
+
+/// A default route of the HTTP server, see [`DMROptions::enabled_routes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Route {
+    /// `/DeviceSpec`, serving the device description, also at [`DESCRIPTION_XML_PATH`] and [`DMROptions::description_path`] if changed.
+    DeviceSpec,
+    /// `/RenderingControl`, serving the SCPD and accepting control actions, with event subscriptions at `/RenderingControl/event`.
+    RenderingControl,
+    /// `/AVTransport`, serving the SCPD and accepting control actions, with event subscriptions at `/AVTransport/event`.
+    AVTransport,
+    /// `/ConnectionManager`, serving the SCPD and accepting control actions, with event subscriptions at `/ConnectionManager/event`.
+    ConnectionManager,
+    /// `/Ignore`, which stood in for `ConnectionManager` before it was implemented, kept for custom device description templates still pointing there.
+    Ignore,
+}
+
+impl Route {
+    /// All default routes.
+    pub const ALL: [Self; 5] = [
+        Self::DeviceSpec,
+        Self::RenderingControl,
+        Self::AVTransport,
+        Self::ConnectionManager,
+        Self::Ignore,
+    ];
+
+    /// The path of the route, e.g. `/AVTransport`.
+    #[must_use]
+    pub const fn path(self) -> &'static str {
+        match self {
+            Self::DeviceSpec => "/DeviceSpec",
+            Self::RenderingControl => "/RenderingControl",
+            Self::AVTransport => "/AVTransport",
+            Self::ConnectionManager => "/ConnectionManager",
+            Self::Ignore => "/Ignore",
+        }
+    }
+
+    /// The path controllers `SUBSCRIBE` to for the events of the service, e.g. `/AVTransport/event`, if the route is a service.
+    #[must_use]
+    pub fn event_path(self) -> Option<String> {
+        matches!(
+            self,
+            Self::RenderingControl | Self::AVTransport | Self::ConnectionManager
+        )
+        .then(|| format!("{}/event", self.path()))
+    }
+
+    /// Whether the route is mounted according to [`DMROptions::enabled_routes`], warning if it's disabled but still advertised.
+    fn is_enabled(self, options: &DMROptions) -> bool {
+        let enabled = options.enabled_routes.contains(&self);
+        if !enabled && self.is_advertised(options) {
+            warn!(
+                "Route {} is disabled, but still advertised in the device description",
+                self.path()
+            );
+        }
+        enabled
+    }
+
+    /// Whether controllers are told about the route, either as the SSDP `LOCATION` or by the device description.
+    fn is_advertised(self, options: &DMROptions) -> bool {
+        self == Self::DeviceSpec
+            || DeviceDescription::from(options)
+                .device
+                .service_list
+                .services
+                .iter()
+                .any(|service| {
+                    service.control_url.ends_with(self.path())
+                        || service.scpd_url.ends_with(self.path())
+                })
+    }
+}
+
+/// How POST requests to paths without a route are answered, see [`DMROptions::unknown_post`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnknownPost {
+    /// `404 Not Found`, with an empty body.
+    #[default]
+    NotFound,
+    /// A SOAP fault with [`UpnpError::invalid_action`], for controllers that only understand faults.
+    Fault,
+}
+
+/// Context of a request, passed to the POST handlers of `/RenderingControl` and `/AVTransport`, and to the GET handler of `/DeviceSpec`.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ControlContext {
+    /// Options of the DMR instance.
+    pub options: Arc<DMROptions>,
+    /// The device description, rendered once and shared between clones.
+    description: Arc<DescriptionCache>,
+    /// The renderer state, shared between clones.
+    state: Arc<Mutex<RendererState>>,
+    /// The namespace prefix of the request's SOAP envelope.
+    soap_prefix: String,
+    /// The `SOAPACTION` header of the request, if any.
+    soap_action: Option<SoapAction>,
+    /// The address of the controller that sent the request, if known.
+    peer: Option<SocketAddr>,
+    /// Recently answered volumes, per instance and channel, shared between clones.
+    volume_cache: Arc<Mutex<TtlCache<(u32, Channel), u16>>>,
+    /// Recently answered mute states, per instance and channel, shared between clones.
+    mute_cache: Arc<Mutex<TtlCache<(u32, Channel), bool>>>,
+    /// Publishes the actions received, shared between clones.
+    events: broadcast::Sender<ControlEvent>,
+    /// GENA subscriptions of controllers, shared between clones.
+    subscriptions: Arc<Subscriptions>,
+}
+
+impl ControlContext {
+    /// How many events [`events`](ControlContext::events) subscribers may fall behind before missing some.
+    pub const EVENT_CAPACITY: usize = 64;
+
+    /// Creates a context with given options, e.g. for testing your handlers. The device description is rendered right away.
+    #[must_use]
+    pub fn new(options: Arc<DMROptions>) -> Self {
+        let description = Arc::new(DescriptionCache::new(&options));
+        let ttl = Duration::from_millis(options.volume_cache_ms);
+        Self {
+            options,
+            description,
+            state: Arc::default(),
+            soap_prefix: DEFAULT_PREFIX.to_string(),
+            soap_action: None,
+            peer: None,
+            volume_cache: Arc::new(Mutex::new(TtlCache::new(ttl))),
+            mute_cache: Arc::new(Mutex::new(TtlCache::new(ttl))),
+            events: broadcast::channel(Self::EVENT_CAPACITY).0,
+            subscriptions: Arc::default(),
+        }
+    }
+
+    /// A clone of the context for a request with given extensions, headers and body, detecting the namespace prefix of its SOAP envelope.
+    fn for_request(&self, extensions: &Extensions, headers: &HeaderMap, body: &str) -> Self {
+        Self {
+            soap_prefix: soap::prefix(body),
+            soap_action: soap_action(headers),
+            peer: peer(extensions),
+            ..self.clone()
+        }
+    }
+
+    /// The namespace prefix of the request's SOAP envelope, e.g. `s` or `SOAP-ENV`, defaulting to `s`.
+    #[must_use]
+    pub fn soap_prefix(&self) -> &str {
+        &self.soap_prefix
+    }
+
+    /// The address of the controller that sent the request, e.g. to only accept commands from the one that set the current URI. It's `None` if the router is served without connection info, as with a context created by [`new`](ControlContext::new).
+    #[must_use]
+    pub const fn peer(&self) -> Option<SocketAddr> {
+        self.peer
+    }
+
+    /// The `SOAPACTION` header of the request, naming the action invoked, or `None` if it's absent or malformed. The default routes dispatch on it, see [`soap::parse_action`].
+    #[must_use]
+    pub const fn soap_action(&self) -> Option<&SoapAction> {
+        self.soap_action.as_ref()
+    }
+
+    /// Subscribes to the actions received from now on, published once parsed and before they're handled. Actions that fail to parse aren't published.
+    ///
+    /// The channel holds up to [`EVENT_CAPACITY`](ControlContext::EVENT_CAPACITY) events: a subscriber that falls further behind misses the oldest ones, and its next `recv` yields [`RecvError::Lagged`](broadcast::error::RecvError::Lagged) with the number of events skipped. Handlers never wait for subscribers.
+    #[must_use]
+    pub fn events(&self) -> broadcast::Receiver<ControlEvent> {
+        self.events.subscribe()
+    }
+
+    /// The GENA subscriptions of controllers to the events of the services, see [`Route::event_path`].
+    #[must_use]
+    pub fn subscriptions(&self) -> &Subscriptions {
+        &self.subscriptions
+    }
+
+    /// Sends a `LastChange` event to the controllers subscribed to its service, returning how many it was delivered to. Subscriptions whose callbacks keep failing are dropped.
+    pub async fn publish_event(&self, event: &LastChange) -> usize {
+        let route = match event.namespace() {
+            EventNamespace::AVTransport => Route::AVTransport,
+            EventNamespace::RenderingControl => Route::RenderingControl,
+        };
+        eventing::publish(&self.subscriptions, route, &event.property_set()).await
+    }
+
+    /// Parses an action, one of `actions`, from a request, publishing it to the subscribers of [`events`](ControlContext::events) if successful. The action named by the `SOAPACTION` header is looked for in the SOAP body (see [`soap::parse_action`]), and the whole envelope is parsed only if the header is absent.
+    fn parse_action<A: FromStr<Err = ParseError> + DeserializeOwned + Clone>(
+        &self,
+        headers: &HeaderMap,
+        body: &str,
+        extensions: &Extensions,
+        actions: &[&str],
+        wrap: fn(A) -> ControlAction,
+    ) -> Result<A, ParseError> {
+        let action = match soap_action(headers) {
+            Some(soap_action) => soap::parse_action(&soap_action.action, actions, body)?,
+            None => A::from_str(body)?,
+        };
+        // Failing only if there are no subscribers
+        let _ = self.events.send(ControlEvent {
+            action: wrap(action.clone()),
+            peer: peer(extensions),
+            received_at: SystemTime::now(),
+        });
+        Ok(action)
+    }
+
+    /// Forgets the cached volumes and mute states, e.g. after changing them other than via `SetVolume` or `SetMute`, so that controllers see the change right away.
+    pub fn invalidate_volume_cache(&self) {
+        lock(&self.volume_cache).clear();
+        lock(&self.mute_cache).clear();
+    }
+
+    /// Reports that given instance started playing other than via `Play`, e.g. after a physical button was pressed, so that subsequent `GetTransportInfo` reflect it. The new `TransportState` is published to subscribed controllers, returning how many it was delivered to, see [`publish_event`](ControlContext::publish_event).
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`UpnpError::invalid_instance_id`] if the instance doesn't exist.
+    pub async fn notify_playing(&self, instance_id: InstanceId) -> Result<usize, UpnpError> {
+        self.update_instance(instance_id, |instance| {
+            instance.transport_state = TransportState::Playing;
+            instance.clock.start();
+        })?;
+        Ok(self
+            .publish_transport_state(instance_id, TransportState::Playing)
+            .await)
+    }
+
+    /// Reports that given instance paused other than via `Pause`, see [`notify_playing`](ControlContext::notify_playing).
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`UpnpError::invalid_instance_id`] if the instance doesn't exist.
+    pub async fn notify_paused(&self, instance_id: InstanceId) -> Result<usize, UpnpError> {
+        self.update_instance(instance_id, |instance| {
+            instance.transport_state = TransportState::PausedPlayback;
+            instance.clock.pause();
+        })?;
+        Ok(self
+            .publish_transport_state(instance_id, TransportState::PausedPlayback)
+            .await)
+    }
+
+    /// Reports that given instance stopped other than via `Stop`, e.g. at the end of the track, see [`notify_playing`](ControlContext::notify_playing).
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`UpnpError::invalid_instance_id`] if the instance doesn't exist.
+    pub async fn notify_stopped(&self, instance_id: InstanceId) -> Result<usize, UpnpError> {
+        self.update_instance(instance_id, |instance| {
+            instance.transport_state = TransportState::Stopped;
+            instance.clock.reset();
+        })?;
+        Ok(self
+            .publish_transport_state(instance_id, TransportState::Stopped)
+            .await)
+    }
+
+    /// Reports that the volume of given instance and channel changed other than via `SetVolume`, e.g. with a physical knob, in the range `0..=100` seen by controllers (clamped to it). The new `Volume` is published to subscribed controllers, returning how many it was delivered to.
+    ///
+    /// `GetVolume` answers the tracked volume unless [`HTTPServer::volume`] is overridden, in which case it must report the new volume as well. Cached volumes are forgotten so that controllers see the change right away either way.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`UpnpError::invalid_instance_id`] if the instance doesn't exist.
+    pub async fn notify_volume(
+        &self,
+        instance_id: InstanceId,
+        channel: Channel,
+        volume: u16,
+    ) -> Result<usize, UpnpError> {
+        let volume = volume.min(rendering_control::VOLUME_MAX);
+        self.update_instance(instance_id, |instance| {
+            instance.set_volume(channel.clone(), volume);
+        })?;
+        self.invalidate_volume_cache();
+        let event = LastChange::rendering_control().volume(instance_id, channel, volume);
+        Ok(self.publish_event(&event).await)
+    }
+
+    /// Publishes the `TransportState` of given instance, see [`notify_playing`](ControlContext::notify_playing).
+    async fn publish_transport_state(
+        &self,
+        instance_id: InstanceId,
+        state: TransportState,
+    ) -> usize {
+        let event = LastChange::av_transport().transport_state(instance_id, state);
+        self.publish_event(&event).await
+    }
+
+    /// Updates the state of given instance, if it exists.
+    fn update_instance(
+        &self,
+        instance_id: InstanceId,
+        update: impl FnOnce(&mut InstanceState),
+    ) -> Result<(), UpnpError> {
+        self.state()
+            .instance_mut(instance_id)
+            .map(update)
+            .ok_or_else(UpnpError::invalid_instance_id)
+    }
+
+    /// Builds the SOAP envelope answering `action` like [`action_response`](crate::response::action_response), reusing the namespace prefix of the request's envelope for strict controllers.
+    #[must_use]
+    pub fn action_response(
+        &self,
+        service_type: &str,
+        action: &str,
+        args: &[(&str, &str)],
+    ) -> String {
+        debug_assert!(
+            match service_type {
+                av_transport::SERVICE_TYPE => AVTransport::ACTIONS.contains(&action),
+                rendering_control::SERVICE_TYPE => RenderingControl::ACTIONS.contains(&action),
+                connection_manager::SERVICE_TYPE => ConnectionManager::ACTIONS.contains(&action),
+                _ => true,
+            },
+            "{action} is not an action of {service_type}"
+        );
+        let service_type = self.options.service_versions.versioned(service_type);
+        action_response_with_prefix(&self.soap_prefix, &service_type, action, args)
+    }
+
+    /// Builds the SOAP envelope of a typed response like [`action_response`](ControlContext::action_response).
+    #[must_use]
+    pub fn envelope<R: ActionResponse>(&self, response: &R) -> String {
+        let arguments = response.arguments();
+        let arguments: Vec<_> = arguments
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+        self.action_response(R::SERVICE_TYPE, R::ACTION, &arguments)
+    }
+
+    /// Locks the renderer state tracked from the actions received so far.
+    pub fn state(&self) -> MutexGuard<'_, RendererState> {
+        lock(&self.state)
+    }
+
+    /// The cached device description.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DmrError::Config`] if the description failed to render.
+    pub fn description(&self) -> Result<Arc<str>, DmrError> {
+        self.description.get()
+    }
+
+    /// Re-renders the cached device description with given options, e.g. after the friendly name changed in a reloaded config. The cached description is kept on error.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DmrError::Config`] if the description failed to render.
+    pub fn reload_description(&self, options: &DMROptions) -> Result<(), DmrError> {
+        self.description.reload(options)
+    }
+}
 
 /// A trait for handling HTTP requests for a DLNA DMR (Digital Media Renderer).
 ///
@@ -19,142 +431,797 @@ use std::{io::Result as IoResult, net::SocketAddrV4, str::FromStr, sync::Arc};
 ///     - [`get_device_spec`](HTTPServer::get_device_spec)
 ///     - [`get_rendering_control`](HTTPServer::get_rendering_control)
 ///     - [`get_av_transport`](HTTPServer::get_av_transport)
+///     - [`get_connection_manager`](HTTPServer::get_connection_manager)
 ///     - [`get_ignore`](HTTPServer::get_ignore)
 /// - POST
 ///     - [`post_device_spec`](HTTPServer::post_device_spec)
 ///     - [`post_rendering_control`](HTTPServer::post_rendering_control)
 ///     - [`post_av_transport`](HTTPServer::post_av_transport)
+///     - [`post_connection_manager`](HTTPServer::post_connection_manager)
 ///     - [`post_ignore`](HTTPServer::post_ignore)
 ///
+/// ## Hooks
+///
+/// The default [`post_av_transport`](HTTPServer::post_av_transport) answers some actions by itself, invoking these hooks so that you can react without reimplementing the handler.
+///
+/// - [`validate_uri`](HTTPServer::validate_uri)
+/// - [`on_uri_set`](HTTPServer::on_uri_set)
+/// - [`on_next_uri_set`](HTTPServer::on_next_uri_set)
+/// - [`on_play_mode_set`](HTTPServer::on_play_mode_set)
+/// - [`media_info`](HTTPServer::media_info)
+/// - [`transport_info`](HTTPServer::transport_info)
+/// - [`position_info`](HTTPServer::position_info)
+/// - [`current_transport_actions`](HTTPServer::current_transport_actions)
+///
+/// The default [`post_rendering_control`](HTTPServer::post_rendering_control) does the same with these hooks.
+///
+/// - [`volume`](HTTPServer::volume)
+/// - [`on_volume_set`](HTTPServer::on_volume_set)
+/// - [`mute`](HTTPServer::mute)
+/// - [`on_mute_set`](HTTPServer::on_mute_set)
+///
 /// ## Other Methods
 ///
 /// Usually you don't need to override these methods.
 ///
-/// - Override [`run_http`](HTTPServer::run_http) if you decide to change the HTTP server backend, or for a finer control over the server's behavior.
+/// - Override [`serve_http_with_shutdown`](HTTPServer::serve_http_with_shutdown) if you decide to change the HTTP server backend, or for a finer control over the server's behavior. [`DMR::run`](crate::DMR::run) binds the listener itself and calls this method, as do [`serve_http`](HTTPServer::serve_http) and [`run_http`](HTTPServer::run_http).
+/// - Override [`router`](HTTPServer::router) to add or replace routes.
+/// - Call [`run_http`](HTTPServer::run_http) to run the HTTP server alone, without SSDP, or [`run_http_with_shutdown`](HTTPServer::run_http_with_shutdown) to stop it programmatically.
+/// - Call [`publish_event`](HTTPServer::publish_event) to tell subscribed controllers about state changes.
 pub trait HTTPServer: Sync {
     /// Create and run a HTTP server with the given options.
-    fn run_http(&'static self, options: Arc<DMROptions>) -> impl Future<Output = IoResult<()>> + Send {async {
-        let ip = options.ip;
-        let http_port = options.http_port;
-        let listener = tokio::net::TcpListener::bind(SocketAddrV4::new(ip, http_port)).await?;
-        info!("HTTP server listening on {ip}:{http_port}");
-
-        let app = Router::new()
-            .route(
-                "/DeviceSpec",
-                get(async || Self::get_device_spec(options).await).post(Self::post_device_spec),
-            )
-            .route(
-                "/RenderingControl",
-                get(Self::get_rendering_control).post(async |s: String| {
-                    self.post_rendering_control(RenderingControl::from_str(&s))
-                        .await
-                }),
-            )
-            .route(
-                "/AVTransport",
-                get(Self::get_av_transport).post(async |s: String| {
-                    self.post_av_transport(AVTransport::from_str(&s)).await
-                }),
-            )
-            .route(
-                "/Ignore",
-                get(Self::get_ignore).post(async || self.post_ignore().await),
-            );
+    fn run_http(
+        &'static self,
+        options: Arc<DMROptions>,
+    ) -> impl Future<Output = IoResult<()>> + Send {
+        self.run_http_with_shutdown(options, pending())
+    }
+
+    /// Create and run a HTTP server with the given options, bound to [`DMROptions::http_bind_address`], until `shutdown` resolves. See [`serve_http_with_shutdown`](HTTPServer::serve_http_with_shutdown).
+    fn run_http_with_shutdown(
+        &'static self,
+        options: Arc<DMROptions>,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> impl Future<Output = IoResult<()>> + Send {
+        async {
+            let listener = TcpListener::bind(options.http_bind_address()).await?;
+            self.serve_http_with_shutdown(listener, options, shutdown)
+                .await
+        }
+    }
+
+    /// Serve HTTP requests on an already bound listener, with the given options, forever. See [`serve_http_with_shutdown`](HTTPServer::serve_http_with_shutdown).
+    fn serve_http(
+        &'static self,
+        listener: TcpListener,
+        options: Arc<DMROptions>,
+    ) -> impl Future<Output = IoResult<()>> + Send {
+        self.serve_http_with_shutdown(listener, options, pending())
+    }
+
+    /// Serve HTTP requests on an already bound listener, with the given options, until `shutdown` resolves. HTTP/2 over cleartext is accepted as well if [`DMROptions::http2`] is enabled. Requests with `Expect: 100-continue` get the interim response once their body is read, which the default routes do right away.
+    ///
+    /// Once `shutdown` resolves, no more connections are accepted, and this returns as soon as the requests in flight have been answered.
+    fn serve_http_with_shutdown(
+        &'static self,
+        listener: TcpListener,
+        options: Arc<DMROptions>,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> impl Future<Output = IoResult<()>> + Send {
+        async move {
+            info!("HTTP server listening on {}", listener.local_addr()?);
+            let router = self.router(options.clone());
+            let result = if options.http2 {
+                serve_h2c(listener, router, shutdown).await
+            } else {
+                axum::serve(
+                    listener,
+                    router.into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .with_graceful_shutdown(shutdown)
+                .await
+            };
+            info!("HTTP server stopped");
+            result
+        }
+    }
+
+    /// Build the router dispatching requests to the handlers, mounting only the [`DMROptions::enabled_routes`]. The context is shared with [`DMR::reload_description`](crate::DMR::reload_description) if this instance is running.
+    fn router(&'static self, options: Arc<DMROptions>) -> Router {
+        let context = runtime::get(self).map_or_else(
+            || Arc::new(ControlContext::new(options)),
+            |runtime| Arc::clone(&runtime.context),
+        );
+        let options = Arc::clone(&context.options);
+        let enabled = |route: Route| route.is_enabled(&options);
+        let max_age = options.description_max_age;
+        let mut router = Router::new();
+        if enabled(Route::DeviceSpec) {
+            let context = Arc::clone(&context);
+            let description = get(async move |headers: HeaderMap| {
+                let response = Self::get_device_spec(&context).await.into_response();
+                with_cache_headers(response, &headers, max_age).await
+            })
+            .post(Self::post_device_spec);
+            let paths = BTreeSet::from([
+                options.description_path.as_str(),
+                Route::DeviceSpec.path(),
+                DESCRIPTION_XML_PATH,
+            ]);
+            for path in paths {
+                router = router.route(path, description.clone());
+            }
+        }
+        let unknown_post = options.unknown_post;
+        let router = self.control_routes(router, &context);
+        let router = event_routes(self, router, &context);
+        self.connection_manager_routes(router, context).fallback(
+            async move |method: Method, uri: Uri, body: String| {
+                unknown_path(unknown_post, &method, &uri, &body)
+            },
+        )
         // TODO: Using state to pass `self`
+    }
 
-        axum::serve(listener, app).await
-    } }
+    /// Mount the enabled `RenderingControl` and `AVTransport` routes on the router, forwarding commands to the [`followers`](HTTPServer::followers).
+    fn control_routes(&'static self, mut router: Router, context: &Arc<ControlContext>) -> Router {
+        let enabled = |route: Route| route.is_enabled(&context.options);
+        let max_age = context.options.description_max_age;
+        if enabled(Route::RenderingControl) {
+            let context = Arc::clone(context);
+            router = router.route(
+                Route::RenderingControl.path(),
+                get(async move |headers: HeaderMap| {
+                    let response = Self::get_rendering_control().await.into_response();
+                    with_cache_headers(response, &headers, max_age).await
+                })
+                .post(
+                    move |extensions: Extensions, headers: HeaderMap, s: String| async move {
+                        let action = context.parse_action(
+                            &headers,
+                            &s,
+                            &extensions,
+                            RenderingControl::ACTIONS,
+                            ControlAction::RenderingControl,
+                        );
+                        let command = action
+                            .as_ref()
+                            .ok()
+                            .filter(|action| action.is_command())
+                            .map(RenderingControl::name);
+                        let response = self
+                            .post_rendering_control(
+                                action,
+                                &context.for_request(&extensions, &headers, &s),
+                            )
+                            .await
+                            .into_response();
+                        forward_in_background(
+                            self.followers(),
+                            rendering_control::SERVICE_TYPE,
+                            command,
+                            s,
+                            response.status(),
+                        );
+                        response
+                    },
+                ),
+            );
+        }
+        if enabled(Route::AVTransport) {
+            let context = Arc::clone(context);
+            router = router.route(
+                Route::AVTransport.path(),
+                get(async move |headers: HeaderMap| {
+                    let response = Self::get_av_transport().await.into_response();
+                    with_cache_headers(response, &headers, max_age).await
+                })
+                .post(
+                    move |extensions: Extensions, headers: HeaderMap, s: String| async move {
+                        let action = context.parse_action(
+                            &headers,
+                            &s,
+                            &extensions,
+                            AVTransport::ACTIONS,
+                            ControlAction::AVTransport,
+                        );
+                        let command = action
+                            .as_ref()
+                            .ok()
+                            .filter(|action| action.is_command())
+                            .map(AVTransport::name);
+                        let response = self
+                            .post_av_transport(
+                                action,
+                                &context.for_request(&extensions, &headers, &s),
+                            )
+                            .await
+                            .into_response();
+                        forward_in_background(
+                            self.followers(),
+                            av_transport::SERVICE_TYPE,
+                            command,
+                            s,
+                            response.status(),
+                        );
+                        response
+                    },
+                ),
+            );
+        }
+        router
+    }
+
+    /// Mount the enabled `ConnectionManager` routes, i.e. `/ConnectionManager` and the legacy `/Ignore`, on the router.
+    fn connection_manager_routes(
+        &'static self,
+        mut router: Router,
+        context: Arc<ControlContext>,
+    ) -> Router {
+        let enabled = |route: Route| route.is_enabled(&context.options);
+        let max_age = context.options.description_max_age;
+        if enabled(Route::ConnectionManager) {
+            let context = Arc::clone(&context);
+            router = router.route(
+                Route::ConnectionManager.path(),
+                get(async move |headers: HeaderMap| {
+                    let response = Self::get_connection_manager().await.into_response();
+                    with_cache_headers(response, &headers, max_age).await
+                })
+                .post(
+                    move |extensions: Extensions, headers: HeaderMap, s: String| async move {
+                        let action = context.parse_action(
+                            &headers,
+                            &s,
+                            &extensions,
+                            ConnectionManager::ACTIONS,
+                            ControlAction::ConnectionManager,
+                        );
+                        self.post_connection_manager(
+                            action,
+                            &context.for_request(&extensions, &headers, &s),
+                        )
+                        .await
+                        .into_response()
+                    },
+                ),
+            );
+        }
+        if enabled(Route::Ignore) {
+            router = router.route(
+                Route::Ignore.path(),
+                get(Self::get_ignore).post(
+                    move |extensions: Extensions, headers: HeaderMap, s: String| async move {
+                        let context = context.for_request(&extensions, &headers, &s);
+                        self.post_ignore(s, &context).await.into_response()
+                    },
+                ),
+            );
+        }
+        router
+    }
 
     // POST Request handlers for specific endpoints.
 
     /// Handles POST requests for `/DeviceSpec`.
+    #[must_use]
     fn post_device_spec() -> impl Future<Output = impl IntoResponse> + Send {
         async { StatusCode::METHOD_NOT_ALLOWED }
     }
 
     /// Handles POST requests for `/RenderingControl`.
+    ///
+    /// By default:
+    ///
+    /// - `GetVolume` is answered with [`volume`](HTTPServer::volume), scaled from the hardware range `0..=volume_max` (see [`DMROptions::volume_max`]) to the range `0..=100` seen by controllers.
+    /// - `SetVolume` invokes [`on_volume_set`](HTTPServer::on_volume_set), scaled the other way.
+    /// - `GetMute` is answered with [`mute`](HTTPServer::mute).
+    /// - `SetMute` invokes [`on_mute_set`](HTTPServer::on_mute_set).
+    /// - Answers to `GetVolume` and `GetMute` are cached for [`DMROptions::volume_cache_ms`].
+    /// - Actions are tracked by [`ControlContext::state`].
+    /// - `GetVolume` and `GetMute` are answered with the tracked volume and mute state if [`volume`](HTTPServer::volume) and [`mute`](HTTPServer::mute) return `None`.
+    /// - Actions addressed to an unknown instance are answered with [`UpnpError::invalid_instance_id`].
+    /// - Actions disabled in [`DMROptions::capabilities`] are answered with a fault.
+    /// - Other actions are answered with [`UpnpError::optional_action_not_implemented`].
+    /// - Unparsable actions are answered with [`UpnpError::invalid_args`], or [`UpnpError::invalid_action`] if the action is unknown.
+    fn post_rendering_control(
+        &self,
+        rendering_control: Result<RenderingControl, ParseError>,
+        context: &ControlContext,
+    ) -> impl Future<Output = impl IntoResponse> + Send {
+        let volume_max = context.options.volume_max;
+        async move {
+            if let Ok(action) = &rendering_control {
+                let checked = context.options.capabilities.check_rendering_control(action);
+                let applied =
+                    checked.and_then(|()| context.state().apply_rendering_control(action));
+                if let Err(e) = applied {
+                    return soap_fault(e.code, &e.description);
+                }
+            }
+            match rendering_control {
+                Ok(RenderingControl::GetVolume(get)) => {
+                    let Some(volume) =
+                        current_volume(self, context, get.instance_id, get.channel).await
+                    else {
+                        let e = UpnpError::invalid_instance_id();
+                        return soap_fault(e.code, &e.description);
+                    };
+                    soap_response(context.action_response(
+                        rendering_control::SERVICE_TYPE,
+                        "GetVolume",
+                        &[("CurrentVolume", &volume.to_string())],
+                    ))
+                }
+                Ok(RenderingControl::SetVolume(set)) => {
+                    context.invalidate_volume_cache();
+                    let volume = set.scaled(volume_max);
+                    self.on_volume_set(set.instance_id, set.channel, volume)
+                        .await;
+                    soap_response(context.action_response(
+                        rendering_control::SERVICE_TYPE,
+                        "SetVolume",
+                        &[],
+                    ))
+                }
+                Ok(RenderingControl::GetMute(get)) => {
+                    let Some(mute) =
+                        current_mute(self, context, get.instance_id, get.channel).await
+                    else {
+                        let e = UpnpError::invalid_instance_id();
+                        return soap_fault(e.code, &e.description);
+                    };
+                    soap_response(context.action_response(
+                        rendering_control::SERVICE_TYPE,
+                        "GetMute",
+                        &[("CurrentMute", if mute { "1" } else { "0" })],
+                    ))
+                }
+                Ok(RenderingControl::SetMute(set)) => {
+                    context.invalidate_volume_cache();
+                    self.on_mute_set(set.instance_id, set.channel, set.desired_mute)
+                        .await;
+                    soap_response(context.action_response(
+                        rendering_control::SERVICE_TYPE,
+                        "SetMute",
+                        &[],
+                    ))
+                }
+                unhandled => unhandled_fault(&unhandled, RenderingControl::ACTIONS),
+            }
+        }
+    }
+
+    /// Handles POST requests for `/AVTransport`.
+    ///
+    /// By default:
+    ///
+    /// - `SetAVTransportURI` and `SetNextAVTransportURI` check the URI with [`validate_uri`](HTTPServer::validate_uri), and for reachability if [`DMROptions::check_uri_reachable`] is enabled.
+    /// - `SetAVTransportURI` then invokes [`on_uri_set`](HTTPServer::on_uri_set), except for resends of the current URI recognized by [`ControlContext::state`] (see [`Applied::Duplicate`]).
+    /// - `SetNextAVTransportURI` then tracks the next URI and invokes [`on_next_uri_set`](HTTPServer::on_next_uri_set).
+    /// - `GetMediaInfo`, `GetTransportInfo` and `GetPositionInfo` are answered with [`media_info`](HTTPServer::media_info), [`transport_info`](HTTPServer::transport_info) and [`position_info`](HTTPServer::position_info), which default to the state tracked by [`ControlContext::state`] (see [`ControlContext::notify_playing`]).
+    /// - `Play`, `Pause` and `Stop` change the tracked transport state (see [`InstanceState::apply`]), which is published to subscribed controllers.
+    /// - `Play` at a speed other than [`DMROptions::supported_play_speeds`] is answered with [`UpnpError::play_speed_not_supported`].
+    /// - `Seek` moves the tracked position (see [`InstanceState::seek_target`]), then invokes [`on_seek`](HTTPServer::on_seek).
+    /// - `GetCurrentTransportActions` is answered with [`current_transport_actions`](HTTPServer::current_transport_actions).
+    /// - `GetDeviceCapabilities` advertises recording as `NOT_IMPLEMENTED` and lists the supported play speeds as `X_DLNA_PlaySpeeds`.
+    /// - `SetPlayMode` invokes [`on_play_mode_set`](HTTPServer::on_play_mode_set), then tracks the play mode and publishes it to subscribed controllers.
+    /// - `GetTransportSettings` reports the tracked play mode and lists the supported play speeds as `X_DLNA_PlaySpeeds`.
+    /// - `Record` is answered with [`UpnpError::transition_not_available`].
+    /// - `SetRecordQualityMode` is answered with [`UpnpError::record_quality_not_supported`].
+    /// - Actions disabled in [`DMROptions::capabilities`] are answered with a fault, and left out of `GetCurrentTransportActions`.
+    /// - Other actions and unparsable ones are answered with faults like [`post_rendering_control`](HTTPServer::post_rendering_control) does.
+    fn post_av_transport(
+        &self,
+        av_transport: Result<AVTransport, ParseError>,
+        context: &ControlContext,
+    ) -> impl Future<Output = impl IntoResponse> + Send {
+        async move {
+            if let Some(Err(e)) = av_transport
+                .as_ref()
+                .ok()
+                .map(|action| context.options.capabilities.check_av_transport(action))
+            {
+                return soap_fault(e.code, &e.description);
+            }
+            match av_transport {
+                Ok(AVTransport::SetAVTransportURI(set)) => {
+                    set_av_transport_uri(self, &set, context).await
+                }
+                Ok(AVTransport::SetNextAVTransportURI(set)) => {
+                    set_next_av_transport_uri(self, &set, context).await
+                }
+                Ok(AVTransport::GetMediaInfo(get)) => {
+                    typed_response(context, self.media_info(get.instance_id, context).await)
+                }
+                Ok(AVTransport::GetTransportInfo(get)) => {
+                    typed_response(context, self.transport_info(get.instance_id, context).await)
+                }
+                Ok(AVTransport::GetPositionInfo(get)) => {
+                    typed_response(context, self.position_info(get.instance_id, context).await)
+                }
+                Ok(AVTransport::Seek(seek)) => self::seek(self, &seek, context).await,
+                Ok(AVTransport::SetPlayMode(set)) => set_play_mode(self, &set, context).await,
+                Ok(AVTransport::GetCurrentTransportActions(get)) => {
+                    let actions = self
+                        .current_transport_actions(get.instance_id, context)
+                        .await
+                        .map(|mut actions| {
+                            actions.retain(|action| context.options.capabilities.allows(*action));
+                            GetCurrentTransportActionsResponse {
+                                actions: actions.into(),
+                            }
+                        });
+                    typed_response(context, actions)
+                }
+                Ok(AVTransport::GetDeviceCapabilities(_)) => {
+                    soap_response(context.action_response(
+                        av_transport::SERVICE_TYPE,
+                        "GetDeviceCapabilities",
+                        &[
+                            ("PlayMedia", "NETWORK"),
+                            ("RecMedia", "NOT_IMPLEMENTED"),
+                            ("RecQualityModes", "NOT_IMPLEMENTED"),
+                            ("X_DLNA_PlaySpeeds", &play_speeds(&context.options)),
+                        ],
+                    ))
+                }
+                Ok(AVTransport::GetTransportSettings(get)) => {
+                    let Some(play_mode) = context
+                        .state()
+                        .instance(get.instance_id)
+                        .map(|instance| instance.play_mode)
+                    else {
+                        let e = UpnpError::invalid_instance_id();
+                        return soap_fault(e.code, &e.description);
+                    };
+                    soap_response(context.action_response(
+                        av_transport::SERVICE_TYPE,
+                        "GetTransportSettings",
+                        &[
+                            ("PlayMode", &play_mode.to_string()),
+                            ("RecQualityMode", "NOT_IMPLEMENTED"),
+                            ("X_DLNA_PlaySpeeds", &play_speeds(&context.options)),
+                        ],
+                    ))
+                }
+                Ok(AVTransport::Play(play))
+                    if !context.options.supported_play_speeds.contains(&play.speed) =>
+                {
+                    let e = UpnpError::play_speed_not_supported();
+                    soap_fault(e.code, &e.description)
+                }
+                Ok(action @ AVTransport::Play(_)) => {
+                    transition(&action, "Play", TransportState::Playing, context).await
+                }
+                Ok(action @ AVTransport::Pause(_)) => {
+                    transition(&action, "Pause", TransportState::PausedPlayback, context).await
+                }
+                Ok(action @ AVTransport::Stop(_)) => {
+                    transition(&action, "Stop", TransportState::Stopped, context).await
+                }
+                Ok(AVTransport::Record(_)) => {
+                    let e = UpnpError::transition_not_available();
+                    soap_fault(e.code, &e.description)
+                }
+                Ok(AVTransport::SetRecordQualityMode(_)) => {
+                    let e = UpnpError::record_quality_not_supported();
+                    soap_fault(e.code, &e.description)
+                }
+                unhandled => unhandled_fault(&unhandled, AVTransport::ACTIONS),
+            }
+        }
+    }
+
+    /// Handles POST requests for `/ConnectionManager`.
+    ///
+    /// By default:
+    ///
+    /// - `GetProtocolInfo` is answered with [`DMROptions::sink_protocol_info`] as the `Sink` and no `Source`.
+    /// - `GetCurrentConnectionIDs` is answered with the single [`DEFAULT_CONNECTION_ID`].
+    /// - `GetCurrentConnectionInfo` is answered with an `Input` connection of unknown protocol for that ID, or [`UpnpError::invalid_connection_reference`] for others.
+    /// - Unparsable requests are answered with [`UpnpError::invalid_args`], or [`UpnpError::invalid_action`] if the action is unknown.
+    fn post_connection_manager(
+        &self,
+        connection_manager: Result<ConnectionManager, ParseError>,
+        context: &ControlContext,
+    ) -> impl Future<Output = impl IntoResponse> + Send {
+        async move {
+            match connection_manager {
+                Ok(ConnectionManager::GetProtocolInfo(_)) => protocol_info(context),
+                Ok(ConnectionManager::GetCurrentConnectionIDs(_)) => {
+                    soap_response(context.action_response(
+                        connection_manager::SERVICE_TYPE,
+                        "GetCurrentConnectionIDs",
+                        &[("ConnectionIDs", &DEFAULT_CONNECTION_ID.to_string())],
+                    ))
+                }
+                Ok(ConnectionManager::GetCurrentConnectionInfo(get))
+                    if get.connection_id == DEFAULT_CONNECTION_ID =>
+                {
+                    soap_response(context.action_response(
+                        connection_manager::SERVICE_TYPE,
+                        "GetCurrentConnectionInfo",
+                        &[
+                            ("RcsID", "0"),
+                            ("AVTransportID", "0"),
+                            ("ProtocolInfo", ""),
+                            ("PeerConnectionManager", ""),
+                            ("PeerConnectionID", "-1"),
+                            ("Direction", "Input"),
+                            ("Status", "OK"),
+                        ],
+                    ))
+                }
+                Ok(ConnectionManager::GetCurrentConnectionInfo(_)) => {
+                    let e = UpnpError::invalid_connection_reference();
+                    soap_fault(e.code, &e.description)
+                }
+                Err(e) => unhandled_fault::<ConnectionManager>(&Err(e), ConnectionManager::ACTIONS),
+            }
+        }
+    }
+
+    /// Handles POST requests for `/Ignore`, which stood in for `ConnectionManager`.
+    ///
+    /// By default, `GetProtocolInfo` is answered like [`post_connection_manager`](HTTPServer::post_connection_manager) does, while other requests are acknowledged without content.
+    fn post_ignore(
+        &self,
+        body: String,
+        context: &ControlContext,
+    ) -> impl Future<Output = impl IntoResponse> + Send {
+        // Only detecting the action, as none of them takes arguments
+        let get_protocol_info = body.contains(":GetProtocolInfo");
+        async move {
+            if get_protocol_info {
+                protocol_info(context)
+            } else {
+                StatusCode::NO_CONTENT.into_response()
+            }
+        }
+    }
+
+    // Eventing.
+
+    /// Sends a `LastChange` event to the controllers subscribed to its service on this running instance, e.g. after the volume changed with a physical knob, returning how many it was delivered to. See [`ControlContext::publish_event`].
+    ///
+    /// ```rust,no_run
+    /// use dlna_dmr::{HTTPServer, xml::{last_change::LastChange, rendering_control::Channel}};
+    ///
+    /// async fn volume_knob_turned(dmr: &'static impl HTTPServer, volume: u16) {
+    ///     let event = LastChange::rendering_control().volume(0, Channel::Master, volume);
+    ///     dmr.publish_event(&event).await.expect("Renderer isn't running");
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`ErrorKind::NotConnected`] if the instance isn't running.
+    fn publish_event(
+        &'static self,
+        event: &LastChange,
+    ) -> impl Future<Output = IoResult<usize>> + Send {
+        async move {
+            let Some(runtime) = runtime::get(self) else {
+                return Err(Error::new(
+                    ErrorKind::NotConnected,
+                    "DMR instance isn't running",
+                ));
+            };
+            Ok(runtime.context.publish_event(event).await)
+        }
+    }
+
+    // Hooks invoked by the default handlers.
+
+    /// Followers to mirror commands to, e.g. for multi-room setups. After a command (as opposed to a query like `GetVolume`) has been handled successfully, its SOAP envelope is forwarded to the followers in the background. Defaults to none.
+    fn followers(&self) -> Option<&FollowerClient> {
+        None
+    }
+
+    /// Validates a URI set via `SetAVTransportURI` or `SetNextAVTransportURI` before [`on_uri_set`](HTTPServer::on_uri_set) or [`on_next_uri_set`](HTTPServer::on_next_uri_set) is invoked, answering with a SOAP fault on error.
+    ///
+    /// By default, `file:` URIs are rejected with [`UpnpError::resource_not_found`], as they give controllers access to the local file system. Override this method with [`validate_scheme`] to allow them explicitly:
+    ///
+    /// ```rust
+    /// use dlna_dmr::{HTTPServer, UpnpError, uri::validate_scheme};
+    ///
+    /// struct MyDMR;
+    ///
+    /// impl HTTPServer for MyDMR {
+    ///     async fn validate_uri(&self, uri: &str) -> Result<(), UpnpError> {
+    ///         validate_scheme(uri, true).map(|_| ())
+    ///     }
+    /// }
+    /// ```
+    fn validate_uri(&self, uri: &str) -> impl Future<Output = Result<(), UpnpError>> + Send {
+        let result = validate_scheme(uri, false).map(|_| ());
+        async move { result }
+    }
+
+    /// Called when a controller sets the current URI via `SetAVTransportURI`, with the URI and its DIDL-Lite metadata.
     #[allow(
         unused_variables,
         reason = "This is a dummy trait method, intended to be overridden"
     )]
-    fn post_rendering_control(
+    fn on_uri_set(&self, uri: &str, metadata: &str) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called when a controller sets the next URI via `SetNextAVTransportURI`, with the URI and its DIDL-Lite metadata. Gapless-capable renderers may start buffering the next track here.
+    ///
+    /// ## Ordering
+    ///
+    /// Controllers announce the next URI while the current track is still playing, so this hook fires before playback of the current track ends, and nothing is invoked when it does end: switching to the preloaded URI is up to you. This hook is never invoked in place of [`on_uri_set`](HTTPServer::on_uri_set), and a later `SetNextAVTransportURI` or `SetAVTransportURI` supersedes the URI passed here.
+    #[allow(
+        unused_variables,
+        reason = "This is a dummy trait method, intended to be overridden"
+    )]
+    fn on_next_uri_set(&self, uri: &str, metadata: &str) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called when a controller sets the play mode of the given instance via `SetPlayMode`, e.g. to shuffle or repeat, answering with a SOAP fault on error. The play mode is tracked by [`ControlContext::state`] once accepted.
+    ///
+    /// By default, only [`PlayMode::Normal`] is accepted, and other modes are rejected with [`UpnpError::play_mode_not_supported`].
+    #[allow(
+        unused_variables,
+        reason = "This is a dummy trait method, intended to be overridden"
+    )]
+    fn on_play_mode_set(
         &self,
-        rendering_control: Result<RenderingControl, DeError>,
-    ) -> impl Future<Output = impl IntoResponse> + Send {
-        async { StatusCode::METHOD_NOT_ALLOWED }
+        instance_id: u32,
+        play_mode: PlayMode,
+    ) -> impl Future<Output = Result<(), UpnpError>> + Send {
+        async move {
+            if play_mode == PlayMode::Normal {
+                Ok(())
+            } else {
+                Err(UpnpError::play_mode_not_supported())
+            }
+        }
     }
 
-    /// Handles POST requests for `/AVTransport`.
+    /// Called when a controller seeks within the current track of the given instance via `Seek`, with the target position. The clock of [`ControlContext::state`] has already moved there.
     #[allow(
         unused_variables,
         reason = "This is a dummy trait method, intended to be overridden"
     )]
-    fn post_av_transport(
+    fn on_seek(&self, instance_id: u32, position: Duration) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Returns the current volume of the given instance and channel, in the hardware range `0..=volume_max` (see [`DMROptions::volume_max`]), answering `GetVolume`. Defaults to `None`, answering `GetVolume` with the volume tracked by [`ControlContext::state`], i.e. the last one set via `SetVolume` or [`ControlContext::notify_volume`].
+    #[allow(
+        unused_variables,
+        reason = "This is a dummy trait method, intended to be overridden"
+    )]
+    fn volume(
         &self,
-        av_transport: Result<AVTransport, DeError>,
-    ) -> impl Future<Output = impl IntoResponse> + Send {
-        async { StatusCode::METHOD_NOT_ALLOWED }
+        instance_id: u32,
+        channel: Channel,
+    ) -> impl Future<Output = Option<u16>> + Send {
+        async { None }
     }
 
-    /// Handles POST requests for `/Ignore`.
-    fn post_ignore(&self) -> impl Future<Output = impl IntoResponse> + Send {
-        async { StatusCode::NO_CONTENT }
+    /// Returns whether the given instance and channel is muted, answering `GetMute`. Defaults to `None`, answering `GetMute` with the mute state tracked by [`ControlContext::state`], i.e. the last one set via `SetMute`.
+    #[allow(
+        unused_variables,
+        reason = "This is a dummy trait method, intended to be overridden"
+    )]
+    fn mute(
+        &self,
+        instance_id: u32,
+        channel: Channel,
+    ) -> impl Future<Output = Option<bool>> + Send {
+        async { None }
+    }
+
+    /// Called when a controller mutes or unmutes via `SetMute`.
+    #[allow(
+        unused_variables,
+        reason = "This is a dummy trait method, intended to be overridden"
+    )]
+    fn on_mute_set(
+        &self,
+        instance_id: u32,
+        channel: Channel,
+        mute: bool,
+    ) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called when a controller sets the volume via `SetVolume`, with the desired volume scaled to the hardware range `0..=volume_max` (see [`DMROptions::volume_max`]).
+    #[allow(
+        unused_variables,
+        reason = "This is a dummy trait method, intended to be overridden"
+    )]
+    fn on_volume_set(
+        &self,
+        instance_id: u32,
+        channel: Channel,
+        volume: u16,
+    ) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Returns the media of the given instance, answering `GetMediaInfo`. Defaults to the media tracked by [`ControlContext::state`], i.e. the URIs set by controllers.
+    fn media_info(
+        &self,
+        instance_id: u32,
+        context: &ControlContext,
+    ) -> impl Future<Output = Result<GetMediaInfoResponse, UpnpError>> + Send {
+        let info = tracked_media_info(context, instance_id);
+        async { info }
+    }
+
+    /// Returns the transport state and speed of the given instance, answering `GetTransportInfo`. Defaults to the state tracked by [`ControlContext::state`], see [`ControlContext::notify_playing`].
+    fn transport_info(
+        &self,
+        instance_id: u32,
+        context: &ControlContext,
+    ) -> impl Future<Output = Result<GetTransportInfoResponse, UpnpError>> + Send {
+        let info = tracked_transport_info(context, instance_id);
+        async { info }
+    }
+
+    /// Returns the current track and position of the given instance, answering `GetPositionInfo`. Defaults to the position tracked by [`ControlContext::state`], which only follows `Play`, `Pause`, `Stop` and `Seek`: override this method if your player knows better.
+    fn position_info(
+        &self,
+        instance_id: u32,
+        context: &ControlContext,
+    ) -> impl Future<Output = Result<GetPositionInfoResponse, UpnpError>> + Send {
+        let info = tracked_position_info(context, instance_id);
+        async { info }
+    }
+
+    /// Returns the actions currently available on the given instance, answering `GetCurrentTransportActions`. Defaults to the actions available in the transport state tracked by [`ControlContext::state`], see [`TransportState::available_actions`].
+    fn current_transport_actions(
+        &self,
+        instance_id: u32,
+        context: &ControlContext,
+    ) -> impl Future<Output = Result<Vec<TransportAction>, UpnpError>> + Send {
+        let actions = context
+            .state()
+            .instance(instance_id)
+            .map(|instance| instance.transport_state.available_actions().to_vec())
+            .ok_or_else(UpnpError::invalid_instance_id);
+        async { actions }
     }
 
     // GET Request handlers for specific endpoints.
 
-    /// Handles GET requests for `/DeviceSpec`.
+    /// Handles GET requests for `/DeviceSpec` and [`DESCRIPTION_XML_PATH`], serving the description cached in the context.
     #[must_use]
-    fn get_device_spec(options: Arc<DMROptions>) -> impl Future<Output = impl IntoResponse> + Send {
+    fn get_device_spec(context: &ControlContext) -> impl Future<Output = impl IntoResponse> + Send {
+        let description = context.description();
         async move {
-            /// Escapes given field under `options`.
-            macro_rules! e {
-                ($i:ident) => {
-                    escape(&options.$i)
-                };
+            match description {
+                Ok(xml) => xml_ok(xml.as_ref()),
+                Err(e) => {
+                    error!("Failed to render device description: {e}");
+                    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                }
             }
-            let xml = format!(
-                include_str!("./template/DeviceSpec.tmpl.xml"),
-                friendlyName = e!(friendly_name),
-                modelName = e!(model_name),
-                modelDescription = e!(model_description),
-                modelURL = e!(model_url),
-                manufacturer = e!(manufacturer),
-                manufacturerURL = e!(manufacturer_url),
-                serialNumber = e!(serial_number),
-                uuid = e!(uuid),
-            );
-            (
-                StatusCode::OK,
-                [("Content-Type", r#"text/xml; charset="utf-8""#)],
-                xml,
-            )
         }
     }
 
     /// Handles GET requests for `/RenderingControl`.
     #[must_use]
     fn get_rendering_control() -> impl Future<Output = impl IntoResponse> + Send {
-        async {
-            (
-                StatusCode::OK,
-                [("Content-Type", r#"text/xml; charset="utf-8""#)],
-                include_str!("./template/RenderingControl.xml"),
-            )
-        }
+        async { xml_ok(template::RENDERING_CONTROL_SCPD) }
     }
 
     /// Handles GET requests for `/AVTransport`.
     #[must_use]
     fn get_av_transport() -> impl Future<Output = impl IntoResponse> + Send {
-        async {
-            (
-                StatusCode::OK,
-                [("Content-Type", r#"text/xml; charset="utf-8""#)],
-                include_str!("./template/AVTransport.xml"),
-            )
-        }
+        async { xml_ok(template::AV_TRANSPORT_SCPD) }
+    }
+
+    /// Handles GET requests for `/ConnectionManager`.
+    #[must_use]
+    fn get_connection_manager() -> impl Future<Output = impl IntoResponse> + Send {
+        async { xml_ok(template::CONNECTION_MANAGER_SCPD) }
     }
 
     /// Handles GET requests for `/Ignore`.
@@ -163,3 +1230,1879 @@ pub trait HTTPServer: Sync {
         async { StatusCode::NO_CONTENT }
     }
 }
+
+/// Adds `ETag` and `Cache-Control` headers to a successful `response` if `max_age` is set, answering with `304 Not Modified` instead if the `If-None-Match` request header matches. The `ETag` is a hash of the body, so it changes whenever the description does.
+async fn with_cache_headers(
+    response: axum::response::Response,
+    headers: &HeaderMap,
+    max_age: Option<u64>,
+) -> axum::response::Response {
+    let Some(max_age) = max_age else {
+        return response;
+    };
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, usize::MAX).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    let etag = format!("\"{:016x}\"", hasher.finish());
+    let not_modified = headers
+        .get(IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+        });
+    let cache_headers = [(ETAG, etag), (CACHE_CONTROL, format!("max-age={max_age}"))];
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+    for (name, value) in cache_headers {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            parts.headers.insert(name, value);
+        }
+    }
+    axum::response::Response::from_parts(parts, bytes.into())
+}
+
+/// Answers a request to a path without a route, logging POST bodies for diagnostics.
+fn unknown_path(
+    unknown_post: UnknownPost,
+    method: &Method,
+    uri: &Uri,
+    body: &str,
+) -> axum::response::Response {
+    if method != Method::POST {
+        debug!("{method} request to unknown path {uri}");
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    debug!("POST request to unknown path {uri}: {body}");
+    match unknown_post {
+        UnknownPost::NotFound => StatusCode::NOT_FOUND.into_response(),
+        UnknownPost::Fault => {
+            let e = UpnpError::invalid_action();
+            soap_fault(e.code, &e.description)
+        }
+    }
+}
+
+/// Mount the event URLs of the enabled services, accepting GENA `SUBSCRIBE` and `UNSUBSCRIBE` requests. New subscriptions are sent the initial event in the background once the response is written, ahead of any other event, see [`initial_event`].
+fn event_routes<S: HTTPServer + ?Sized>(
+    server: &'static S,
+    mut router: Router,
+    context: &Arc<ControlContext>,
+) -> Router {
+    for route in Route::ALL {
+        if let Some(path) = route.event_path()
+            && context.options.enabled_routes.contains(&route)
+        {
+            let context = Arc::clone(context);
+            router = router.route(
+                &path,
+                any(async move |method: Method, headers: HeaderMap| {
+                    let (response, subscription) =
+                        eventing::respond(&context.subscriptions, route, &method, &headers);
+                    let Some(subscription) = subscription else {
+                        return response;
+                    };
+                    // The body is dropped once the response is written, and only then may the controller learn of the `SID`
+                    let (on_written, written) = oneshot::channel::<()>();
+                    let context = Arc::clone(&context);
+                    tokio::spawn(async move {
+                        let _ = written.await;
+                        if let Some(body) = initial_event(server, &context, route).await {
+                            subscription.notify(&context.subscriptions, &body).await;
+                        }
+                    });
+                    response.map(|body| {
+                        Body::new(body.map_frame(move |frame| {
+                            let _on_written = &on_written;
+                            frame
+                        }))
+                    })
+                }),
+            );
+        }
+    }
+    router
+}
+
+/// The initial event of a new subscription to the service of given route, i.e. the current values of its evented state variables. Volumes and mute states are those answered to `GetVolume` and `GetMute`, see [`current_volume`] and [`current_mute`].
+async fn initial_event<S: HTTPServer + ?Sized>(
+    server: &S,
+    context: &ControlContext,
+    route: Route,
+) -> Option<String> {
+    let event = match route {
+        Route::AVTransport => {
+            context
+                .state()
+                .instances()
+                .fold(LastChange::av_transport(), |event, (id, instance)| {
+                    event
+                        .transport_state(id, instance.transport_state)
+                        .set(id, "AVTransportURI", &instance.current_uri)
+                        .set(id, "NextAVTransportURI", &instance.next_uri)
+                        .set(id, "CurrentPlayMode", instance.play_mode)
+                })
+        }
+        Route::RenderingControl => {
+            let instance_ids: Vec<_> = context.state().instances().map(|(id, _)| id).collect();
+            let mut event = LastChange::rendering_control();
+            for id in instance_ids {
+                if let Some(volume) = current_volume(server, context, id, Channel::Master).await {
+                    event = event.volume(id, Channel::Master, volume);
+                }
+                if let Some(mute) = current_mute(server, context, id, Channel::Master).await {
+                    event = event.mute(id, Channel::Master, mute);
+                }
+            }
+            event
+        }
+        Route::ConnectionManager => {
+            return Some(property_set(&[
+                ("SourceProtocolInfo", ""),
+                ("SinkProtocolInfo", &sink_protocol_info(&context.options)),
+                ("CurrentConnectionIDs", &DEFAULT_CONNECTION_ID.to_string()),
+            ]));
+        }
+        Route::DeviceSpec | Route::Ignore => return None,
+    };
+    Some(event.property_set())
+}
+
+/// The volume of given instance and channel as answered to `GetVolume`, in the range `0..=100` seen by controllers: cached, or queried from [`HTTPServer::volume`] and scaled from the hardware range, or else tracked by [`ControlContext::state`]. `None` if the instance doesn't exist.
+async fn current_volume<S: HTTPServer + ?Sized>(
+    server: &S,
+    context: &ControlContext,
+    instance_id: InstanceId,
+    channel: Channel,
+) -> Option<u16> {
+    let key = (instance_id, channel.clone());
+    let mut volume = lock(&context.volume_cache).get(&key);
+    if volume.is_none() {
+        volume = server.volume(instance_id, channel.clone()).await;
+        if let Some(volume) = volume {
+            lock(&context.volume_cache).insert(key, volume);
+        }
+    }
+    volume
+        .map(|volume| {
+            scale_volume(
+                volume,
+                context.options.volume_max,
+                rendering_control::VOLUME_MAX,
+            )
+        })
+        .or_else(|| {
+            context
+                .state()
+                .instance(instance_id)
+                .map(|instance| instance.volume(&channel))
+        })
+}
+
+/// Whether given instance and channel is muted as answered to `GetMute`: cached, or queried from [`HTTPServer::mute`], or else tracked by [`ControlContext::state`]. `None` if the instance doesn't exist.
+async fn current_mute<S: HTTPServer + ?Sized>(
+    server: &S,
+    context: &ControlContext,
+    instance_id: InstanceId,
+    channel: Channel,
+) -> Option<bool> {
+    let key = (instance_id, channel.clone());
+    let mut mute = lock(&context.mute_cache).get(&key);
+    if mute.is_none() {
+        mute = server.mute(instance_id, channel.clone()).await;
+        if let Some(mute) = mute {
+            lock(&context.mute_cache).insert(key, mute);
+        }
+    }
+    mute.or_else(|| {
+        context
+            .state()
+            .instance(instance_id)
+            .map(|instance| instance.mute(&channel))
+    })
+}
+
+/// The address of the controller that sent a request, if the router is served with connection info.
+fn peer(extensions: &Extensions) -> Option<SocketAddr> {
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| *address)
+}
+
+/// The `SOAPACTION` header of a request, if present and well-formed.
+fn soap_action(headers: &HeaderMap) -> Option<SoapAction> {
+    headers
+        .get(SoapAction::HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+/// The comma-separated [`DMROptions::sink_protocol_info`].
+fn sink_protocol_info(options: &DMROptions) -> String {
+    options
+        .sink_protocol_info
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Answers `GetProtocolInfo` with [`DMROptions::sink_protocol_info`] as the `Sink`, and no `Source`.
+fn protocol_info(context: &ControlContext) -> axum::response::Response {
+    let sink = sink_protocol_info(&context.options);
+    soap_response(context.action_response(
+        connection_manager::SERVICE_TYPE,
+        "GetProtocolInfo",
+        &[("Source", ""), ("Sink", &sink)],
+    ))
+}
+
+/// Answers `SetAVTransportURI` as described in [`HTTPServer::post_av_transport`].
+async fn set_av_transport_uri<S: HTTPServer + ?Sized>(
+    server: &S,
+    set: &SetAVTransportURI,
+    context: &ControlContext,
+) -> axum::response::Response {
+    if let Err(e) = accept_uri(
+        server.validate_uri(&set.current_uri),
+        context,
+        &set.current_uri,
+    )
+    .await
+    {
+        return soap_fault(e.code, &e.description);
+    }
+    let applied = context
+        .state()
+        .instance_mut(set.instance_id)
+        .map(|instance| instance.set_uri(&set.current_uri, &set.current_uri_meta_data));
+    let Some(applied) = applied else {
+        let e = UpnpError::invalid_instance_id();
+        return soap_fault(e.code, &e.description);
+    };
+    if applied == Applied::Duplicate {
+        debug!("Ignoring duplicate SetAVTransportURI: {}", set.current_uri);
+    } else {
+        server
+            .on_uri_set(&set.current_uri, &set.current_uri_meta_data)
+            .await;
+    }
+    soap_response(context.action_response(av_transport::SERVICE_TYPE, "SetAVTransportURI", &[]))
+}
+
+/// Answers `SetNextAVTransportURI` as described in [`HTTPServer::post_av_transport`], tracking the next URI reported by `GetMediaInfo`.
+async fn set_next_av_transport_uri<S: HTTPServer + ?Sized>(
+    server: &S,
+    set: &SetNextAVTransportURI,
+    context: &ControlContext,
+) -> axum::response::Response {
+    if let Err(e) = accept_uri(server.validate_uri(&set.next_uri), context, &set.next_uri).await {
+        return soap_fault(e.code, &e.description);
+    }
+    let applied = context
+        .state()
+        .apply(&AVTransport::SetNextAVTransportURI(set.clone()));
+    if let Err(e) = applied {
+        return soap_fault(e.code, &e.description);
+    }
+    server
+        .on_next_uri_set(&set.next_uri, &set.next_uri_meta_data)
+        .await;
+    soap_response(context.action_response(av_transport::SERVICE_TYPE, "SetNextAVTransportURI", &[]))
+}
+
+/// Answers `Play`, `Pause` or `Stop` as described in [`HTTPServer::post_av_transport`], named `name` and leading to `state`, which is published to subscribed controllers.
+async fn transition(
+    action: &AVTransport,
+    name: &str,
+    state: TransportState,
+    context: &ControlContext,
+) -> axum::response::Response {
+    let applied = context.state().apply(action);
+    if let Err(e) = applied {
+        return soap_fault(e.code, &e.description);
+    }
+    context
+        .publish_transport_state(action.instance_id(), state)
+        .await;
+    soap_response(context.action_response(av_transport::SERVICE_TYPE, name, &[]))
+}
+
+/// Answers `Seek` as described in [`HTTPServer::post_av_transport`].
+async fn seek<S: HTTPServer + ?Sized>(
+    server: &S,
+    seek: &Seek,
+    context: &ControlContext,
+) -> axum::response::Response {
+    let applied = context
+        .state()
+        .instance_mut(seek.instance_id)
+        .ok_or_else(UpnpError::invalid_instance_id)
+        .and_then(|instance| {
+            instance.apply(&AVTransport::Seek(seek.clone()))?;
+            Ok(instance.clock.position())
+        });
+    match applied {
+        Ok(position) => {
+            server.on_seek(seek.instance_id, position).await;
+            soap_response(context.action_response(av_transport::SERVICE_TYPE, "Seek", &[]))
+        }
+        Err(e) => soap_fault(e.code, &e.description),
+    }
+}
+
+/// Answers `SetPlayMode` as described in [`HTTPServer::post_av_transport`].
+async fn set_play_mode<S: HTTPServer + ?Sized>(
+    server: &S,
+    set: &SetPlayMode,
+    context: &ControlContext,
+) -> axum::response::Response {
+    if context.state().instance(set.instance_id).is_none() {
+        let e = UpnpError::invalid_instance_id();
+        return soap_fault(e.code, &e.description);
+    }
+    if let Err(e) = server
+        .on_play_mode_set(set.instance_id, set.new_play_mode)
+        .await
+    {
+        return soap_fault(e.code, &e.description);
+    }
+    let updated = context.update_instance(set.instance_id, |instance| {
+        instance.play_mode = set.new_play_mode;
+    });
+    if let Err(e) = updated {
+        return soap_fault(e.code, &e.description);
+    }
+    let event =
+        LastChange::av_transport().set(set.instance_id, "CurrentPlayMode", set.new_play_mode);
+    context.publish_event(&event).await;
+    soap_response(context.action_response(av_transport::SERVICE_TYPE, "SetPlayMode", &[]))
+}
+
+/// Answers an action the default handler doesn't implement with [`UpnpError::optional_action_not_implemented`], or one that failed to parse with [`UpnpError::invalid_args`] if it's one of `actions`, [`UpnpError::invalid_action`] otherwise.
+fn unhandled_fault<A>(
+    action: &Result<A, ParseError>,
+    actions: &[&str],
+) -> axum::response::Response {
+    let e = match action {
+        Ok(_) => UpnpError::optional_action_not_implemented(),
+        Err(ParseError {
+            action: Some(action),
+            ..
+        }) if actions.contains(&action.as_str()) => UpnpError::invalid_args(),
+        Err(_) => UpnpError::invalid_action(),
+    };
+    soap_fault(e.code, &e.description)
+}
+
+/// Answers an action with its typed output arguments, or with a SOAP fault.
+fn typed_response<R: ActionResponse>(
+    context: &ControlContext,
+    response: Result<R, UpnpError>,
+) -> axum::response::Response {
+    match response {
+        Ok(response) => soap_response(context.envelope(&response)),
+        Err(e) => soap_fault(e.code, &e.description),
+    }
+}
+
+/// The media of given instance tracked by the context, as answered to `GetMediaInfo`.
+fn tracked_media_info(
+    context: &ControlContext,
+    instance_id: InstanceId,
+) -> Result<GetMediaInfoResponse, UpnpError> {
+    context
+        .state()
+        .instance(instance_id)
+        .map(|instance| GetMediaInfoResponse {
+            nr_tracks: u32::from(!instance.current_uri.is_empty()),
+            media_duration: format_time(instance.track_duration.unwrap_or_default()),
+            current_uri: instance.current_uri.clone(),
+            current_uri_meta_data: instance.current_uri_metadata.clone(),
+            next_uri: instance.next_uri.clone(),
+            next_uri_meta_data: instance.next_uri_metadata.clone(),
+            play_medium: "NETWORK".to_string(),
+            record_medium: "NOT_IMPLEMENTED".to_string(),
+            write_status: "NOT_IMPLEMENTED".to_string(),
+        })
+        .ok_or_else(UpnpError::invalid_instance_id)
+}
+
+/// The current track and position of given instance tracked by the context, as answered to `GetPositionInfo`. Counters aren't supported.
+fn tracked_position_info(
+    context: &ControlContext,
+    instance_id: InstanceId,
+) -> Result<GetPositionInfoResponse, UpnpError> {
+    context
+        .state()
+        .instance(instance_id)
+        .map(|instance| GetPositionInfoResponse {
+            track: u32::from(!instance.current_uri.is_empty()),
+            track_duration: format_time(instance.track_duration.unwrap_or_default()),
+            track_meta_data: instance.current_uri_metadata.clone(),
+            track_uri: instance.current_uri.clone(),
+            rel_time: format_time(instance.clock.position()),
+            abs_time: "NOT_IMPLEMENTED".to_string(),
+            rel_count: i32::MAX,
+            abs_count: i32::MAX,
+        })
+        .ok_or_else(UpnpError::invalid_instance_id)
+}
+
+/// Waits for `validated`, the outcome of [`HTTPServer::validate_uri`], then checks that `uri` is reachable if [`DMROptions::check_uri_reachable`] is enabled.
+async fn accept_uri(
+    validated: impl Future<Output = Result<(), UpnpError>>,
+    context: &ControlContext,
+    uri: &str,
+) -> Result<(), UpnpError> {
+    validated.await?;
+    if !context.options.check_uri_reachable {
+        return Ok(());
+    }
+    let timeout = Duration::from_millis(context.options.uri_validation_timeout_ms);
+    check_reachable(uri, timeout).await
+}
+
+/// Formats [`DMROptions::supported_play_speeds`] as the comma-separated `X_DLNA_PlaySpeeds` argument.
+fn play_speeds(options: &DMROptions) -> String {
+    options
+        .supported_play_speeds
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The transport state of given instance tracked by the context, as answered to `GetTransportInfo`.
+fn tracked_transport_info(
+    context: &ControlContext,
+    instance_id: InstanceId,
+) -> Result<GetTransportInfoResponse, UpnpError> {
+    context
+        .state()
+        .instance(instance_id)
+        .map(|instance| GetTransportInfoResponse {
+            current_transport_state: instance.transport_state,
+            current_transport_status: "OK".to_string(),
+            current_speed: PlaySpeed::One,
+        })
+        .ok_or_else(UpnpError::invalid_instance_id)
+}
+
+/// Locks `mutex`, ignoring poisoning as the protected data stays consistent.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Serves both HTTP/1.1 and HTTP/2 with prior knowledge, detected per connection, until `shutdown` resolves and the open connections have been closed gracefully.
+async fn serve_h2c(
+    listener: TcpListener,
+    router: Router,
+    shutdown: impl Future<Output = ()>,
+) -> IoResult<()> {
+    let builder = auto::Builder::new(TokioExecutor::new());
+    let graceful = GracefulShutdown::new();
+    let mut shutdown = std::pin::pin!(shutdown);
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            () = &mut shutdown => break,
+        };
+        let (stream, remote) = match accepted {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                // Same as `axum::serve`: errors like running out of file descriptors are transient
+                warn!("Failed to accept HTTP connection: {e}");
+                sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+        let service =
+            TowerToHyperService::new(router.clone().layer(Extension(ConnectInfo(remote))));
+        let connection = builder
+            .serve_connection_with_upgrades(TokioIo::new(stream), service)
+            .into_owned();
+        let connection = graceful.watch(connection);
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                debug!("HTTP connection from {remote} closed: {e}");
+            }
+        });
+    }
+    drop(listener);
+    graceful.shutdown().await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Capabilities, ServiceVersions, xml::av_transport::PlaySpeed};
+    use std::fs::read_to_string;
+
+    /// Records the hooks invoked, along with their arguments.
+    #[derive(Default)]
+    struct Recorder {
+        calls: Mutex<Vec<(&'static str, String, String)>>,
+    }
+
+    impl HTTPServer for Recorder {
+        async fn on_uri_set(&self, uri: &str, metadata: &str) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(("on_uri_set", uri.to_string(), metadata.to_string()));
+        }
+
+        async fn on_next_uri_set(&self, uri: &str, metadata: &str) {
+            self.calls.lock().unwrap().push((
+                "on_next_uri_set",
+                uri.to_string(),
+                metadata.to_string(),
+            ));
+        }
+    }
+
+    fn get_xml(path: &str) -> Result<AVTransport, ParseError> {
+        read_to_string(format!("tests/AVTransport/{path}"))
+            .expect("Failed to read XML file")
+            .parse()
+    }
+
+    fn context() -> ControlContext {
+        ControlContext::new(Arc::new(DMROptions::default()))
+    }
+
+    /// A server relying on the default handlers and hooks.
+    struct Plain;
+
+    impl HTTPServer for Plain {}
+
+    /// A server with a hardware volume range of `0..=30`, currently at 15.
+    #[derive(Default)]
+    struct Volume {
+        set: Mutex<Vec<u16>>,
+    }
+
+    impl HTTPServer for Volume {
+        async fn volume(&self, _instance_id: u32, _channel: Channel) -> Option<u16> {
+            Some(15)
+        }
+
+        async fn on_volume_set(&self, _instance_id: u32, _channel: Channel, volume: u16) {
+            self.set.lock().unwrap().push(volume);
+        }
+    }
+
+    /// A server counting how often the volume is queried.
+    #[derive(Default)]
+    struct Polled {
+        queries: Mutex<u32>,
+    }
+
+    impl HTTPServer for Polled {
+        async fn volume(&self, _instance_id: u32, _channel: Channel) -> Option<u16> {
+            *self.queries.lock().unwrap() += 1;
+            Some(50)
+        }
+    }
+
+    fn volume_context() -> ControlContext {
+        ControlContext::new(Arc::new(DMROptions {
+            volume_max: 30,
+            ..Default::default()
+        }))
+    }
+
+    fn get_rendering_control_xml(path: &str) -> Result<RenderingControl, ParseError> {
+        read_to_string(format!("tests/RenderingControl/{path}"))
+            .expect("Failed to read XML file")
+            .parse()
+    }
+
+    async fn body_string(response: axum::response::Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("Failed to read body");
+        String::from_utf8(body.to_vec()).expect("Body is not UTF-8")
+    }
+
+    #[tokio::test]
+    async fn test_on_uri_set() {
+        let recorder = Recorder::default();
+        let response = recorder
+            .post_av_transport(get_xml("SetAVTransportURI.xml"), &context())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            *recorder.calls.lock().unwrap(),
+            [(
+                "on_uri_set",
+                "http://example.com/sample.mp4?param1=a&param2=b".to_string(),
+                String::new()
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_media_info() {
+        let recorder = Recorder::default();
+        let context = context();
+        recorder
+            .post_av_transport(get_xml("SetAVTransportURI.xml"), &context)
+            .await;
+        let response = recorder
+            .post_av_transport(get_xml("GetMediaInfo.xml"), &context)
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_string(response).await;
+        assert!(body.contains("<NrTracks>1</NrTracks>"), "{body}");
+        assert!(
+            body.contains(
+                "<CurrentURI>http://example.com/sample.mp4?param1=a&amp;param2=b</CurrentURI>"
+            ),
+            "{body}"
+        );
+        assert!(body.contains("<PlayMedium>NETWORK</PlayMedium>"), "{body}");
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_uri_set() {
+        let recorder = Recorder::default();
+        let context = context();
+        for _ in 0..2 {
+            let response = recorder
+                .post_av_transport(get_xml("SetAVTransportURI.xml"), &context)
+                .await
+                .into_response();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        assert_eq!(recorder.calls.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_file_uri_rejected() {
+        let recorder = Recorder::default();
+        let xml = read_to_string("tests/AVTransport/SetAVTransportURI.xml")
+            .expect("Failed to read XML file")
+            .replace(
+                "http://example.com/sample.mp4?param1=a&amp;param2=b",
+                "file:///etc/passwd",
+            );
+        let response = recorder
+            .post_av_transport(xml.parse(), &context())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(
+            body_string(response)
+                .await
+                .contains("<errorCode>716</errorCode>")
+        );
+        assert!(recorder.calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_on_next_uri_set() {
+        let recorder = Recorder::default();
+        let response = recorder
+            .post_av_transport(get_xml("SetNextAVTransportURI.xml"), &context())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            *recorder.calls.lock().unwrap(),
+            [(
+                "on_next_uri_set",
+                "http://example.com/sample.mp4?param1=a&param2=b".to_string(),
+                String::new()
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_next_uri_tracked() {
+        let context = context();
+        let response = Plain
+            .post_av_transport(get_xml("SetNextAVTransportURI.xml"), &context)
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = Plain
+            .post_av_transport(get_xml("GetMediaInfo.xml"), &context)
+            .await
+            .into_response();
+        assert!(
+            body_string(response)
+                .await
+                .contains("<NextURI>http://example.com/sample.mp4?param1=a&amp;param2=b</NextURI>")
+        );
+
+        let xml = read_to_string("tests/AVTransport/SetNextAVTransportURI.xml")
+            .expect("Failed to read XML file")
+            .replace("<InstanceID>0</InstanceID>", "<InstanceID>1</InstanceID>");
+        let recorder = Recorder::default();
+        let response = recorder
+            .post_av_transport(xml.parse(), &context)
+            .await
+            .into_response();
+        assert!(
+            body_string(response)
+                .await
+                .contains("<errorCode>718</errorCode>")
+        );
+        assert!(recorder.calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_transitions() {
+        let context = context();
+        let post = async |path: &str| {
+            let response = Plain
+                .post_av_transport(get_xml(path), &context)
+                .await
+                .into_response();
+            body_string(response).await
+        };
+        assert!(
+            post("Play.xml")
+                .await
+                .contains("<errorCode>701</errorCode>")
+        );
+        post("SetAVTransportURI.xml").await;
+        for (action, state) in [
+            ("Play", "PLAYING"),
+            ("Pause", "PAUSED_PLAYBACK"),
+            ("Stop", "STOPPED"),
+        ] {
+            let body = post(&format!("{action}.xml")).await;
+            assert!(body.contains(&format!("<u:{action}Response")), "{body}");
+            let body = post("GetTransportInfo.xml").await;
+            assert!(
+                body.contains(&format!(
+                    "<CurrentTransportState>{state}</CurrentTransportState>"
+                )),
+                "{body}"
+            );
+        }
+        assert!(
+            post("Pause.xml")
+                .await
+                .contains("<errorCode>701</errorCode>")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transitions_published() {
+        let context = context();
+        let (sender, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let callback = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let callback_url = format!("http://{}/events", callback.local_addr().unwrap());
+        let controller = Router::new().route(
+            "/events",
+            any(async move |body: String| sender.send(body).unwrap()),
+        );
+        tokio::spawn(async move { axum::serve(callback, controller).await });
+        context.subscriptions().subscribe(
+            Route::AVTransport,
+            vec![callback_url],
+            Duration::from_mins(5),
+        );
+
+        for path in [
+            "SetAVTransportURI.xml",
+            "Play.xml",
+            "Pause.xml",
+            "SetPlayMode.xml",
+        ] {
+            let response = AnyPlayMode
+                .post_av_transport(get_xml(path), &context)
+                .await
+                .into_response();
+            assert_eq!(response.status(), StatusCode::OK, "{path}");
+        }
+        for expected in [
+            "&lt;TransportState val=&quot;PLAYING&quot;/&gt;",
+            "&lt;TransportState val=&quot;PAUSED_PLAYBACK&quot;/&gt;",
+            "&lt;CurrentPlayMode val=&quot;REPEAT_ALL&quot;/&gt;",
+        ] {
+            let body = received.recv().await.unwrap();
+            assert!(body.contains(expected), "{body}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_current_transport_actions() {
+        let get_actions = async |context: &ControlContext| {
+            let response = Plain
+                .post_av_transport(get_xml("GetCurrentTransportActions.xml"), context)
+                .await
+                .into_response();
+            assert_eq!(response.status(), StatusCode::OK);
+            body_string(response).await
+        };
+        let context = context();
+        let body = get_actions(&context).await;
+        assert!(body.contains("<Actions></Actions>"), "{body}");
+
+        // Following the tracked state, e.g. after a physical button was pressed
+        context.notify_playing(0).await.expect("Instance 0 exists");
+        let body = get_actions(&context).await;
+        assert!(
+            body.contains(&format!(
+                r#"<s:Body><u:GetCurrentTransportActionsResponse xmlns:u="{}"><Actions>Pause,Stop,Seek,Next,Previous</Actions></u:GetCurrentTransportActionsResponse></s:Body>"#,
+                av_transport::SERVICE_TYPE
+            )),
+            "{body}"
+        );
+        context.notify_stopped(0).await.expect("Instance 0 exists");
+        assert!(
+            get_actions(&context)
+                .await
+                .contains("<Actions>Play</Actions>")
+        );
+
+        let xml = read_to_string("tests/AVTransport/GetCurrentTransportActions.xml")
+            .expect("Failed to read XML file")
+            .replace("<InstanceID>0</InstanceID>", "<InstanceID>1</InstanceID>");
+        let response = Plain
+            .post_av_transport(xml.parse(), &context)
+            .await
+            .into_response();
+        assert!(
+            body_string(response)
+                .await
+                .contains("<errorCode>718</errorCode>")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_seek() {
+        let context = context();
+        Plain
+            .post_av_transport(get_xml("SetAVTransportURI.xml"), &context)
+            .await;
+        let seek_xml =
+            read_to_string("tests/AVTransport/Seek.xml").expect("Failed to read XML file");
+        let seek = |target: &str| {
+            seek_xml
+                .replace("<Target>12</Target>", &format!("<Target>{target}</Target>"))
+                .parse()
+        };
+        let response = Plain
+            .post_av_transport(seek("0:00:12"), &context)
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(body_string(response).await.contains("<u:SeekResponse"));
+        let response = Plain
+            .post_av_transport(get_xml("GetPositionInfo.xml"), &context)
+            .await
+            .into_response();
+        assert!(
+            body_string(response)
+                .await
+                .contains("<RelTime>00:00:12</RelTime>")
+        );
+
+        context.state().instance_mut(0).unwrap().track_duration = Some(Duration::from_secs(10));
+        let response = Plain
+            .post_av_transport(seek("0:00:12"), &context)
+            .await
+            .into_response();
+        assert!(
+            body_string(response)
+                .await
+                .contains("<errorCode>711</errorCode>")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_capabilities() {
+        let context = ControlContext::new(Arc::new(DMROptions {
+            capabilities: Capabilities {
+                can_seek: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+        context.notify_playing(0).await.expect("Instance 0 exists");
+        let response = Plain
+            .post_av_transport(get_xml("GetCurrentTransportActions.xml"), &context)
+            .await
+            .into_response();
+        assert!(
+            body_string(response)
+                .await
+                .contains("<Actions>Pause,Stop,Next,Previous</Actions>")
+        );
+
+        let response = Plain
+            .post_av_transport(get_xml("Seek.xml"), &context)
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(
+            body_string(response)
+                .await
+                .contains("<errorCode>710</errorCode>")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_volume_scaled() {
+        let response = Volume::default()
+            .post_rendering_control(
+                get_rendering_control_xml("GetVolume.xml"),
+                &volume_context(),
+            )
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(
+            body_string(response)
+                .await
+                .contains("<CurrentVolume>50</CurrentVolume>")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_initial_rendering_control_event() {
+        let context = volume_context();
+        // The tracked volume is left out in favor of the one answered to `GetVolume`
+        context
+            .state()
+            .instance_mut(0)
+            .unwrap()
+            .set_volume(Channel::Master, 80);
+        let event = initial_event(&Volume::default(), &context, Route::RenderingControl)
+            .await
+            .expect("RenderingControl is evented");
+        assert!(
+            event.contains("&lt;Volume channel=&quot;Master&quot; val=&quot;50&quot;/&gt;"),
+            "{event}"
+        );
+        // Unknown to the server, so tracked
+        assert!(
+            event.contains("&lt;Mute channel=&quot;Master&quot; val=&quot;0&quot;/&gt;"),
+            "{event}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_notify_volume() {
+        let context = context();
+        assert_eq!(context.notify_volume(0, Channel::Master, 70).await, Ok(0));
+        let response = Plain
+            .post_rendering_control(get_rendering_control_xml("GetVolume.xml"), &context)
+            .await
+            .into_response();
+        assert!(
+            body_string(response)
+                .await
+                .contains("<CurrentVolume>70</CurrentVolume>")
+        );
+        assert_eq!(
+            context.notify_volume(1, Channel::Master, 70).await,
+            Err(UpnpError::invalid_instance_id())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tracked_volume() {
+        let context = context();
+        let post = async |xml: String| {
+            let response = Plain
+                .post_rendering_control(xml.parse(), &context)
+                .await
+                .into_response();
+            assert_eq!(response.status(), StatusCode::OK);
+            body_string(response).await
+        };
+        let xml = |path: &str| {
+            read_to_string(format!("tests/RenderingControl/{path}"))
+                .expect("Failed to read XML file")
+        };
+        post(xml("SetVolume.xml").replace("<DesiredVolume>50<", "<DesiredVolume>30<")).await;
+        post(xml("SetMute.xml")).await;
+        assert!(
+            post(xml("GetVolume.xml"))
+                .await
+                .contains("<CurrentVolume>30</CurrentVolume>")
+        );
+        assert!(
+            post(xml("GetMute.xml"))
+                .await
+                .contains("<CurrentMute>1</CurrentMute>")
+        );
+        let event = initial_event(&Plain, &context, Route::RenderingControl)
+            .await
+            .expect("RenderingControl is evented");
+        assert!(
+            event.contains("&lt;Volume channel=&quot;Master&quot; val=&quot;30&quot;/&gt;"),
+            "{event}"
+        );
+        assert!(
+            event.contains("&lt;Mute channel=&quot;Master&quot; val=&quot;1&quot;/&gt;"),
+            "{event}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_volume_scaled() {
+        let server = Volume::default();
+        let response = server
+            .post_rendering_control(
+                get_rendering_control_xml("SetVolume.xml"),
+                &volume_context(),
+            )
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(*server.set.lock().unwrap(), [15]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_volume_cache() {
+        let server = Polled::default();
+        let context = context();
+        let get_volume = async || {
+            let response = server
+                .post_rendering_control(get_rendering_control_xml("GetVolume.xml"), &context)
+                .await
+                .into_response();
+            assert_eq!(response.status(), StatusCode::OK);
+            *server.queries.lock().unwrap()
+        };
+        assert_eq!(get_volume().await, 1);
+        assert_eq!(get_volume().await, 1);
+
+        let response = server
+            .post_rendering_control(get_rendering_control_xml("SetVolume.xml"), &context)
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(get_volume().await, 2);
+
+        tokio::time::advance(Duration::from_millis(context.options.volume_cache_ms)).await;
+        assert_eq!(get_volume().await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_device_capabilities() {
+        let response = Recorder::default()
+            .post_av_transport(get_xml("GetDeviceCapabilities.xml"), &context())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_string(response).await;
+        assert!(body.contains("<PlayMedia>NETWORK</PlayMedia>"));
+        assert!(body.contains("<RecMedia>NOT_IMPLEMENTED</RecMedia>"));
+        assert!(body.contains("<RecQualityModes>NOT_IMPLEMENTED</RecQualityModes>"));
+    }
+
+    #[tokio::test]
+    async fn test_play_speeds() {
+        let context = ControlContext::new(Arc::new(DMROptions {
+            supported_play_speeds: vec![PlaySpeed::One, PlaySpeed::Other("2".to_string())],
+            ..Default::default()
+        }));
+        for path in ["GetTransportSettings.xml", "GetDeviceCapabilities.xml"] {
+            let response = Recorder::default()
+                .post_av_transport(get_xml(path), &context)
+                .await
+                .into_response();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = body_string(response).await;
+            assert!(
+                body.contains("<X_DLNA_PlaySpeeds>1,2</X_DLNA_PlaySpeeds>"),
+                "{body}"
+            );
+        }
+
+        let play = read_to_string("tests/AVTransport/Play.xml")
+            .expect("Failed to read XML file")
+            .replace("<Speed>1</Speed>", "<Speed>-2</Speed>");
+        let response = Recorder::default()
+            .post_av_transport(play.parse(), &context)
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(
+            body_string(response)
+                .await
+                .contains("<errorCode>717</errorCode>")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_protocol_info() {
+        let envelope = r#"<?xml version="1.0"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+    <s:Body>
+        <u:GetProtocolInfo xmlns:u="urn:schemas-upnp-org:service:ConnectionManager:1"/>
+    </s:Body>
+</s:Envelope>"#;
+        let response = Recorder::default()
+            .post_ignore(envelope.to_string(), &context())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_string(response).await;
+        assert!(
+            body.contains("http-get:*:video/mp4:DLNA.ORG_OP=01;DLNA.ORG_FLAGS=01700000000000000000000000000000,"),
+            "{body}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connection_manager() {
+        let post = async |path: &str, connection_id: Option<&str>| {
+            let mut xml = read_to_string(format!("tests/ConnectionManager/{path}"))
+                .expect("Failed to read XML file");
+            if let Some(connection_id) = connection_id {
+                xml = xml.replace(
+                    "<ConnectionID>0</ConnectionID>",
+                    &format!("<ConnectionID>{connection_id}</ConnectionID>"),
+                );
+            }
+            Recorder::default()
+                .post_connection_manager(
+                    xml.parse(),
+                    &context().for_request(&Extensions::new(), &HeaderMap::new(), &xml),
+                )
+                .await
+                .into_response()
+        };
+
+        let response = post("GetProtocolInfo.xml", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_string(response).await;
+        assert!(body.contains("<Source></Source>"), "{body}");
+        assert!(body.contains("<Sink>http-get:*:video/mp4:"), "{body}");
+
+        let response = post("GetCurrentConnectionIDs.xml", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_string(response).await;
+        assert!(body.contains("<ConnectionIDs>0</ConnectionIDs>"), "{body}");
+
+        let response = post("GetCurrentConnectionInfo.xml", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_string(response).await;
+        assert!(
+            body.contains("<PeerConnectionID>-1</PeerConnectionID>"),
+            "{body}"
+        );
+        assert!(body.contains("<Direction>Input</Direction>"), "{body}");
+
+        let response = post("GetCurrentConnectionInfo.xml", Some("1")).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = body_string(response).await;
+        assert!(body.contains("<errorCode>706</errorCode>"), "{body}");
+    }
+
+    #[tokio::test]
+    async fn test_soap_prefix_echoed() {
+        let xml = read_to_string("tests/AVTransport/GetDeviceCapabilities.xml")
+            .expect("Failed to read XML file")
+            .replace("<s:", "<SOAP-ENV:")
+            .replace("</s:", "</SOAP-ENV:")
+            .replace(" s:", " SOAP-ENV:")
+            .replace("xmlns:s=", "xmlns:SOAP-ENV=");
+        let envelope: av_transport::AVTransportEnvelope =
+            xml.parse().expect("Failed to parse SOAP-ENV envelope");
+        assert_eq!(envelope.prefix, "SOAP-ENV");
+
+        let response = Recorder::default()
+            .post_av_transport(
+                xml.parse(),
+                &context().for_request(&Extensions::new(), &HeaderMap::new(), &xml),
+            )
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_string(response).await;
+        assert!(
+            body.contains(
+                r#"<SOAP-ENV:Envelope xmlns:SOAP-ENV="http://schemas.xmlsoap.org/soap/envelope/""#
+            ),
+            "{body}"
+        );
+        assert!(
+            body.ends_with("</SOAP-ENV:Body></SOAP-ENV:Envelope>"),
+            "{body}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_service_version_2() {
+        let xml = read_to_string("tests/AVTransport/GetDeviceCapabilities.xml")
+            .expect("Failed to read XML file")
+            .replace("AVTransport:1", "AVTransport:2");
+        let context = ControlContext::new(Arc::new(DMROptions {
+            service_versions: ServiceVersions {
+                av_transport: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        }));
+        let response = Recorder::default()
+            .post_av_transport(
+                xml.parse(),
+                &context.for_request(&Extensions::new(), &HeaderMap::new(), &xml),
+            )
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body_string(response).await;
+        assert!(
+            body.contains(
+                r#"<u:GetDeviceCapabilitiesResponse xmlns:u="urn:schemas-upnp-org:service:AVTransport:2""#
+            ),
+            "{body}"
+        );
+    }
+
+    /// A server accepting every play mode.
+    struct AnyPlayMode;
+
+    impl HTTPServer for AnyPlayMode {
+        async fn on_play_mode_set(
+            &self,
+            _instance_id: u32,
+            _play_mode: PlayMode,
+        ) -> Result<(), UpnpError> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_set_play_mode() {
+        let response = Recorder::default()
+            .post_av_transport(get_xml("SetPlayMode.xml"), &context())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(
+            body_string(response)
+                .await
+                .contains("<errorCode>712</errorCode>")
+        );
+
+        let context = context();
+        let response = AnyPlayMode
+            .post_av_transport(get_xml("SetPlayMode.xml"), &context)
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            context
+                .state()
+                .instance(0)
+                .map(|instance| instance.play_mode),
+            Some(PlayMode::RepeatAll)
+        );
+        let response = AnyPlayMode
+            .post_av_transport(get_xml("GetTransportSettings.xml"), &context)
+            .await
+            .into_response();
+        assert!(
+            body_string(response)
+                .await
+                .contains("<PlayMode>REPEAT_ALL</PlayMode>")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_fault() {
+        for (path, code) in [("Record.xml", 701), ("SetRecordQualityMode.xml", 713)] {
+            let response = Recorder::default()
+                .post_av_transport(get_xml(path), &context())
+                .await
+                .into_response();
+            assert_eq!(
+                response.status(),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "{path}"
+            );
+            assert!(
+                body_string(response)
+                    .await
+                    .contains(&format!("<errorCode>{code}</errorCode>")),
+                "{path}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unhandled_faults() {
+        let play = read_to_string("tests/AVTransport/Play.xml")
+            .expect("Failed to read XML file")
+            .replace("<Speed>1</Speed>", "");
+        let unknown = play.replace("u:Play", "u:Rewind");
+        for (action, code, description) in [
+            (get_xml("Next.xml"), 602, "Optional Action Not Implemented"),
+            (play.parse(), 402, "Invalid Args"),
+            (unknown.parse(), 401, "Invalid Action"),
+        ] {
+            let response = Recorder::default()
+                .post_av_transport(action, &context())
+                .await
+                .into_response();
+            assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+            let body = body_string(response).await;
+            assert!(
+                body.contains(
+                    "<s:Fault><faultcode>s:Client</faultcode><faultstring>UPnPError</faultstring>"
+                ),
+                "{body}"
+            );
+            assert!(
+                body.contains(&format!(
+                    "<errorCode>{code}</errorCode><errorDescription>{description}</errorDescription>"
+                )),
+                "{body}"
+            );
+        }
+
+        let response = Recorder::default()
+            .post_rendering_control(get_rendering_control_xml("GetVolumeDB.xml"), &context())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(
+            body_string(response)
+                .await
+                .contains("<errorCode>602</errorCode>")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_h2c() {
+        use http_body_util::{BodyExt, Full};
+        use hyper::{Request, body::Bytes, client::conn::http2};
+
+        let server: &'static Plain = Box::leak(Box::new(Plain));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let options = Arc::new(DMROptions {
+            http2: true,
+            ..Default::default()
+        });
+        tokio::spawn(server.serve_http(listener, options));
+
+        let stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        let (mut sender, connection) = http2::handshake(TokioExecutor::new(), TokioIo::new(stream))
+            .await
+            .expect("h2c handshake failed");
+        tokio::spawn(connection);
+        let envelope = read_to_string("tests/AVTransport/GetCurrentTransportActions.xml")
+            .expect("Failed to read XML file");
+        let request = Request::post(format!("http://{address}/AVTransport"))
+            .header("Content-Type", crate::response::XML_CONTENT_TYPE)
+            .body(Full::new(Bytes::from(envelope)))
+            .unwrap();
+        let response = sender
+            .send_request(request)
+            .await
+            .expect("h2c request failed");
+        assert_eq!(response.version(), hyper::Version::HTTP_2);
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(
+            String::from_utf8_lossy(&body).contains("<Actions></Actions>"),
+            "{body:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_description_cache() {
+        use crate::xml::device_description::DeviceDescription;
+
+        let options = DMROptions {
+            friendly_name: "Living Room".to_string(),
+            ..Default::default()
+        };
+        let context = ControlContext::new(Arc::new(options.clone()));
+        let rendered = DeviceDescription::from(&options)
+            .to_xml()
+            .expect("Failed to serialize the description");
+        assert_eq!(context.description().as_deref(), Ok(rendered.as_str()));
+
+        let reloaded = DMROptions {
+            friendly_name: "Kitchen".to_string(),
+            ..options
+        };
+        // Clones share the cache, as the router's handlers do
+        context
+            .clone()
+            .reload_description(&reloaded)
+            .expect("Failed to reload the description");
+        let response = Recorder::get_device_spec(&context).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            body_string(response).await,
+            DeviceDescription::from(&reloaded)
+                .to_xml()
+                .expect("Failed to serialize the description")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_route() {
+        use crate::client;
+        use axum::http::Method;
+
+        let server: &'static Plain = Box::leak(Box::new(Plain));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let options = Arc::new(DMROptions {
+            enabled_routes: [
+                Route::DeviceSpec,
+                Route::RenderingControl,
+                Route::AVTransport,
+            ]
+            .into(),
+            ..Default::default()
+        });
+        tokio::spawn(server.serve_http(listener, options));
+
+        let ignore = client::request(
+            Method::GET,
+            &format!("http://{address}/Ignore"),
+            &[],
+            String::new(),
+        )
+        .await
+        .expect("Request to /Ignore failed");
+        assert_eq!(ignore.status(), StatusCode::NOT_FOUND);
+
+        let envelope = read_to_string("tests/AVTransport/GetCurrentTransportActions.xml")
+            .expect("Failed to read XML file");
+        let av_transport = client::request(
+            Method::POST,
+            &format!("http://{address}/AVTransport"),
+            &[("Content-Type", crate::response::XML_CONTENT_TYPE)],
+            envelope,
+        )
+        .await
+        .expect("Request to /AVTransport failed");
+        assert_eq!(av_transport.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_description_xml() {
+        use crate::client;
+        use axum::http::{Method, header::CONTENT_TYPE};
+
+        let server: &'static Plain = Box::leak(Box::new(Plain));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let options = Arc::new(DMROptions {
+            description_path: "/renderer.xml".to_string(),
+            ..Default::default()
+        });
+        tokio::spawn(server.serve_http(listener, options));
+
+        for path in ["/renderer.xml", "/DeviceSpec", DESCRIPTION_XML_PATH] {
+            let response = client::request(
+                Method::GET,
+                &format!("http://{address}{path}"),
+                &[],
+                String::new(),
+            )
+            .await
+            .unwrap_or_else(|e| panic!("Request to {path} failed: {e}"));
+            assert_eq!(response.status(), StatusCode::OK, "{path}");
+            assert_eq!(
+                response.headers()[CONTENT_TYPE],
+                crate::response::XML_CONTENT_TYPE,
+                "{path}"
+            );
+            assert!(
+                String::from_utf8_lossy(response.body()).contains("<deviceType>"),
+                "{path}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_subscription() {
+        use crate::client;
+
+        let server: &'static Plain = Box::leak(Box::new(Plain));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(server.serve_http(listener, Arc::new(DMROptions::default())));
+        let url = format!("http://{address}/AVTransport/event");
+        let subscribe = Method::from_bytes(b"SUBSCRIBE").unwrap();
+        let unsubscribe = Method::from_bytes(b"UNSUBSCRIBE").unwrap();
+
+        // A controller receiving the events
+        let (sender, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let callback = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let callback_url = format!("<http://{}/events>", callback.local_addr().unwrap());
+        let controller = Router::new().route(
+            "/events",
+            any(async move |headers: HeaderMap, body: String| {
+                sender.send((headers, body)).unwrap();
+            }),
+        );
+        tokio::spawn(async move { axum::serve(callback, controller).await });
+
+        let response = client::request(
+            subscribe.clone(),
+            &url,
+            &[
+                ("CALLBACK", &callback_url),
+                ("NT", "upnp:event"),
+                ("TIMEOUT", "Second-300"),
+            ],
+            String::new(),
+        )
+        .await
+        .expect("SUBSCRIBE failed");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["TIMEOUT"], "Second-300");
+        let sid = response.headers()["SID"].to_str().unwrap().to_string();
+
+        let (headers, body) = received.recv().await.unwrap();
+        assert_eq!(headers["SID"], sid.as_str());
+        assert_eq!(headers["SEQ"], "0");
+        assert!(
+            body.contains("&lt;TransportState val=&quot;NO_MEDIA_PRESENT&quot;/&gt;"),
+            "{body}"
+        );
+
+        let renew = async || {
+            client::request(subscribe.clone(), &url, &[("SID", &sid)], String::new())
+                .await
+                .expect("Renewing SUBSCRIBE failed")
+                .status()
+        };
+        assert_eq!(renew().await, StatusCode::OK);
+
+        let response = client::request(unsubscribe, &url, &[("SID", &sid)], String::new())
+            .await
+            .expect("UNSUBSCRIBE failed");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(renew().await, StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[tokio::test]
+    async fn test_notify_playing() {
+        let get_transport_state = async |context: &ControlContext| {
+            let response = Plain
+                .post_av_transport(get_xml("GetTransportInfo.xml"), context)
+                .await
+                .into_response();
+            let body = body_string(response).await;
+            let start = body.find("<CurrentTransportState>").unwrap();
+            let end = body.find("</CurrentTransportState>").unwrap();
+            body[start + "<CurrentTransportState>".len()..end].to_string()
+        };
+        let context = context();
+        assert_eq!(get_transport_state(&context).await, "NO_MEDIA_PRESENT");
+
+        // A controller subscribed to the events
+        let (sender, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let callback = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let callback_url = format!("http://{}/events", callback.local_addr().unwrap());
+        let controller = Router::new().route(
+            "/events",
+            any(async move |body: String| sender.send(body).unwrap()),
+        );
+        tokio::spawn(async move { axum::serve(callback, controller).await });
+        context.subscriptions().subscribe(
+            Route::AVTransport,
+            vec![callback_url],
+            Duration::from_mins(5),
+        );
+
+        assert_eq!(context.notify_playing(0).await, Ok(1));
+        assert_eq!(get_transport_state(&context).await, "PLAYING");
+        let body = received.recv().await.unwrap();
+        assert!(
+            body.contains("&lt;TransportState val=&quot;PLAYING&quot;/&gt;"),
+            "{body}"
+        );
+        context.notify_paused(0).await.expect("Instance 0 exists");
+        assert_eq!(get_transport_state(&context).await, "PAUSED_PLAYBACK");
+        assert_eq!(
+            context.notify_stopped(1).await,
+            Err(UpnpError::invalid_instance_id())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_description_cache_headers() {
+        use crate::client;
+        use axum::http::Method;
+
+        let server: &'static Plain = Box::leak(Box::new(Plain));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let options = Arc::new(DMROptions {
+            description_max_age: Some(600),
+            ..Default::default()
+        });
+        tokio::spawn(server.serve_http(listener, options));
+
+        for path in ["DeviceSpec", "AVTransport"] {
+            let url = format!("http://{address}/{path}");
+            let response = client::request(Method::GET, &url, &[], String::new())
+                .await
+                .expect("Request failed");
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()[CACHE_CONTROL], "max-age=600");
+            let etag = response.headers()[ETAG].to_str().unwrap().to_string();
+
+            let cached = client::request(
+                Method::GET,
+                &url,
+                &[("If-None-Match", &etag)],
+                String::new(),
+            )
+            .await
+            .expect("Conditional request failed");
+            assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+            assert!(cached.body().is_empty());
+
+            let stale = client::request(
+                Method::GET,
+                &url,
+                &[("If-None-Match", "\"stale\"")],
+                String::new(),
+            )
+            .await
+            .expect("Conditional request failed");
+            assert_eq!(stale.status(), StatusCode::OK);
+            assert_eq!(stale.headers()[ETAG], etag.as_str());
+        }
+    }
+
+    /// Log messages captured by [`capture_logs`], from all tests.
+    static LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    /// A logger recording messages into [`LOGS`].
+    struct Capture;
+
+    impl log::Log for Capture {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            LOGS.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Starts capturing debug messages into [`LOGS`].
+    fn capture_logs() {
+        if log::set_logger(&Capture).is_ok() {
+            log::set_max_level(log::LevelFilter::Debug);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unknown_post() {
+        use crate::client;
+
+        capture_logs();
+        let server: &'static Plain = Box::leak(Box::new(Plain));
+        for (unknown_post, status) in [
+            (UnknownPost::NotFound, StatusCode::NOT_FOUND),
+            (UnknownPost::Fault, StatusCode::INTERNAL_SERVER_ERROR),
+        ] {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            let options = Arc::new(DMROptions {
+                unknown_post,
+                ..Default::default()
+            });
+            tokio::spawn(server.serve_http(listener, options));
+
+            let response = client::request(
+                Method::POST,
+                &format!("http://{address}/Unknown"),
+                &[],
+                "<probe/>".to_string(),
+            )
+            .await
+            .expect("Request to /Unknown failed");
+            assert_eq!(response.status(), status, "{unknown_post:?}");
+            let body = String::from_utf8_lossy(response.body());
+            match unknown_post {
+                UnknownPost::NotFound => assert!(body.is_empty()),
+                UnknownPost::Fault => assert!(body.contains("<errorCode>401</errorCode>")),
+            }
+        }
+        assert!(
+            LOGS.lock()
+                .unwrap()
+                .iter()
+                .any(|message| message == "POST request to unknown path /Unknown: <probe/>")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_soap_action_dispatch() {
+        use crate::client;
+
+        let server: &'static Plain = Box::leak(Box::new(Plain));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/AVTransport", listener.local_addr().unwrap());
+        tokio::spawn(server.serve_http(listener, Arc::new(DMROptions::default())));
+
+        // Without `encodingStyle`, which only parses if dispatched by the header
+        let envelope = read_to_string("tests/AVTransport/GetCurrentTransportActions.xml")
+            .expect("Failed to read XML file")
+            .replace(
+                r#" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/""#,
+                "",
+            );
+        let header = format!(
+            r#""{}#GetCurrentTransportActions""#,
+            av_transport::SERVICE_TYPE
+        );
+        let unknown = format!(r#""{}#Rewind""#, av_transport::SERVICE_TYPE);
+        for (headers, status, expected) in [
+            (
+                vec![("SOAPACTION", header.as_str())],
+                StatusCode::OK,
+                "<Actions></Actions>",
+            ),
+            (
+                vec![("SOAPACTION", unknown.as_str())],
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "<errorCode>401</errorCode>",
+            ),
+            (
+                vec![],
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "<errorCode>402</errorCode>",
+            ),
+        ] {
+            let response = client::request(Method::POST, &url, &headers, envelope.clone())
+                .await
+                .expect("Request to /AVTransport failed");
+            assert_eq!(response.status(), status, "{headers:?}");
+            let body = String::from_utf8_lossy(response.body());
+            assert!(body.contains(expected), "{headers:?}: {body}");
+        }
+
+        let mut headers = HeaderMap::new();
+        headers.insert("SOAPACTION", HeaderValue::from_str(&header).unwrap());
+        let context = context().for_request(&Extensions::new(), &headers, &envelope);
+        assert_eq!(
+            context.soap_action().map(|action| action.action.as_str()),
+            Some("GetCurrentTransportActions")
+        );
+    }
+
+    /// A server recording the controllers that sent `AVTransport` actions.
+    #[derive(Default)]
+    struct Peers {
+        peers: Mutex<Vec<Option<SocketAddr>>>,
+    }
+
+    impl HTTPServer for Peers {
+        async fn post_av_transport(
+            &self,
+            _av_transport: Result<AVTransport, ParseError>,
+            context: &ControlContext,
+        ) -> impl IntoResponse {
+            self.peers.lock().unwrap().push(context.peer());
+            StatusCode::OK
+        }
+    }
+
+    #[tokio::test]
+    async fn test_peer() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let server: &'static Peers = Box::leak(Box::default());
+        let envelope =
+            read_to_string("tests/AVTransport/Stop.xml").expect("Failed to read XML file");
+        // Both the plain HTTP/1.1 server and the auto-detecting one used for h2c
+        for http2 in [false, true] {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            let options = Arc::new(DMROptions {
+                http2,
+                ..Default::default()
+            });
+            tokio::spawn(server.serve_http(listener, options));
+
+            let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+            let request = format!(
+                "POST /AVTransport HTTP/1.1\r\nHost: {address}\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{envelope}",
+                envelope.len()
+            );
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+            assert_eq!(
+                server.peers.lock().unwrap().pop(),
+                Some(Some(stream.local_addr().unwrap())),
+                "http2: {http2}"
+            );
+        }
+        assert_eq!(context().peer(), None);
+    }
+
+    #[tokio::test]
+    async fn test_expect_continue() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let server: &'static Plain = Box::leak(Box::new(Plain));
+        let envelope = read_to_string("tests/AVTransport/GetCurrentTransportActions.xml")
+            .expect("Failed to read XML file");
+        // Both the plain HTTP/1.1 server and the auto-detecting one used for h2c
+        for http2 in [false, true] {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            let options = Arc::new(DMROptions {
+                http2,
+                ..Default::default()
+            });
+            tokio::spawn(server.serve_http(listener, options));
+
+            let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+            let head = format!(
+                "POST /AVTransport HTTP/1.1\r\nHost: {address}\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nExpect: 100-continue\r\nConnection: close\r\n\r\n",
+                envelope.len()
+            );
+            stream.write_all(head.as_bytes()).await.unwrap();
+            let mut buf = [0u8; 64];
+            let size = tokio::time::timeout(Duration::from_secs(1), stream.read(&mut buf))
+                .await
+                .expect("Timed out waiting for 100 Continue")
+                .unwrap();
+            assert_eq!(&buf[..size], b"HTTP/1.1 100 Continue\r\n\r\n");
+
+            stream.write_all(envelope.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            tokio::time::timeout(Duration::from_secs(1), stream.read_to_string(&mut response))
+                .await
+                .expect("Timed out waiting for the response")
+                .unwrap();
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+            assert!(response.contains("<Actions></Actions>"), "{response}");
+        }
+    }
+}
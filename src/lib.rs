@@ -59,37 +59,159 @@
 #![warn(clippy::all, clippy::nursery, clippy::pedantic, clippy::cargo)]
 #![allow(clippy::multiple_crate_versions, reason = "Dependencies' requirements")]
 
+mod builder;
+mod cache;
+mod capabilities;
+mod client;
+mod config;
+mod control_event;
 mod defaults;
+mod error;
+mod eventing;
+mod follower;
 mod http;
+pub mod media;
+pub mod response;
+mod runtime;
+mod self_check;
+mod service_versions;
 mod ssdp;
+pub mod state;
+mod template;
+pub mod uri;
 pub mod xml;
 
 pub use axum::response::Response;
-pub use http::HTTPServer;
+pub use builder::DMROptionsBuilder;
+pub use capabilities::Capabilities;
+pub use config::ConfigFormat;
+pub use control_event::{ControlAction, ControlEvent};
+pub use error::{ConfigError, DMROptionsError, DmrError, ParseError, UpnpError};
+pub use eventing::{Subscription, Subscriptions};
+pub use follower::FollowerClient;
+pub use http::{ControlContext, DESCRIPTION_XML_PATH, HTTPServer, Route, UnknownPost};
+use ipnet::IpNet;
 use log::{error, info};
+use runtime::Runtime;
 use serde::{Deserialize, Serialize};
+pub use service_versions::ServiceVersions;
 use ssdp::SSDPServer;
+pub use ssdp::SearchLatency;
 use std::{
-    net::{Ipv4Addr, SocketAddrV4},
+    collections::BTreeSet,
+    future::pending,
+    io::{Error, ErrorKind, Result as IoResult},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
     sync::Arc,
-    io::Result as IoResult,
+    time::Duration,
 };
+pub use template::{DEVICE_SPEC, render_device_spec};
+use tokio::{net::TcpListener, sync::broadcast};
+use xml::{av_transport::PlaySpeed, protocol_info::ProtocolInfo};
 
 /// Options for a DMR instance.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(
+    clippy::struct_excessive_bools,
+    reason = "Independent toggles, mirroring the config file"
+)]
 pub struct DMROptions {
-    /// Local IP.
+    /// Local IP, defaulting to the address of the local machine, or `127.0.0.1` if it can't be determined. With an IPv6 address, SSDP uses the `ff02::c` (link-local) and `ff05::c` (site-local) groups on the default interface instead of `239.255.255.250`.
     #[serde(default = "defaults::ip")]
-    pub ip: Ipv4Addr,
+    pub ip: IpAddr,
+    /// IPv4 addresses of the interfaces SSDP joins the multicast group on and sends announcements out of, e.g. `["192.168.1.20", "10.0.0.5"]` to be discovered on both the wired and the Wi-Fi network. If unset, only the interface of `ip` is used, or all IPv4 interfaces if `ip` is `0.0.0.0`, in which case each announces its own address as `LOCATION`. Search responses are routed by the OS either way.
+    #[serde(default = "defaults::interfaces")]
+    pub interfaces: Option<Vec<Ipv4Addr>>,
     /// The SSDP server port.
     #[serde(default = "defaults::ssdp_port")]
     pub ssdp_port: u16,
+    /// Whether to bind the SSDP server to an ephemeral port if `ssdp_port` is already in use, e.g. by the SSDP service of the OS. The renderer can still announce itself then, but won't receive multicast searches.
+    #[serde(default = "defaults::ssdp_port_fallback")]
+    pub ssdp_port_fallback: bool,
+    /// Whether to also multicast `ssdp:alive` messages when a controller searches for the first time, for controllers that only catch announcements. Bursts are sent at most every 5 seconds.
+    #[serde(default = "defaults::ssdp_alive_on_new_search")]
+    pub ssdp_alive_on_new_search: bool,
+    /// Additional search targets to announce and answer, as pairs of `ST` (e.g. `urn:my-company:service:Foo:1`) and `USN` (e.g. `uuid:{uuid}::urn:my-company:service:Foo:1`, with the actual UUID), for vendor-specific controllers. Both are used verbatim.
+    #[serde(default = "defaults::extra_search_targets")]
+    pub extra_search_targets: Vec<(String, String)>,
+    /// Subnets whose controllers get answers to their searches, e.g. `["172.16.0.0/12"]` to ignore searches from a guest network. Searches from everywhere are answered if unset. Announcements are sent regardless.
+    #[serde(default = "defaults::respond_to_subnets")]
+    pub respond_to_subnets: Option<Vec<IpNet>>,
+    /// Random jitter applied to the interval between `ssdp:alive` announcements, as a percentage below `100`, so that renderers started simultaneously desynchronize over time. The average interval stays the same.
+    #[serde(default = "defaults::ssdp_keep_alive_jitter")]
+    pub ssdp_keep_alive_jitter: u8,
+    /// How long controllers may consider the renderer available after an announcement or search response, in seconds, sent as their `CACHE-CONTROL: max-age`. Raising it keeps the renderer listed by controllers that miss announcements on flaky networks.
+    #[serde(default = "defaults::ssdp_cache_max_age")]
+    pub ssdp_cache_max_age: u32,
+    /// Interval between `ssdp:alive` announcements, in milliseconds, before jitter. A third of [`ssdp_cache_max_age`](DMROptions::ssdp_cache_max_age) if unset, so that controllers missing an announcement or two still don't expire the renderer.
+    #[serde(default = "defaults::ssdp_keep_alive_ms")]
+    pub ssdp_keep_alive_ms: Option<u64>,
+    /// Number of times the full set of `ssdp:alive` announcements is sent on startup, a few hundred milliseconds apart, and of `ssdp:byebye` ones on shutdown, since multicast messages are frequently dropped. Must not be `0`.
+    #[serde(default = "defaults::ssdp_announce_repeats")]
+    pub ssdp_announce_repeats: u8,
+    /// `SERVER` header of SSDP announcements and search responses, which should take the `OS/version UPnP/1.0 product/version` form, as strict controllers look for the `UPnP/1.0` token. `Rust/1 UPnP/1.0 dlna-dmr/{version}` if unset.
+    #[serde(default = "defaults::server_string")]
+    pub server_string: Option<String>,
+    /// Size of the buffer SSDP messages are received into, in bytes. Longer messages are truncated.
+    #[serde(default = "defaults::ssdp_recv_buffer")]
+    pub ssdp_recv_buffer: usize,
     /// The HTTP server port.
     #[serde(default = "defaults::http_port")]
     pub http_port: u16,
-    /// The UUID of the DMR instance.
+    /// Local address the HTTP server binds to, e.g. `0.0.0.0` in a container, while SSDP keeps advertising `ip` in `LOCATION`. Defaults to `ip`, whose family it must share. Controllers must still reach the HTTP server via the advertised address (or [`advertise_location`](DMROptions::advertise_location)), so the bind address should include it.
+    #[serde(default = "defaults::http_bind_ip")]
+    pub http_bind_ip: Option<IpAddr>,
+    /// Whether to also accept HTTP/2 over cleartext (h2c, with prior knowledge) besides HTTP/1.1, for controllers that multiplex requests over a single connection.
+    #[serde(default = "defaults::http2")]
+    pub http2: bool,
+    /// Default routes mounted by the HTTP server, all by default. Minimal renderers may disable e.g. the legacy `/Ignore` route; a warning is logged at startup for each disabled route that's still advertised in the device description.
+    #[serde(default = "defaults::enabled_routes")]
+    pub enabled_routes: BTreeSet<Route>,
+    /// How POST requests to paths without a route are answered, e.g. when controllers probe for services the renderer doesn't have. Their bodies are logged at debug level either way. `404 Not Found` by default.
+    #[serde(default = "defaults::unknown_post")]
+    pub unknown_post: UnknownPost,
+    /// How long controllers may cache the device description and service descriptions, in seconds. If set, they're served with `ETag` and `Cache-Control: max-age` headers, and conditional requests with a matching `If-None-Match` are answered with `304 Not Modified`. No cache headers are sent by default.
+    #[serde(default = "defaults::description_max_age")]
+    pub description_max_age: Option<u64>,
+    /// Base URL advertised to controllers instead of `http://{ip}:{http_port}`, e.g. `http://192.168.1.20:8080` behind a reverse proxy or with Docker port mapping. Used verbatim (without trailing slashes) as the prefix of the SSDP `LOCATION` and of the service URLs in the device description.
+    #[serde(default = "defaults::advertise_location")]
+    pub advertise_location: Option<String>,
+    /// Path the device description is served at, which the SSDP `LOCATION` of announcements and search responses points to. The description is served at `/DeviceSpec` and [`DESCRIPTION_XML_PATH`] as well, for controllers expecting it there. `/DeviceSpec` by default.
+    #[serde(default = "defaults::description_path")]
+    pub description_path: String,
+    /// Whether to fetch the device description via `ip` once started, logging a prominent error if it isn't reachable, e.g. because `ip` belongs to the wrong interface.
+    #[serde(default = "defaults::startup_self_check")]
+    pub startup_self_check: bool,
+    /// Optional actions the renderer supports. Disabled ones are left out of `GetCurrentTransportActions` and answered with a fault if sent anyway. All are enabled by default.
+    #[serde(default = "defaults::capabilities")]
+    pub capabilities: Capabilities,
+    /// Versions of the services advertised over SSDP and in the device description, e.g. `{ av_transport = 2 }` in the config file for controllers ignoring renderers that only offer version `1`. Responses use the same versions, while requests of any version are accepted. All are `1` by default.
+    #[serde(default = "defaults::service_versions")]
+    pub service_versions: ServiceVersions,
+    /// Playback speeds accepted by `Play`, e.g. `["1", "2", "-2"]` in the config file, advertised to controllers by `GetTransportSettings` and `GetDeviceCapabilities`. Other speeds are answered with [`UpnpError::play_speed_not_supported`]. Only normal speed by default.
+    #[serde(default = "defaults::supported_play_speeds")]
+    pub supported_play_speeds: Vec<PlaySpeed>,
+    /// Formats the renderer accepts, advertised as the `Sink` of `ConnectionManager::GetProtocolInfo`, e.g. `["http-get:*:video/mp4:DLNA.ORG_OP=01;DLNA.ORG_FLAGS=01700000000000000000000000000000"]` in the config file. Some servers refuse to stream without matching DLNA parameters, see [`DlnaParams`](xml::protocol_info::DlnaParams). Defaults to common audio and video formats, with byte-range seeking.
+    #[serde(default = "defaults::sink_protocol_info")]
+    pub sink_protocol_info: Vec<ProtocolInfo>,
+    /// How long the values answering `GetVolume` and `GetMute` are reused, in milliseconds, so that controllers polling frequently don't invoke [`HTTPServer::volume`] and [`HTTPServer::mute`] each time. Any `SetVolume` or `SetMute` invalidates them right away. `0` disables caching.
+    #[serde(default = "defaults::volume_cache_ms")]
+    pub volume_cache_ms: u64,
+    /// Whether to check that `http:` URIs set via `SetAVTransportURI` or `SetNextAVTransportURI` are reachable with a `HEAD` request, after [`HTTPServer::validate_uri`] accepted them. See [`uri::check_reachable`] for the faults controllers get otherwise.
+    #[serde(default = "defaults::check_uri_reachable")]
+    pub check_uri_reachable: bool,
+    /// How long the `HEAD` request checking URIs may take, in milliseconds, see [`check_uri_reachable`](DMROptions::check_uri_reachable).
+    #[serde(default = "defaults::uri_validation_timeout_ms")]
+    pub uri_validation_timeout_ms: u64,
+    /// The maximum of the hardware volume range. Volumes are scaled between `0..=volume_max` and the range `0..=100` seen by controllers.
+    #[serde(default = "defaults::volume_max")]
+    pub volume_max: u16,
+    /// The UUID of the DMR instance. Ignored if [`uuid_seed`](DMROptions::uuid_seed) is set.
     #[serde(default = "defaults::uuid")]
     pub uuid: String,
+    /// Seed to derive a stable UUID from, e.g. the machine ID or the friendly name, so that the renderer keeps its identity across restarts without a configured `uuid`. See [`DMROptions::derive_uuid`].
+    #[serde(default = "defaults::uuid_seed")]
+    pub uuid_seed: Option<String>,
     /// Friendly name of the DMR instance.
     #[serde(default = "defaults::friendly_name")]
     pub friendly_name: String,
@@ -117,9 +239,37 @@ impl Default for DMROptions {
     fn default() -> Self {
         Self {
             ip: defaults::ip(),
+            interfaces: defaults::interfaces(),
             ssdp_port: defaults::ssdp_port(),
+            ssdp_port_fallback: defaults::ssdp_port_fallback(),
+            ssdp_alive_on_new_search: defaults::ssdp_alive_on_new_search(),
+            extra_search_targets: defaults::extra_search_targets(),
+            respond_to_subnets: defaults::respond_to_subnets(),
+            ssdp_keep_alive_jitter: defaults::ssdp_keep_alive_jitter(),
+            ssdp_cache_max_age: defaults::ssdp_cache_max_age(),
+            ssdp_keep_alive_ms: defaults::ssdp_keep_alive_ms(),
+            ssdp_announce_repeats: defaults::ssdp_announce_repeats(),
+            server_string: defaults::server_string(),
+            ssdp_recv_buffer: defaults::ssdp_recv_buffer(),
             http_port: defaults::http_port(),
+            http_bind_ip: defaults::http_bind_ip(),
+            http2: defaults::http2(),
+            enabled_routes: defaults::enabled_routes(),
+            unknown_post: defaults::unknown_post(),
+            description_max_age: defaults::description_max_age(),
+            advertise_location: defaults::advertise_location(),
+            description_path: defaults::description_path(),
+            startup_self_check: defaults::startup_self_check(),
+            capabilities: defaults::capabilities(),
+            service_versions: defaults::service_versions(),
+            supported_play_speeds: defaults::supported_play_speeds(),
+            sink_protocol_info: defaults::sink_protocol_info(),
+            volume_cache_ms: defaults::volume_cache_ms(),
+            check_uri_reachable: defaults::check_uri_reachable(),
+            uri_validation_timeout_ms: defaults::uri_validation_timeout_ms(),
+            volume_max: defaults::volume_max(),
             uuid: defaults::uuid(),
+            uuid_seed: defaults::uuid_seed(),
             friendly_name: defaults::friendly_name(),
             model_name: defaults::model_name(),
             model_description: defaults::model_description(),
@@ -131,39 +281,902 @@ impl Default for DMROptions {
     }
 }
 
+impl DMROptions {
+    /// Starts building options from the defaults, validated once built. See [`DMROptionsBuilder`].
+    #[must_use]
+    pub fn builder() -> DMROptionsBuilder {
+        DMROptionsBuilder::default()
+    }
+
+    /// Checks the options for mistakes that would otherwise only surface once controllers try to reach the renderer.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DmrError::Config`] if:
+    ///
+    /// - [`advertise_location`](DMROptions::advertise_location) isn't an absolute `http` or `https` URL without query or fragment.
+    /// - [`uuid_seed`](DMROptions::uuid_seed) is empty, or unset while [`uuid`](DMROptions::uuid) isn't a valid UUID (see [`DMROptions::normalize_uuid`]).
+    /// - Any of [`service_versions`](DMROptions::service_versions) is `0`.
+    /// - [`description_path`](DMROptions::description_path) isn't an absolute path distinct from the other routes.
+    /// - [`ssdp_keep_alive_jitter`](DMROptions::ssdp_keep_alive_jitter) isn't below `100`.
+    /// - [`ssdp_announce_repeats`](DMROptions::ssdp_announce_repeats) is `0`.
+    /// - [`ssdp_cache_max_age`](DMROptions::ssdp_cache_max_age) or [`ssdp_keep_alive_ms`](DMROptions::ssdp_keep_alive_ms) is `0`.
+    /// - [`ssdp_cache_max_age`](DMROptions::ssdp_cache_max_age) isn't longer than [`ssdp_keep_alive_ms`](DMROptions::ssdp_keep_alive_ms).
+    /// - [`interfaces`](DMROptions::interfaces) is empty, or set along with an IPv6 `ip`.
+    /// - [`http_bind_ip`](DMROptions::http_bind_ip) isn't of the same family as `ip`.
+    /// - [`server_string`](DMROptions::server_string) is empty or spans multiple lines.
+    /// - Any of [`extra_search_targets`](DMROptions::extra_search_targets) is empty or spans multiple lines.
+    pub fn validate(&self) -> Result<(), DmrError> {
+        for (st, usn) in &self.extra_search_targets {
+            if [st, usn]
+                .iter()
+                .any(|value| value.trim().is_empty() || value.contains(['\r', '\n']))
+            {
+                return Err(DmrError::Config(format!(
+                    "Invalid extra search target ({st:?}, {usn:?}): must be non-empty single lines"
+                )));
+            }
+        }
+        let path = &self.description_path;
+        if !path.starts_with('/') || path.contains(['?', '#']) || path.contains(char::is_whitespace)
+        {
+            return Err(DmrError::Config(format!(
+                "Invalid `description_path` {path:?}: expected an absolute path without query or fragment"
+            )));
+        }
+        if Route::ALL
+            .iter()
+            .any(|route| *route != Route::DeviceSpec && route.path() == path)
+        {
+            return Err(DmrError::Config(format!(
+                "`description_path` {path:?} conflicts with another route"
+            )));
+        }
+        if let Some(server) = &self.server_string
+            && (server.trim().is_empty() || server.contains(['\r', '\n']))
+        {
+            return Err(DmrError::Config(format!(
+                "Invalid `server_string` {server:?}: must be a non-empty single line"
+            )));
+        }
+        match &self.interfaces {
+            Some(interfaces) if interfaces.is_empty() => {
+                return Err(DmrError::Config(
+                    "`interfaces` must not be empty, leave it unset instead".to_string(),
+                ));
+            }
+            Some(_) if self.ip.is_ipv6() => {
+                return Err(DmrError::Config(
+                    "`interfaces` are IPv4 addresses, which can't be used with an IPv6 `ip`"
+                        .to_string(),
+                ));
+            }
+            _ => {}
+        }
+        if let Some(bind_ip) = self.http_bind_ip
+            && bind_ip.is_ipv4() != self.ip.is_ipv4()
+        {
+            return Err(DmrError::Config(format!(
+                "`http_bind_ip` {bind_ip} must be of the same family as `ip` {}",
+                self.ip
+            )));
+        }
+        self.validate_ssdp_timing()?;
+        if !self.service_versions.is_valid() {
+            return Err(DmrError::Config(format!(
+                "`service_versions` must be at least 1, got {:?}",
+                self.service_versions
+            )));
+        }
+        if self
+            .uuid_seed
+            .as_deref()
+            .is_some_and(|seed| seed.trim().is_empty())
+        {
+            return Err(DmrError::Config(
+                "`uuid_seed` must not be empty".to_string(),
+            ));
+        }
+        self.effective_uuid()?;
+        if let Some(location) = &self.advertise_location {
+            let invalid = |reason: &str| {
+                DmrError::Config(format!(
+                    "Invalid `advertise_location` {location:?}: {reason}"
+                ))
+            };
+            let uri: axum::http::Uri = location.parse().map_err(|e| invalid(&format!("{e}")))?;
+            if !matches!(uri.scheme_str(), Some("http" | "https")) {
+                return Err(invalid("expected an http or https URL"));
+            }
+            if uri.host().is_none_or(str::is_empty) {
+                return Err(invalid("missing host"));
+            }
+            if uri.query().is_some() || location.contains('#') {
+                return Err(invalid("query and fragment are not allowed"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the options timing SSDP announcements, see [`Self::validate`].
+    fn validate_ssdp_timing(&self) -> Result<(), DmrError> {
+        if self.ssdp_keep_alive_jitter >= 100 {
+            return Err(DmrError::Config(format!(
+                "`ssdp_keep_alive_jitter` must be below 100, got {}",
+                self.ssdp_keep_alive_jitter
+            )));
+        }
+        if self.ssdp_cache_max_age == 0 {
+            return Err(DmrError::Config(
+                "`ssdp_cache_max_age` must not be 0".to_string(),
+            ));
+        }
+        if self.ssdp_announce_repeats == 0 {
+            return Err(DmrError::Config(
+                "`ssdp_announce_repeats` must not be 0".to_string(),
+            ));
+        }
+        if self.ssdp_keep_alive_ms == Some(0) {
+            return Err(DmrError::Config(
+                "`ssdp_keep_alive_ms` must not be 0".to_string(),
+            ));
+        }
+        if let Some(keep_alive) = self.ssdp_keep_alive_ms
+            && keep_alive >= u64::from(self.ssdp_cache_max_age) * 1000
+        {
+            return Err(DmrError::Config(format!(
+                "`ssdp_keep_alive_ms` ({keep_alive}) must be shorter than `ssdp_cache_max_age` ({}s), or controllers expire the renderer between announcements",
+                self.ssdp_cache_max_age
+            )));
+        }
+        Ok(())
+    }
+
+    /// Normalizes a configured UUID by trimming it and stripping the `uuid:` prefix of UDNs and USNs, if present, so that it isn't doubled when advertised.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`DmrError::Config`] if the remainder isn't a valid UUID.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use dlna_dmr::DMROptions;
+    ///
+    /// let uuid = "12345678-1234-5678-1234-567812345678";
+    /// assert_eq!(DMROptions::normalize_uuid(uuid).unwrap(), uuid);
+    /// assert_eq!(DMROptions::normalize_uuid(&format!("uuid:{uuid}")).unwrap(), uuid);
+    /// assert!(DMROptions::normalize_uuid("uuid:living-room").is_err());
+    /// ```
+    pub fn normalize_uuid(uuid: &str) -> Result<String, DmrError> {
+        let trimmed = uuid.trim();
+        let bare = trimmed
+            .get(..5)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("uuid:"))
+            .map_or(trimmed, |_| &trimmed[5..]);
+        uuid::Uuid::try_parse(bare).map_err(|e| {
+            DmrError::Config(format!(
+                "Invalid `uuid` {uuid:?}: {e}, expected a UUID like `12345678-1234-5678-1234-567812345678`"
+            ))
+        })?;
+        Ok(bare.to_string())
+    }
+
+    /// The UUID to advertise: derived from [`uuid_seed`](DMROptions::uuid_seed) if set, otherwise the normalized [`uuid`](DMROptions::uuid).
+    fn effective_uuid(&self) -> Result<String, DmrError> {
+        self.uuid_seed.as_deref().map_or_else(
+            || Self::normalize_uuid(&self.uuid),
+            |seed| Ok(Self::derive_uuid(seed)),
+        )
+    }
+
+    /// Derives a UUID v5 from given seed in a namespace specific to this crate. The same seed always yields the same UUID.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use dlna_dmr::DMROptions;
+    ///
+    /// let uuid = DMROptions::derive_uuid("Living Room");
+    /// assert_eq!(uuid, DMROptions::derive_uuid("Living Room"));
+    /// assert_ne!(uuid, DMROptions::derive_uuid("Kitchen"));
+    /// ```
+    #[must_use]
+    pub fn derive_uuid(seed: &str) -> String {
+        /// `UUID` v5 of `https://github.com/PRO-2684/dlna-dmr` in the URL namespace.
+        const NAMESPACE: uuid::Uuid = uuid::uuid!("5cbe69e1-6185-5d71-9ce6-93d1e8af91dc");
+        uuid::Uuid::new_v5(&NAMESPACE, seed.as_bytes()).to_string()
+    }
+
+    /// The base URL advertised to controllers if overridden, without trailing slashes; otherwise empty, i.e. URLs are relative to the description.
+    fn base_url(&self) -> &str {
+        self.advertise_location
+            .as_deref()
+            .map_or("", |location| location.trim_end_matches('/'))
+    }
+
+    /// Interval between `ssdp:alive` announcements, before jitter.
+    fn ssdp_keep_alive(&self) -> Duration {
+        self.ssdp_keep_alive_ms.map_or_else(
+            || Duration::from_secs(u64::from(self.ssdp_cache_max_age)) / 3,
+            Duration::from_millis,
+        )
+    }
+
+    /// The address the HTTP server binds to, i.e. [`http_bind_ip`](DMROptions::http_bind_ip) if set, otherwise [`ip`](DMROptions::ip), with [`http_port`](DMROptions::http_port).
+    #[must_use]
+    pub fn http_bind_address(&self) -> SocketAddr {
+        SocketAddr::new(self.http_bind_ip.unwrap_or(self.ip), self.http_port)
+    }
+
+    /// The URL of the device description, advertised as the SSDP `LOCATION`.
+    fn location(&self) -> String {
+        self.location_at(self.ip)
+    }
+
+    /// The URL of the device description when reached via `ip`, unless [`advertise_location`](DMROptions::advertise_location) overrides it.
+    fn location_at(&self, ip: IpAddr) -> String {
+        self.advertise_location.as_ref().map_or_else(
+            || {
+                let address = SocketAddr::new(ip, self.http_port);
+                format!("http://{address}{}", self.description_path)
+            },
+            |base| format!("{}{}", base.trim_end_matches('/'), self.description_path),
+        )
+    }
+}
+
+/// Addresses a DMR instance is reachable at, reported by [`DMR::on_ready`] once it's up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadyInfo {
+    /// The IP address advertised to control points.
+    pub advertise_ip: IpAddr,
+    /// The port the HTTP server is listening on, resolved if [`DMROptions::http_port`] is `0`.
+    pub http_port: u16,
+    /// The port the SSDP server is bound to, which may differ from [`DMROptions::ssdp_port`] on fallback.
+    pub ssdp_port: u16,
+    /// The UUID of the DMR instance.
+    pub uuid: String,
+}
+
 /// A trait for DMR instances.
 pub trait DMR: HTTPServer {
-    /// Create and run the DMR instance, stopping when Ctrl-C is pressed.
+    /// Create and run the DMR instance, stopping when Ctrl-C is pressed. See [`run_with_shutdown`](DMR::run_with_shutdown).
     fn run(&'static self, options: Arc<DMROptions>) -> impl Future<Output = IoResult<()>> + Send
     where
         Self: Sync,
-    {async {
-        let address = SocketAddrV4::new(options.ip, options.ssdp_port);
-        let ssdp = SSDPServer::new(
-            address,
-            options.uuid.clone(),
-            options.http_port,
-        )
-        .await?;
-
-        tokio::select! {
-            _ = ssdp.keep_alive() => {}
-            _ = ssdp.run() => {}
-            r = self.run_http(options) => {
-                if let Err(e) = r {
-                    error!("IO Error while running HTTP server: {e}");
-                }
+    {
+        self.run_with_shutdown(options, async {
+            if let Err(e) = tokio::signal::ctrl_c().await {
+                error!("IO Error while waiting for Ctrl-C: {e}");
             }
-            r = tokio::signal::ctrl_c() => {
-                if let Err(e) = r {
-                    error!("IO Error while waiting for Ctrl-C: {e}");
+        })
+    }
+
+    /// Create and run the DMR instance, stopping once `shutdown` resolves, e.g. when the application embedding it quits.
+    ///
+    /// If [`DMROptions::http_port`] is `0`, an ephemeral port is used and advertised instead. Likewise, the UUID is derived from [`DMROptions::uuid_seed`] if set, or stripped of its `uuid:` prefix otherwise (see [`DMROptions::normalize_uuid`]). [`on_ready`](DMR::on_ready) is invoked once both the SSDP and HTTP servers are bound, after which the self-check runs if [`DMROptions::startup_self_check`] is enabled.
+    ///
+    /// On shutdown, the HTTP server stops accepting connections and answers the requests in flight (see [`HTTPServer::serve_http_with_shutdown`]), then `ssdp:byebye` is sent.
+    fn run_with_shutdown(
+        &'static self,
+        options: Arc<DMROptions>,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> impl Future<Output = IoResult<()>> + Send
+    where
+        Self: Sync,
+    {
+        async {
+            options
+                .validate()
+                .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+            let listener = TcpListener::bind(options.http_bind_address()).await?;
+            let http_port = listener.local_addr()?.port();
+            let uuid = options
+                .effective_uuid()
+                .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+            let options = if http_port == options.http_port && uuid == options.uuid {
+                options
+            } else {
+                Arc::new(DMROptions {
+                    http_port,
+                    uuid,
+                    ..(*options).clone()
+                })
+            };
+            let runtime = runtime::register(
+                self,
+                Runtime {
+                    ssdp: Arc::new(
+                        SSDPServer::new(&options)?
+                            .with_search_hook(|from, st| self.on_search(from, st)),
+                    ),
+                    context: Arc::new(ControlContext::new(Arc::clone(&options))),
+                },
+            );
+            let ssdp = &runtime.ssdp;
+
+            self.on_ready(&ReadyInfo {
+                advertise_ip: options.ip,
+                http_port,
+                ssdp_port: ssdp.port(),
+                uuid: options.uuid.clone(),
+            })
+            .await;
+            let self_check = options.startup_self_check.then(|| {
+                (
+                    SocketAddr::new(options.ip, http_port),
+                    options.description_path.clone(),
+                    options.uuid.clone(),
+                )
+            });
+
+            tokio::select! {
+                () = Arc::clone(ssdp).serve() => {}
+                () = async {
+                    if let Some((address, path, uuid)) = self_check {
+                        self_check::run(address, &path, &uuid).await;
+                    }
+                    pending::<()>().await;
+                } => {}
+                r = self.serve_http_with_shutdown(listener, options, shutdown) => {
+                    if let Err(e) = r {
+                        error!("IO Error while running HTTP server: {e}");
+                    }
                 }
             }
+
+            // Dropping `runtime` also sends `ssdp:byebye` and unregisters if cancelled or panicking
+            runtime.stop().await;
+
+            info!("DMR stopped");
+            Ok(())
         }
+    }
 
-        ssdp.stop().await;
+    /// Announce this running instance to a single controller, by sending it unicast `ssdp:alive` messages.
+    ///
+    /// This lets you register the renderer manually with a controller on another subnet, which multicast announcements and searches don't reach.
+    ///
+    /// ## Limitations
+    ///
+    /// - The announcement is sent once: the controller forgets the renderer when the advertised `max-age` expires (see [`DMROptions::ssdp_cache_max_age`]) unless you call this method again.
+    /// - The controller must listen for unicast `NOTIFY` messages on `controller`, which is usually port `1900`. Some controllers only accept multicast ones and ignore it.
+    /// - The controller must be able to reach the HTTP server via [`DMROptions::ip`], as the advertised `LOCATION` points there.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`ErrorKind::NotConnected`] if the instance isn't running, or the error encountered while sending.
+    fn announce_to(
+        &'static self,
+        controller: SocketAddr,
+    ) -> impl Future<Output = IoResult<()>> + Send {
+        async move {
+            let Some(runtime) = runtime::get(self) else {
+                return Err(Error::new(
+                    ErrorKind::NotConnected,
+                    "DMR instance isn't running",
+                ));
+            };
+            runtime.ssdp.announce_to(controller).await
+        }
+    }
 
-        info!("DMR stopped");
+    /// Statistics of the time this running instance took to answer `M-SEARCH` requests so far, e.g. to tell whether slow discovery is due to the renderer or to the network. Searches are answered after a random delay of up to their `MX` header (at most 5 seconds), which is included.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`ErrorKind::NotConnected`] if the instance isn't running.
+    fn search_latency(&'static self) -> IoResult<SearchLatency> {
+        let Some(runtime) = runtime::get(self) else {
+            return Err(Error::new(
+                ErrorKind::NotConnected,
+                "DMR instance isn't running",
+            ));
+        };
+        Ok(runtime.ssdp.search_latency())
+    }
+
+    /// Re-renders the device description served by this running instance with given options, e.g. after the friendly name changed in a reloaded config. The description is rendered once at startup and cached, so changes to the options are otherwise not reflected. The `CONFIGID.UPNP.ORG` of SSDP messages is updated accordingly; call [`announce_update`](DMR::announce_update) afterwards so that controllers notice right away. Other options only take effect after a restart.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`ErrorKind::NotConnected`] if the instance isn't running, or [`ErrorKind::InvalidInput`] if the description failed to render.
+    fn reload_description(&'static self, options: &DMROptions) -> IoResult<()> {
+        let Some(runtime) = runtime::get(self) else {
+            return Err(Error::new(
+                ErrorKind::NotConnected,
+                "DMR instance isn't running",
+            ));
+        };
+        runtime
+            .context
+            .reload_description(options)
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        runtime.ssdp.reconfigure(options);
         Ok(())
-    } }
+    }
+
+    /// Tells controllers that the device description of this running instance changed, e.g. after [`reload_description`](DMR::reload_description) or an icon was added, so that they fetch it again instead of using a cached one. Broadcasts `ssdp:update` announcements carrying the next `BOOTID.UPNP.ORG`, then switches to it.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`ErrorKind::NotConnected`] if the instance isn't running, or the error encountered while sending.
+    fn announce_update(&'static self) -> IoResult<()> {
+        let Some(runtime) = runtime::get(self) else {
+            return Err(Error::new(
+                ErrorKind::NotConnected,
+                "DMR instance isn't running",
+            ));
+        };
+        runtime.ssdp.announce_update().map(drop)
+    }
+
+    /// The context shared by the handlers of this running instance, e.g. to report playback changes triggered outside of controllers with [`ControlContext::notify_playing`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`ErrorKind::NotConnected`] if the instance isn't running.
+    fn context(&'static self) -> IoResult<Arc<ControlContext>> {
+        let Some(runtime) = runtime::get(self) else {
+            return Err(Error::new(
+                ErrorKind::NotConnected,
+                "DMR instance isn't running",
+            ));
+        };
+        Ok(Arc::clone(&runtime.context))
+    }
+
+    /// Subscribes to the actions received by this running instance, see [`ControlContext::events`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`ErrorKind::NotConnected`] if the instance isn't running.
+    fn events(&'static self) -> IoResult<broadcast::Receiver<ControlEvent>> {
+        self.context().map(|context| context.events())
+    }
+
+    /// Called with the sender and `ST` header of each `M-SEARCH` request before it's answered, e.g. to log or rate-limit controllers that spam searches, or to implement allow and deny lists. Returning `false` suppresses the response. Searches from outside of [`DMROptions::respond_to_subnets`] are dropped before reaching it, and it's called even if we don't provide the searched target.
+    ///
+    /// Called from the SSDP server's tasks, so it should return quickly. The default answers every search.
+    #[allow(
+        unused_variables,
+        reason = "This is a dummy trait method, intended to be overridden"
+    )]
+    fn on_search(&self, from: SocketAddr, st: &str) -> bool {
+        true
+    }
+
+    /// Called once the SSDP and HTTP servers are up, with the addresses they are reachable at. Useful for showing them in a UI, especially with ephemeral ports.
+    #[allow(
+        unused_variables,
+        reason = "This is a dummy trait method, intended to be overridden"
+    )]
+    fn on_ready(&self, info: &ReadyInfo) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Method;
+    use std::{
+        net::{Ipv4Addr, SocketAddrV4},
+        sync::Mutex,
+    };
+    use tokio::{net::TcpStream, time::sleep};
+
+    /// Records the info passed to `on_ready`.
+    #[derive(Default)]
+    struct Ready {
+        info: Mutex<Option<ReadyInfo>>,
+    }
+
+    impl HTTPServer for Ready {}
+
+    impl DMR for Ready {
+        async fn on_ready(&self, info: &ReadyInfo) {
+            *self.info.lock().unwrap() = Some(info.clone());
+        }
+    }
+
+    /// Panics while serving HTTP.
+    struct Panicking;
+
+    impl HTTPServer for Panicking {
+        async fn serve_http_with_shutdown(
+            &'static self,
+            _listener: TcpListener,
+            _options: Arc<DMROptions>,
+            _shutdown: impl Future<Output = ()> + Send + 'static,
+        ) -> IoResult<()> {
+            panic!("Injected panic")
+        }
+    }
+
+    impl DMR for Panicking {}
+
+    #[tokio::test]
+    async fn test_run_panic_unregisters() {
+        static DMR: Panicking = Panicking;
+        let options = DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            http_port: 0,
+            startup_self_check: false,
+            ..Default::default()
+        };
+        let result = tokio::spawn(DMR.run(Arc::new(options))).await;
+        assert!(result.expect_err("`run` should panic").is_panic());
+        // The registration has been dropped while unwinding, sending `ssdp:byebye` as well
+        assert!(runtime::get(&DMR).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_on_ready_ephemeral_port() {
+        let dmr: &'static Ready = Box::leak(Box::default());
+        let options = DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            http_port: 0,
+            uuid: "uuid:12345678-1234-5678-1234-567812345678".to_string(),
+            ..Default::default()
+        };
+        let options = Arc::new(options);
+        let handle = tokio::spawn(dmr.run(Arc::clone(&options)));
+
+        let mut info = None;
+        for _ in 0..100 {
+            info = dmr.info.lock().unwrap().clone();
+            if info.is_some() {
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        let info = info.expect("`on_ready` wasn't invoked");
+        assert_eq!(info.advertise_ip, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(info.uuid, "12345678-1234-5678-1234-567812345678");
+        assert_ne!(info.http_port, 0);
+        assert_ne!(info.ssdp_port, 0);
+        TcpStream::connect(SocketAddr::new(info.advertise_ip, info.http_port))
+            .await
+            .expect("HTTP server isn't listening on the reported port");
+
+        // The served description is cached, and only changes on reload
+        let url = format!("http://{}:{}/DeviceSpec", info.advertise_ip, info.http_port);
+        let fetch = async || {
+            let response = client::request(Method::GET, &url, &[], String::new())
+                .await
+                .expect("Failed to fetch the description");
+            String::from_utf8(response.into_body().to_vec()).expect("Description is not UTF-8")
+        };
+        assert!(
+            fetch()
+                .await
+                .contains("<friendlyName>Dummy Renderer</friendlyName>")
+        );
+        let reloaded = DMROptions {
+            friendly_name: "Kitchen".to_string(),
+            ..(*options).clone()
+        };
+        dmr.reload_description(&reloaded)
+            .expect("Failed to reload the description");
+        assert!(
+            fetch()
+                .await
+                .contains("<friendlyName>Kitchen</friendlyName>")
+        );
+
+        handle.abort();
+    }
+
+    #[tokio::test]
+    // Binding other loopback addresses than `127.0.0.1` requires aliases elsewhere
+    #[cfg(target_os = "linux")]
+    async fn test_http_bind_ip() {
+        let dmr: &'static Ready = Box::leak(Box::default());
+        let bind_ip = Ipv4Addr::new(127, 0, 0, 2);
+        let options = DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            http_port: 0,
+            http_bind_ip: Some(bind_ip.into()),
+            startup_self_check: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            options.http_bind_address(),
+            SocketAddr::new(bind_ip.into(), 0)
+        );
+        let handle = tokio::spawn(dmr.run(Arc::new(options)));
+
+        let mut info = None;
+        for _ in 0..100 {
+            info = dmr.info.lock().unwrap().clone();
+            if info.is_some() {
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        let info = info.expect("`on_ready` wasn't invoked");
+        // SSDP advertises `ip`, while the HTTP server only listens on `http_bind_ip`
+        assert_eq!(info.advertise_ip, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        let location = runtime::get(dmr)
+            .expect("Not running")
+            .context
+            .options
+            .location();
+        assert!(location.starts_with(&format!("http://127.0.0.1:{}/", info.http_port)));
+        TcpStream::connect(SocketAddr::new(bind_ip.into(), info.http_port))
+            .await
+            .expect("HTTP server isn't listening on `http_bind_ip`");
+        assert!(
+            TcpStream::connect(SocketAddr::new(info.advertise_ip, info.http_port))
+                .await
+                .is_err(),
+            "HTTP server is listening on `ip`"
+        );
+
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_run_with_shutdown() {
+        use tokio::{sync::oneshot, time::timeout};
+
+        // Both the plain HTTP/1.1 server and the auto-detecting one used for h2c
+        for http2 in [false, true] {
+            let dmr: &'static Ready = Box::leak(Box::default());
+            let options = DMROptions {
+                ip: Ipv4Addr::LOCALHOST.into(),
+                ssdp_port: 0,
+                http_port: 0,
+                startup_self_check: false,
+                http2,
+                ..Default::default()
+            };
+            let (stop, stopped) = oneshot::channel::<()>();
+            let handle = tokio::spawn(dmr.run_with_shutdown(Arc::new(options), async {
+                stopped.await.ok();
+            }));
+            for _ in 0..100 {
+                if dmr.info.lock().unwrap().is_some() {
+                    break;
+                }
+                sleep(Duration::from_millis(10)).await;
+            }
+            let info = dmr
+                .info
+                .lock()
+                .unwrap()
+                .clone()
+                .expect("`on_ready` wasn't invoked");
+            let address = SocketAddr::new(info.advertise_ip, info.http_port);
+            // An idle keep-alive connection doesn't hold up the shutdown
+            let _idle = TcpStream::connect(address)
+                .await
+                .expect("HTTP server isn't listening");
+
+            stop.send(()).unwrap();
+            timeout(Duration::from_secs(1), handle)
+                .await
+                .expect("`run_with_shutdown` didn't return promptly")
+                .expect("`run_with_shutdown` panicked")
+                .expect("`run_with_shutdown` failed");
+            assert!(runtime::get(dmr).is_none(), "http2: {http2}");
+            assert!(
+                TcpStream::connect(address).await.is_err(),
+                "HTTP server still listening"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events() {
+        let dmr: &'static Ready = Box::leak(Box::default());
+        let options = DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            http_port: 0,
+            startup_self_check: false,
+            ..Default::default()
+        };
+        let handle = tokio::spawn(dmr.run(Arc::new(options)));
+        let mut info = None;
+        for _ in 0..100 {
+            info = dmr.info.lock().unwrap().clone();
+            if info.is_some() {
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        let info = info.expect("`on_ready` wasn't invoked");
+
+        let mut events = dmr.events().expect("DMR is running");
+        let envelope =
+            std::fs::read_to_string("tests/AVTransport/Play.xml").expect("Failed to read XML file");
+        client::request(
+            Method::POST,
+            &format!(
+                "http://{}:{}/AVTransport",
+                info.advertise_ip, info.http_port
+            ),
+            &[("Content-Type", response::XML_CONTENT_TYPE)],
+            envelope,
+        )
+        .await
+        .expect("Failed to send Play");
+        let event = tokio::time::timeout(Duration::from_secs(1), events.recv())
+            .await
+            .expect("Timed out waiting for the event")
+            .expect("Failed to receive the event");
+        assert!(matches!(
+            event.action,
+            ControlAction::AVTransport(xml::AVTransport::Play(_))
+        ));
+        assert_eq!(
+            event.peer.map(|peer| peer.ip()),
+            Some(Ipv4Addr::LOCALHOST.into())
+        );
+
+        handle.abort();
+    }
+
+    #[test]
+    fn test_validate_advertise_location() {
+        let options = |location: &str| DMROptions {
+            advertise_location: Some(location.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(options("http://192.168.1.20:8080").validate(), Ok(()));
+        assert_eq!(
+            options("https://proxy.example.com/renderer/").validate(),
+            Ok(())
+        );
+        for location in [
+            "192.168.1.20:8080",
+            "ftp://192.168.1.20",
+            "/renderer",
+            "http://proxy.example.com/?a=b",
+            "http://proxy.example.com/#renderer",
+            "http://exa mple.com",
+        ] {
+            assert!(options(location).validate().is_err(), "{location}");
+        }
+    }
+
+    #[test]
+    fn test_validate_description_path() {
+        let options = |path: &str| DMROptions {
+            description_path: path.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(options("/description.xml").validate(), Ok(()));
+        assert_eq!(options("/DeviceSpec").validate(), Ok(()));
+        for path in [
+            "description.xml",
+            "/desc?x=1",
+            "/desc#x",
+            "/de sc",
+            "/AVTransport",
+        ] {
+            assert!(options(path).validate().is_err(), "{path}");
+        }
+    }
+
+    #[test]
+    fn test_validate_interfaces() {
+        let options = |ip: IpAddr, interfaces: Vec<Ipv4Addr>| DMROptions {
+            ip,
+            interfaces: Some(interfaces),
+            ..Default::default()
+        };
+        let wired = Ipv4Addr::new(192, 168, 1, 20);
+        let wifi = Ipv4Addr::new(10, 0, 0, 5);
+        assert_eq!(
+            options(Ipv4Addr::UNSPECIFIED.into(), vec![wired, wifi]).validate(),
+            Ok(())
+        );
+        assert!(
+            options(Ipv4Addr::UNSPECIFIED.into(), Vec::new())
+                .validate()
+                .is_err()
+        );
+        assert!(
+            options(std::net::Ipv6Addr::LOCALHOST.into(), vec![wired])
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_validate_http_bind_ip() {
+        let options = |ip: IpAddr, http_bind_ip: IpAddr| DMROptions {
+            ip,
+            http_bind_ip: Some(http_bind_ip),
+            ..Default::default()
+        };
+        let v4 = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20));
+        let v6 = IpAddr::V6(std::net::Ipv6Addr::LOCALHOST);
+        assert_eq!(options(v4, Ipv4Addr::UNSPECIFIED.into()).validate(), Ok(()));
+        assert_eq!(
+            options(v6, std::net::Ipv6Addr::UNSPECIFIED.into()).validate(),
+            Ok(())
+        );
+        assert!(options(v4, v6).validate().is_err());
+        assert!(options(v6, v4).validate().is_err());
+    }
+
+    #[test]
+    fn test_derive_uuid() {
+        let uuid = DMROptions::derive_uuid("Living Room");
+        assert_eq!(uuid, DMROptions::derive_uuid("Living Room"));
+        assert_ne!(uuid, DMROptions::derive_uuid("Kitchen"));
+        let parsed = uuid::Uuid::parse_str(&uuid).expect("Invalid UUID");
+        assert_eq!(parsed.get_version_num(), 5);
+
+        let options = DMROptions {
+            uuid_seed: Some(" ".to_string()),
+            ..Default::default()
+        };
+        assert!(options.validate().is_err());
+    }
+
+    #[test]
+    fn test_normalize_uuid() {
+        let uuid = "12345678-1234-5678-1234-567812345678";
+        for input in [
+            uuid.to_string(),
+            format!("uuid:{uuid}"),
+            format!("UUID:{uuid}"),
+            format!(" uuid:{uuid}\n"),
+        ] {
+            assert_eq!(
+                DMROptions::normalize_uuid(&input).unwrap(),
+                uuid,
+                "{input:?}"
+            );
+        }
+        for input in [
+            "",
+            "uuid:",
+            "uuid:uuid:12345678-1234-5678-1234-567812345678",
+            "living-room",
+        ] {
+            let DmrError::Config(error) = DMROptions::normalize_uuid(input).expect_err(input);
+            assert!(error.contains("Invalid `uuid`"), "{error}");
+        }
+
+        let options = DMROptions {
+            uuid: "not-a-uuid".to_string(),
+            ..Default::default()
+        };
+        assert!(options.validate().is_err());
+        // The configured UUID is ignored if derived from a seed
+        let options = DMROptions {
+            uuid_seed: Some("Living Room".to_string()),
+            ..options
+        };
+        assert_eq!(
+            options.effective_uuid().unwrap(),
+            DMROptions::derive_uuid("Living Room")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_announce_to_not_running() {
+        let dmr: &'static Ready = Box::leak(Box::default());
+        let err = dmr
+            .announce_to(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 1900).into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotConnected);
+        let err = dmr.search_latency().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotConnected);
+        let err = dmr.context().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotConnected);
+        let err = dmr.events().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotConnected);
+    }
 }
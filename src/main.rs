@@ -1,14 +1,15 @@
 #![warn(clippy::all, clippy::nursery, clippy::pedantic, clippy::cargo)]
+#![allow(clippy::multiple_crate_versions, reason = "Dependencies' requirements")]
 
 use axum::{http::StatusCode, response::IntoResponse};
 use dlna_dmr::{
-    DMR, DMROptions, HTTPServer,
+    ConfigFormat, ControlContext, DMR, DMROptions, HTTPServer, ParseError,
     xml::{AVTransport, RenderingControl},
 };
-use log::{info, warn};
-use quick_xml::DeError;
+use log::{error, info, warn};
+use serde_json::{Map, Value};
 use std::{
-    io::{Error, ErrorKind, Result as IoResult},
+    io::{Error, ErrorKind, Read, Result as IoResult},
     sync::Arc,
 };
 
@@ -17,7 +18,8 @@ struct DummyDMR {}
 impl HTTPServer for DummyDMR {
     async fn post_av_transport(
         &self,
-        av_transport: Result<AVTransport, DeError>,
+        av_transport: Result<AVTransport, ParseError>,
+        _context: &ControlContext,
     ) -> impl IntoResponse {
         match av_transport {
             Ok(av_transport) => match av_transport {
@@ -37,13 +39,14 @@ impl HTTPServer for DummyDMR {
                 _ => {}
             },
             Err(e) => warn!("Failed to deserialize `/AVTransport` XML: {e}"),
-        };
+        }
         StatusCode::METHOD_NOT_ALLOWED
     }
 
     async fn post_rendering_control(
         &self,
-        rendering_control: Result<RenderingControl, DeError>,
+        rendering_control: Result<RenderingControl, ParseError>,
+        _context: &ControlContext,
     ) -> impl IntoResponse {
         match rendering_control {
             Ok(rendering_control) => match rendering_control {
@@ -71,22 +74,95 @@ impl HTTPServer for DummyDMR {
 
 impl DMR for DummyDMR {}
 
+/// Reads the configuration from the file at `arg`, from `stdin` if `arg` is `-`, or returns an empty configuration if there's no argument.
+fn read_config(arg: Option<&str>, mut stdin: impl Read) -> IoResult<String> {
+    match arg {
+        Some("-") => {
+            info!("Reading configuration from stdin");
+            let mut config = String::new();
+            stdin.read_to_string(&mut config)?;
+            Ok(config)
+        }
+        Some(path) => {
+            info!("Using configuration file: {path}");
+            std::fs::read_to_string(path)
+        }
+        None => {
+            info!("No configuration file provided, using default settings");
+            Ok(String::new())
+        }
+    }
+}
+
+/// Merges `overlay` into `base`, recursing into tables present in both. Other values in `overlay`, including arrays, replace those in `base`, except for nulls (e.g. `null` in JSON or `~` in YAML), which leave fields unset.
+fn merge(base: &mut Map<String, Value>, overlay: Map<String, Value>) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (_, Value::Null) => {}
+            (Some(Value::Object(base)), Value::Object(overlay)) => merge(base, overlay),
+            (_, Value::Object(overlay)) => {
+                let mut table = Map::new();
+                merge(&mut table, overlay);
+                base.insert(key, Value::Object(table));
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// The format of the configuration at `arg`, determined by its extension (see [`ConfigFormat::from_path`]). Defaults to TOML, e.g. for `stdin`.
+fn config_format(arg: Option<&str>) -> ConfigFormat {
+    arg.filter(|arg| *arg != "-")
+        .and_then(ConfigFormat::from_path)
+        .unwrap_or(ConfigFormat::Toml)
+}
+
+/// Reads and parses the configuration at `arg` (see [`read_config`]) in its format, see [`config_format`].
+fn parse_config(arg: Option<&str>, stdin: impl Read) -> IoResult<Map<String, Value>> {
+    let config = read_config(arg, stdin)?;
+    config_format(arg).parse(&config).map_err(|e| {
+        error!("{e}");
+        Error::new(ErrorKind::InvalidData, e)
+    })
+}
+
+/// Loads the options from the configuration files at `paths`, merged in order so that later files override fields of earlier ones. Files may be in any enabled format, see [`config_format`]. Only the first file is required to exist, unless `strict` is set; missing ones are skipped with a warning otherwise.
+fn load_options(paths: &[&str], strict: bool, mut stdin: impl Read) -> IoResult<DMROptions> {
+    let mut table = Map::new();
+    merge(
+        &mut table,
+        parse_config(paths.first().copied(), &mut stdin)?,
+    );
+    for path in paths.iter().skip(1) {
+        match parse_config(Some(path), &mut stdin) {
+            Ok(overlay) => merge(&mut table, overlay),
+            Err(e) if e.kind() == ErrorKind::NotFound && !strict => {
+                warn!("Skipping missing configuration file {path}");
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    serde_json::from_value(Value::Object(table)).map_err(|e| {
+        error!("Failed to parse configuration: {e}");
+        Error::new(ErrorKind::InvalidData, e)
+    })
+}
+
 #[tokio::main]
 async fn main() -> IoResult<()> {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
-    // Load and parse configuration
-    let config = if let Some(arg) = std::env::args().nth(1) {
-        info!("Using configuration file: {arg}");
-        std::fs::read_to_string(arg)?
-    } else {
-        info!("No configuration file provided, using default settings");
-        "".to_string()
-    };
-    let options: DMROptions = toml::from_str(&config).map_err(|e| {
-        eprintln!("Failed to parse configuration: {e}");
-        Error::new(ErrorKind::InvalidData, e)
-    })?;
+    // Load and merge configuration files, given in order of precedence
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let strict = args.iter().any(|arg| arg == "--strict");
+    let paths: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|arg| *arg != "--strict")
+        .collect();
+    let options = load_options(&paths, strict, std::io::stdin().lock())?;
 
     let dmr = DummyDMR {};
     let dmr = Box::leak(Box::new(dmr));
@@ -94,3 +170,96 @@ async fn main() -> IoResult<()> {
     // Start the DMR, stopping when Ctrl-C is pressed.
     dmr.run(Arc::new(options)).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_config_from_stdin() {
+        let stdin = "friendly_name = \"Piped Renderer\"\nhttp_port = 9090\n".as_bytes();
+        let config = read_config(Some("-"), stdin).expect("Failed to read stdin");
+        let options: DMROptions = toml::from_str(&config).expect("Failed to parse config");
+        assert_eq!(options.friendly_name, "Piped Renderer");
+        assert_eq!(options.http_port, 9090);
+    }
+
+    #[test]
+    fn test_read_config_from_file() {
+        let config = read_config(Some("tests/test-config.toml"), std::io::empty())
+            .expect("Failed to read file");
+        let options: DMROptions = toml::from_str(&config).expect("Failed to parse config");
+        assert_eq!(options.friendly_name, "Friendly Renderer");
+        assert_eq!(read_config(None, std::io::empty()).unwrap(), "");
+    }
+
+    #[test]
+    fn test_config_format() {
+        assert_eq!(config_format(Some("-")), ConfigFormat::Toml);
+        assert_eq!(config_format(Some("config.yml")), ConfigFormat::Yaml);
+        assert_eq!(config_format(Some("config")), ConfigFormat::Toml);
+        assert_eq!(config_format(None), ConfigFormat::Toml);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_merge_formats() {
+        let paths = ["tests/test-config.override.toml", "tests/test-config.json"];
+        let options = load_options(&paths, true, std::io::empty()).expect("Failed to load");
+        assert_eq!(options.friendly_name, "Friendly Renderer");
+        assert_eq!(options.http_port, 9090);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_null_fields() {
+        let paths = ["tests/test-config.toml", "tests/test-config.null.json"];
+        let options = load_options(&paths, true, std::io::empty()).expect("Failed to load");
+        assert_eq!(options.friendly_name, "Friendly Renderer");
+        assert_eq!(options.http_bind_ip, None);
+
+        // As serialized, with unset fields as nulls
+        let path = std::env::temp_dir().join(format!("dlna-dmr-{}.json", std::process::id()));
+        let serialized = DMROptions {
+            friendly_name: "Serialized Renderer".to_string(),
+            ..Default::default()
+        };
+        std::fs::write(&path, serde_json::to_string(&serialized).unwrap()).unwrap();
+        let loaded = load_options(&[path.to_str().unwrap()], true, std::io::empty());
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.expect("Failed to load serialized options");
+        assert_eq!(loaded.friendly_name, "Serialized Renderer");
+        assert_eq!(loaded.http_bind_ip, None);
+    }
+
+    #[test]
+    fn test_merge_config_files() {
+        let paths = [
+            "tests/test-config.toml",
+            "tests/test-config.override.toml",
+            "tests/missing.toml",
+        ];
+        let options = load_options(&paths, false, std::io::empty()).expect("Failed to load");
+        assert_eq!(options.friendly_name, "Overridden Renderer");
+        assert_eq!(options.http_port, 9090);
+        assert_eq!(options.model_name, DMROptions::default().model_name);
+        let err = load_options(&paths, true, std::io::empty()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+
+        let table = |toml: &str| toml::from_str::<Map<String, Value>>(toml).unwrap();
+        let mut base = table("a = 1\n[t]\nx = 1\ny = [1, 2]\n");
+        merge(&mut base, table("b = 2\n[t]\ny = [3]\n"));
+        let expected = table("a = 1\nb = 2\n[t]\nx = 1\ny = [3]\n");
+        assert_eq!(base, expected);
+
+        // Nulls leave fields unset
+        let Value::Object(overlay) =
+            serde_json::json!({ "a": null, "c": { "x": null }, "t": { "x": null } })
+        else {
+            unreachable!("An object literal");
+        };
+        merge(&mut base, overlay);
+        let expected = table("a = 1\nb = 2\n[c]\n[t]\nx = 1\ny = [3]\n");
+        assert_eq!(base, expected);
+    }
+}
@@ -0,0 +1,100 @@
+//! Module for reasoning about media resources, e.g. estimating their duration.
+
+use std::time::Duration;
+
+/// Estimates the duration of a constant-bitrate resource from its size, e.g. the HTTP `Content-Length`, and its bitrate in bits per second. Returns zero if the bitrate is unknown (`0`).
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::media::duration_from_bytes;
+/// use std::time::Duration;
+///
+/// // 3 MB at 320 kbps
+/// assert_eq!(duration_from_bytes(3_000_000, 320_000), Duration::from_millis(75_000));
+/// ```
+#[must_use]
+pub fn duration_from_bytes(content_length: u64, bitrate_bps: u32) -> Duration {
+    if bitrate_bps == 0 {
+        return Duration::ZERO;
+    }
+    let bits = u128::from(content_length) * 8;
+    let nanos = bits * 1_000_000_000 / u128::from(bitrate_bps);
+    u64::try_from(nanos).map_or(Duration::MAX, Duration::from_nanos)
+}
+
+/// Parses a duration in the form `H+:MM:SS[.F+]` used by DIDL-Lite and `AVTransport`, e.g. `0:01:30.000`. Fractions may also be written as `F0/F1`, e.g. `0:01:30.1/2`. Hours may exceed 24, and may be left out by sloppy controllers, e.g. `01:30`.
+#[must_use]
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let parts: Vec<_> = s.trim().split(':').collect();
+    let (hours, minutes, seconds) = match parts[..] {
+        [hours, minutes, seconds] => (hours, minutes, seconds),
+        [minutes, seconds] => ("0", minutes, seconds),
+        _ => return None,
+    };
+    let (seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+    let hours: u64 = hours.parse().ok()?;
+    let minutes: u64 = minutes.parse().ok().filter(|&minutes| minutes < 60)?;
+    let seconds: u64 = seconds.parse().ok().filter(|&seconds| seconds < 60)?;
+    let fraction = match fraction.split_once('/') {
+        _ if fraction.is_empty() => Duration::ZERO,
+        Some((numerator, denominator)) => {
+            let numerator: u32 = numerator.parse().ok()?;
+            let denominator: u32 = denominator.parse().ok().filter(|&d| d > numerator)?;
+            Duration::from_secs(1) * numerator / denominator
+        }
+        None => Duration::from_secs_f64(format!("0.{fraction}").parse().ok()?),
+    };
+    let seconds = hours
+        .checked_mul(3600)?
+        .checked_add(minutes * 60 + seconds)?;
+    Some(Duration::from_secs(seconds) + fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_from_bytes() {
+        // 1 MB at 8 Mbps
+        assert_eq!(
+            duration_from_bytes(1_000_000, 8_000_000),
+            Duration::from_secs(1)
+        );
+        // 5 MiB at 128 kbps
+        assert_eq!(
+            duration_from_bytes(5 * 1024 * 1024, 128_000),
+            Duration::from_millis(327_680)
+        );
+        // A 4 GB movie at 5 Mbps
+        assert_eq!(
+            duration_from_bytes(4_000_000_000, 5_000_000),
+            Duration::from_secs(6400)
+        );
+        assert_eq!(duration_from_bytes(1_000_000, 0), Duration::ZERO);
+        assert_eq!(duration_from_bytes(u64::MAX, 1), Duration::MAX);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("0:01:30.000"), Some(Duration::from_secs(90)));
+        assert_eq!(
+            parse_duration("12:00:01"),
+            Some(Duration::from_secs(43_201))
+        );
+        assert_eq!(
+            parse_duration("0:00:01.5"),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            parse_duration("0:00:01.1/4"),
+            Some(Duration::from_millis(1250))
+        );
+        assert_eq!(parse_duration("01:30"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("100:00:00"), Some(Duration::from_hours(100)));
+        assert_eq!(parse_duration("0:60:00"), None);
+        assert_eq!(parse_duration("1:0:01:30"), None);
+        assert_eq!(parse_duration("NOT_IMPLEMENTED"), None);
+    }
+}
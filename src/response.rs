@@ -0,0 +1,162 @@
+//! Module for building responses to control points, so that your handlers don't need to depend on axum directly.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use dlna_dmr::{
+//!     ControlContext, HTTPServer, ParseError, Response,
+//!     response::{action_response, soap_fault, soap_response},
+//!     xml::{AVTransport, av_transport},
+//! };
+//!
+//! struct MyDMR;
+//!
+//! impl HTTPServer for MyDMR {
+//!     async fn post_av_transport(
+//!         &self,
+//!         av_transport: Result<AVTransport, ParseError>,
+//!         _context: &ControlContext,
+//!     ) -> Response {
+//!         match av_transport {
+//!             Ok(AVTransport::Stop(_)) => {
+//!                 // Stop playback here...
+//!                 soap_response(action_response(av_transport::SERVICE_TYPE, "Stop", &[]))
+//!             }
+//!             _ => soap_fault(401, "Invalid Action"),
+//!         }
+//!     }
+//! }
+//! ```
+
+use super::{
+    Response,
+    xml::soap::{DEFAULT_PREFIX, envelope},
+};
+use axum::{http::StatusCode, response::IntoResponse};
+use quick_xml::escape::escape;
+use std::fmt::Write;
+
+/// Output arguments of an action, which [`ControlContext::envelope`](crate::ControlContext::envelope) serializes into the SOAP envelope answering it, e.g. [`GetTransportInfoResponse`](crate::xml::av_transport::GetTransportInfoResponse).
+pub trait ActionResponse {
+    /// The version-`1` type of the service the action belongs to, e.g. [`av_transport::SERVICE_TYPE`](crate::xml::av_transport::SERVICE_TYPE).
+    const SERVICE_TYPE: &'static str;
+    /// The name of the action answered, e.g. `"GetTransportInfo"`.
+    const ACTION: &'static str;
+
+    /// The output arguments with their values, in the order of the SCPD.
+    fn arguments(&self) -> Vec<(&'static str, String)>;
+}
+
+/// Content type of XML responses.
+pub const XML_CONTENT_TYPE: &str = r#"text/xml; charset="utf-8""#;
+
+/// Builds the SOAP envelope answering `action` of the service `service_type`, with given output arguments. The values of the arguments are escaped.
+#[must_use]
+pub fn action_response(service_type: &str, action: &str, args: &[(&str, &str)]) -> String {
+    action_response_with_prefix(DEFAULT_PREFIX, service_type, action, args)
+}
+
+/// Like [`action_response`], but with given namespace prefix for the envelope instead of `s`, e.g. the one the controller used (see [`soap::prefix`](crate::xml::soap::prefix) and [`ControlContext::action_response`](crate::ControlContext::action_response)).
+#[must_use]
+pub fn action_response_with_prefix(
+    prefix: &str,
+    service_type: &str,
+    action: &str,
+    args: &[(&str, &str)],
+) -> String {
+    let mut body = String::new();
+    for (name, value) in args {
+        write!(body, "<{name}>{}</{name}>", escape(*value))
+            .expect("Writing to a String never fails");
+    }
+    envelope(
+        prefix,
+        &format!(r#"<u:{action}Response xmlns:u="{service_type}">{body}</u:{action}Response>"#),
+    )
+}
+
+/// Wraps an XML document into a `200 OK` response.
+#[must_use]
+pub fn xml_ok(body: impl Into<String>) -> Response {
+    (
+        StatusCode::OK,
+        [("Content-Type", XML_CONTENT_TYPE)],
+        body.into(),
+    )
+        .into_response()
+}
+
+/// Wraps a SOAP envelope into a `200 OK` response. See [`action_response`] for building the envelope.
+#[must_use]
+pub fn soap_response(envelope: String) -> Response {
+    xml_ok(envelope)
+}
+
+/// Builds a `500 Internal Server Error` response carrying a SOAP fault with given `UPnP` error code and description, e.g. `401 Invalid Action`. The description is escaped.
+#[must_use]
+pub fn soap_fault(code: u16, description: &str) -> Response {
+    let envelope = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/"><s:Body><s:Fault><faultcode>s:Client</faultcode><faultstring>UPnPError</faultstring><detail><UPnPError xmlns="urn:schemas-upnp-org:control-1-0"><errorCode>{code}</errorCode><errorDescription>{}</errorDescription></UPnPError></detail></s:Fault></s:Body></s:Envelope>"#,
+        escape(description)
+    );
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        [("Content-Type", XML_CONTENT_TYPE)],
+        envelope,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content_type(response: &Response) -> &str {
+        response
+            .headers()
+            .get("Content-Type")
+            .expect("Missing Content-Type")
+            .to_str()
+            .expect("Invalid Content-Type")
+    }
+
+    async fn body_string(response: Response) -> String {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("Failed to read body");
+        String::from_utf8(body.to_vec()).expect("Body is not UTF-8")
+    }
+
+    #[tokio::test]
+    async fn test_xml_ok() {
+        let response = xml_ok("<root/>");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(content_type(&response), XML_CONTENT_TYPE);
+        assert_eq!(body_string(response).await, "<root/>");
+    }
+
+    #[tokio::test]
+    async fn test_soap_response() {
+        let response = soap_response(action_response(
+            "urn:schemas-upnp-org:service:RenderingControl:1",
+            "GetVolume",
+            &[("CurrentVolume", "50")],
+        ));
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(content_type(&response), XML_CONTENT_TYPE);
+        assert!(body_string(response).await.contains(
+            r#"<u:GetVolumeResponse xmlns:u="urn:schemas-upnp-org:service:RenderingControl:1"><CurrentVolume>50</CurrentVolume></u:GetVolumeResponse>"#
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_soap_fault() {
+        let response = soap_fault(718, "Invalid <InstanceID>");
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(content_type(&response), XML_CONTENT_TYPE);
+        let body = body_string(response).await;
+        assert!(body.contains("<errorCode>718</errorCode>"));
+        assert!(body.contains("<errorDescription>Invalid &lt;InstanceID&gt;</errorDescription>"));
+    }
+}
@@ -0,0 +1,152 @@
+//! Shared state of running DMR instances, looked up by the instance itself.
+//!
+//! Since [`DMR::run`](super::DMR::run) takes `&'static self`, a running instance is identified by its type and address. Note that all instances of a zero-sized type share the same address, so only one of them can be running at a time.
+
+use super::{ControlContext, ssdp::SSDPServer};
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    ops::Deref,
+    sync::{Arc, LazyLock, Mutex, PoisonError},
+};
+
+/// State shared between a running DMR instance and its methods.
+#[derive(Debug)]
+pub struct Runtime {
+    /// The SSDP server of the instance, shared with its tasks.
+    pub ssdp: Arc<SSDPServer>,
+    /// The context shared by the HTTP handlers of the instance.
+    pub context: Arc<ControlContext>,
+}
+
+/// Key identifying a DMR instance.
+type Key = (TypeId, usize);
+
+/// Runtimes of the running DMR instances.
+static RUNNING: LazyLock<Mutex<HashMap<Key, Arc<Runtime>>>> = LazyLock::new(Mutex::default);
+
+/// The key of given DMR instance.
+fn key<T: ?Sized + 'static>(dmr: &T) -> Key {
+    (
+        TypeId::of::<T>(),
+        std::ptr::from_ref(dmr).cast::<()>().addr(),
+    )
+}
+
+/// Registration of a running DMR instance, dereferencing to its runtime.
+///
+/// Dropping it unregisters the instance, and stops the SSDP server with a single `ssdp:byebye` unless [`stop`](Registration::stop) already did. This happens however [`DMR::run`](super::DMR::run) exits: by returning, by being cancelled, or by a panic unwinding through it.
+#[derive(Debug)]
+pub struct Registration<'a, T: ?Sized + 'static> {
+    dmr: &'a T,
+    runtime: Arc<Runtime>,
+    stopped: bool,
+}
+
+impl<T: ?Sized + Sync + 'static> Registration<'_, T> {
+    /// Stops the SSDP server with repeated `ssdp:byebye` (see [`SSDPServer::stop`]), then unregisters the instance.
+    pub async fn stop(mut self) {
+        self.runtime.ssdp.stop().await;
+        self.stopped = true;
+    }
+}
+
+impl<T: ?Sized + 'static> Deref for Registration<'_, T> {
+    type Target = Runtime;
+    fn deref(&self) -> &Runtime {
+        &self.runtime
+    }
+}
+
+impl<T: ?Sized + 'static> Drop for Registration<'_, T> {
+    fn drop(&mut self) {
+        if !self.stopped {
+            self.runtime.ssdp.stop_now();
+        }
+        unregister(self.dmr);
+    }
+}
+
+/// Registers the runtime of a DMR instance that starts running, replacing any previous one. The instance is unregistered once the returned [`Registration`] is dropped.
+pub fn register<T: ?Sized + 'static>(dmr: &T, runtime: Runtime) -> Registration<'_, T> {
+    let runtime = Arc::new(runtime);
+    RUNNING
+        .lock()
+        .expect("Runtime registry poisoned")
+        .insert(key(dmr), Arc::clone(&runtime));
+    Registration {
+        dmr,
+        runtime,
+        stopped: false,
+    }
+}
+
+/// Gets the runtime of a running DMR instance.
+pub fn get<T: ?Sized + 'static>(dmr: &T) -> Option<Arc<Runtime>> {
+    RUNNING
+        .lock()
+        .expect("Runtime registry poisoned")
+        .get(&key(dmr))
+        .cloned()
+}
+
+/// Unregisters the runtime of a DMR instance that stopped running.
+fn unregister<T: ?Sized + 'static>(dmr: &T) {
+    // Called while unwinding, where panicking again would abort
+    RUNNING
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(&key(dmr));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DMROptions;
+    use std::{
+        net::{Ipv4Addr, SocketAddrV4},
+        time::Duration,
+    };
+    use tokio::{net::UdpSocket, time::timeout};
+
+    #[tokio::test]
+    async fn test_byebye_on_panic() {
+        static DMR: u8 = 0;
+        let group = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0))
+            .await
+            .expect("Failed to bind control point");
+        let group_address = group.local_addr().expect("No local address");
+        let mut ssdp = SSDPServer::new(&DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            ..Default::default()
+        })
+        .expect("Failed to create SSDP server");
+        ssdp.set_multicast_addr(group_address);
+
+        let task = tokio::spawn(async move {
+            let context = Arc::new(ControlContext::new(Arc::new(DMROptions::default())));
+            let _registration = register(
+                &DMR,
+                Runtime {
+                    ssdp: Arc::new(ssdp),
+                    context,
+                },
+            );
+            tokio::select! {
+                () = std::future::pending() => {}
+                () = async { panic!("Injected panic") } => {}
+            }
+        });
+        assert!(task.await.expect_err("Task should panic").is_panic());
+
+        let mut buf = [0u8; 4096];
+        let (size, _) = timeout(Duration::from_secs(1), group.recv_from(&mut buf))
+            .await
+            .expect("Timed out waiting for byebye")
+            .expect("Failed to receive byebye");
+        let notify = String::from_utf8_lossy(&buf[..size]);
+        assert!(notify.contains("NTS: ssdp:byebye\r\n"), "{notify}");
+        assert!(get(&DMR).is_none());
+    }
+}
@@ -0,0 +1,133 @@
+//! Startup self-check, fetching the device description via the advertised address.
+
+use super::client;
+use axum::http::{Method, StatusCode};
+use log::{error, info};
+use std::{
+    io::{Error, Result},
+    net::SocketAddr,
+    time::Duration,
+};
+use tokio::time::timeout;
+
+/// How long to wait for the device description.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Fetches the description at `path` from `address`, checking that it's the description of the device with given `uuid`.
+pub async fn check(address: SocketAddr, path: &str, uuid: &str) -> Result<()> {
+    let url = format!("http://{address}{path}");
+    let response = timeout(
+        TIMEOUT,
+        client::request(Method::GET, &url, &[], String::new()),
+    )
+    .await
+    .map_err(Error::other)??;
+    if response.status() != StatusCode::OK {
+        return Err(Error::other(format!(
+            "Unexpected status {}",
+            response.status()
+        )));
+    }
+    let udn = format!("<UDN>uuid:{uuid}</UDN>");
+    if !String::from_utf8_lossy(response.body()).contains(&udn) {
+        return Err(Error::other("Description belongs to another device"));
+    }
+    Ok(())
+}
+
+/// Runs the self-check, logging the outcome.
+pub async fn run(address: SocketAddr, path: &str, uuid: &str) {
+    match check(address, path, uuid).await {
+        Ok(()) => info!("Self-check passed: description reachable at http://{address}{path}"),
+        Err(e) => error!(
+            "Self-check FAILED: couldn't fetch http://{address}{path} ({e}). Control points won't reach this renderer either; check that `ip` is the address of the right interface and that no firewall blocks port {}",
+            address.port()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DMROptions, HTTPServer};
+    use std::{
+        net::{Ipv4Addr, Ipv6Addr, SocketAddrV4},
+        sync::Arc,
+    };
+    use tokio::net::TcpListener;
+
+    struct Server;
+
+    impl HTTPServer for Server {}
+
+    #[tokio::test]
+    async fn test_self_check_passes() {
+        let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0))
+            .await
+            .expect("Failed to bind");
+        let port = listener.local_addr().expect("No local address").port();
+        let options = Arc::new(DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            http_port: port,
+            uuid: "12345678-1234-5678-1234-567812345678".to_string(),
+            ..Default::default()
+        });
+        let handle = tokio::spawn(Server.serve_http(listener, options));
+
+        let address = SocketAddrV4::new(Ipv4Addr::LOCALHOST, port).into();
+        check(
+            address,
+            "/DeviceSpec",
+            "12345678-1234-5678-1234-567812345678",
+        )
+        .await
+        .expect("Self-check failed");
+        assert!(check(address, "/DeviceSpec", "another").await.is_err());
+
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_self_check_ipv6() {
+        let listener = TcpListener::bind((Ipv6Addr::LOCALHOST, 0))
+            .await
+            .expect("Failed to bind");
+        let address = listener.local_addr().expect("No local address");
+        let options = Arc::new(DMROptions {
+            ip: Ipv6Addr::LOCALHOST.into(),
+            http_port: address.port(),
+            uuid: "12345678-1234-5678-1234-567812345678".to_string(),
+            ..Default::default()
+        });
+        let handle = tokio::spawn(Server.serve_http(listener, options));
+
+        // Fetched from `http://[::1]:port/DeviceSpec`
+        check(
+            address,
+            "/DeviceSpec",
+            "12345678-1234-5678-1234-567812345678",
+        )
+        .await
+        .expect("Self-check failed");
+
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_self_check_unreachable() {
+        let listener = TcpListener::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0))
+            .await
+            .expect("Failed to bind");
+        let port = listener.local_addr().expect("No local address").port();
+        drop(listener);
+        assert!(
+            check(
+                SocketAddrV4::new(Ipv4Addr::LOCALHOST, port).into(),
+                "/DeviceSpec",
+                "any"
+            )
+            .await
+            .is_err()
+        );
+    }
+}
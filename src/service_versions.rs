@@ -0,0 +1,77 @@
+//! Versions of the services a renderer advertises, for controllers that prefer newer ones.
+
+use super::xml::{av_transport, connection_manager, rendering_control};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Versions of the services advertised to controllers, see [`DMROptions::service_versions`](super::DMROptions::service_versions).
+///
+/// All of them are `1` by default; fields missing from the config file stay `1`. The versions only change the advertised service types and the namespace of responses: the actions and state variables of version `1` are still the only ones supported, which newer versions are supersets of.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::{ServiceVersions, xml::av_transport};
+///
+/// let versions = ServiceVersions {
+///     av_transport: 2,
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     versions.versioned(av_transport::SERVICE_TYPE),
+///     "urn:schemas-upnp-org:service:AVTransport:2"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServiceVersions {
+    /// Version of `AVTransport`.
+    pub av_transport: u8,
+    /// Version of `RenderingControl`.
+    pub rendering_control: u8,
+    /// Version of `ConnectionManager`.
+    pub connection_manager: u8,
+}
+
+impl Default for ServiceVersions {
+    fn default() -> Self {
+        Self {
+            av_transport: 1,
+            rendering_control: 1,
+            connection_manager: 1,
+        }
+    }
+}
+
+impl ServiceVersions {
+    /// The configured version of given version-`1` service type, e.g. [`av_transport::SERVICE_TYPE`]. Other service types are returned unchanged.
+    #[must_use]
+    pub fn versioned(self, service_type: &str) -> Cow<'_, str> {
+        let version = match service_type {
+            av_transport::SERVICE_TYPE => self.av_transport,
+            rendering_control::SERVICE_TYPE => self.rendering_control,
+            connection_manager::SERVICE_TYPE => self.connection_manager,
+            _ => return Cow::Borrowed(service_type),
+        };
+        match service_type.strip_suffix(":1") {
+            Some(unversioned) if version != 1 => Cow::Owned(format!("{unversioned}:{version}")),
+            _ => Cow::Borrowed(service_type),
+        }
+    }
+
+    /// The service types of `RenderingControl`, `AVTransport` and `ConnectionManager`, in this order, with their configured versions.
+    #[must_use]
+    pub fn service_types(self) -> [String; 3] {
+        [
+            rendering_control::SERVICE_TYPE,
+            av_transport::SERVICE_TYPE,
+            connection_manager::SERVICE_TYPE,
+        ]
+        .map(|service_type| self.versioned(service_type).into_owned())
+    }
+
+    /// Whether all versions are at least `1`.
+    pub(crate) const fn is_valid(self) -> bool {
+        self.av_transport > 0 && self.rendering_control > 0 && self.connection_manager > 0
+    }
+}
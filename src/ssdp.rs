@@ -1,153 +1,481 @@
 //! SSDP-related code.
 
-use log::{error, info, trace};
-use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use super::{DMROptions, ServiceVersions, xml::device_description::DEVICE_TYPE};
+use ipnet::IpNet;
+use local_ip_address::list_afinet_netifas;
+use log::{debug, error, info, trace, warn};
+use socket2::{Domain, Protocol, SockAddr, SockRef, Socket, Type};
 use std::{
+    borrow::Cow,
+    collections::HashSet,
+    hash::{DefaultHasher, Hash, Hasher},
     io::{Error, ErrorKind, Result},
-    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
-    time::Duration,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
-use tokio::{net::UdpSocket, time::sleep};
+use tokio::{net::UdpSocket, task::JoinSet, time::sleep};
 
 /// A SSDP server implementation.
 #[derive(Debug)]
 pub struct SSDPServer {
-    socket: UdpSocket,
-    address: SocketAddrV4,
+    socket: Arc<UdpSocket>,
+    address: SocketAddr,
     uuid: String,
-    http_port: u16,
+    /// Interfaces multicast messages are sent and received on, with the `LOCATION` advertised on each.
+    interfaces: Vec<(IpAddr, String)>,
+    /// Held while sending multicast messages, since the interface they're sent out of is set on the whole socket.
+    multicast_lock: Mutex<()>,
+    recv_buffer: usize,
+    /// Where multicast messages are sent, [`Self::multicast_group`] except in tests.
+    multicast_addr: SocketAddr,
+    /// Versions of the services announced and answered as search targets.
+    service_versions: ServiceVersions,
+    /// Additional search targets announced and answered, as pairs of `ST` and `USN`.
+    extra_search_targets: Vec<(String, String)>,
+    /// The `SERVER` header of announcements and search responses.
+    server: String,
+    /// How long controllers may cache announcements and search responses, in seconds.
+    cache_max_age: u32,
+    /// Interval between `ssdp:alive` announcements, before jitter.
+    keep_alive_interval: Duration,
+    /// Number of times announcements are sent on startup and shutdown.
+    announce_repeats: u8,
+    /// Jitter of the keep-alive interval, in percent.
+    keep_alive_jitter: u8,
+    /// Whether to send an `ssdp:alive` burst on searches from new controllers.
+    alive_on_new_search: bool,
+    /// Subnets whose searches are answered, all if `None`.
+    respond_to_subnets: Option<Vec<IpNet>>,
+    /// Foreign addresses we've warned about sharing our UUID.
+    collisions: Mutex<HashSet<SocketAddr>>,
+    /// Controllers that have searched for us, and when the last burst answering a new one was sent.
+    searchers: Mutex<(HashSet<IpAddr>, Option<Instant>)>,
+    /// Time taken to answer searches.
+    search_latency: Mutex<SearchLatency>,
+    /// Network location signature sent as `01-NLS`, generated once per server so that controllers can tell restarts apart.
+    nls: String,
+    /// Boot instance of the device sent as `BOOTID.UPNP.ORG`, initially the Unix time the server was created at.
+    boot_id: AtomicU32,
+    /// Configuration of the device description sent as `CONFIGID.UPNP.ORG`, see [`config_id`].
+    config_id: AtomicU32,
+    /// Invoked with the sender and `ST` of each search before answering it, suppressing the answer if it returns `false`.
+    search_hook: Option<SearchHook>,
+}
+
+/// Decides whether a search is answered, given its sender and `ST` header.
+type SearchFilter = dyn Fn(SocketAddr, &str) -> bool + Send + Sync;
+
+/// A [`SearchFilter`] set with [`SSDPServer::with_search_hook`].
+struct SearchHook(Box<SearchFilter>);
+
+impl std::fmt::Debug for SearchHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SearchHook")
+    }
+}
+
+/// Statistics of the time taken to answer `M-SEARCH` requests, from handling the request until the last response is sent, including the random delay of up to `MX` seconds. Useful to tell whether slow discovery is due to the renderer or to the network.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLatency {
+    /// Number of searches answered.
+    pub count: u64,
+    /// Shortest time taken.
+    pub min: Duration,
+    /// Longest time taken.
+    pub max: Duration,
+    /// Total time taken.
+    pub total: Duration,
+}
+
+impl SearchLatency {
+    /// Average time taken, if any search was answered.
+    #[must_use]
+    pub fn average(&self) -> Option<Duration> {
+        u32::try_from(self.count)
+            .ok()
+            .filter(|&count| count > 0)
+            .map(|count| self.total / count)
+    }
+
+    /// Records the time taken to answer a search.
+    fn record(&mut self, latency: Duration) {
+        self.min = if self.count == 0 {
+            latency
+        } else {
+            self.min.min(latency)
+        };
+        self.max = self.max.max(latency);
+        self.total += latency;
+        self.count += 1;
+    }
 }
 
 impl SSDPServer {
     /// The multicast address used for SSDP discovery.
     const SSDP_MULTICAST_ADDR: SocketAddrV4 =
         SocketAddrV4::new(Ipv4Addr::new(239, 255, 255, 250), 1900);
-    /// The SSDP server's name.
-    const SSDP_SERVER_NAME: &'static str = "CustomSSDP/1.0";
+    /// The link-local multicast address used for SSDP discovery over IPv6, where announcements are sent.
+    const SSDP_MULTICAST_ADDR_V6: SocketAddrV6 =
+        SocketAddrV6::new(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0xc), 1900, 0, 0);
+    /// The site-local multicast address used for SSDP discovery over IPv6, which is only listened to.
+    const SSDP_SITE_LOCAL_ADDR_V6: SocketAddrV6 =
+        SocketAddrV6::new(Ipv6Addr::new(0xff05, 0, 0, 0, 0, 0, 0, 0xc), 1900, 0, 0);
+    /// The SSDP server's name, in the `OS/version UPnP/1.0 product/version` form of the spec, unless overridden by [`DMROptions::server_string`].
+    const SSDP_SERVER_NAME: &'static str =
+        concat!("Rust/1 UPnP/1.0 dlna-dmr/", env!("CARGO_PKG_VERSION"));
+    /// The `OPT` header of announcements and search responses, declaring the `01-` namespace of the `01-NLS` header.
+    const OPT: &'static str = "\"http://schemas.upnp.org/upnp/1/0/\"; ns=01";
     // /// The timeout for reading from the socket in milliseconds.
     // const SOCKET_READ_TIMEOUT: u64 = 1000;
-    /// Interval for sending keep-alive messages.
-    const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(60);
+    /// Number of maximum-sized messages the socket's receive buffer (`SO_RCVBUF`) should hold, absorbing bursts of searches.
+    const SOCKET_RECV_BUFFER_MESSAGES: usize = 16;
+    /// Minimum interval between `ssdp:alive` bursts triggered by new controllers.
+    const ALIVE_BURST_DEBOUNCE: Duration = Duration::from_secs(5);
+    /// Average gap between repeated announcements on startup.
+    const STARTUP_REPEAT_GAP: Duration = Duration::from_millis(200);
+    /// Gap between repeated announcements on shutdown, kept short since it blocks the thread.
+    const BYEBYE_REPEAT_GAP: Duration = Duration::from_millis(50);
+    /// Maximum number of controllers remembered, after which all are forgotten.
+    const MAX_SEARCHERS: usize = 1024;
+
+    /// The multicast group SSDP messages are sent to, for the IP version of `ip`.
+    const fn multicast_group(ip: IpAddr) -> SocketAddr {
+        match ip {
+            IpAddr::V4(_) => SocketAddr::V4(Self::SSDP_MULTICAST_ADDR),
+            IpAddr::V6(_) => SocketAddr::V6(Self::SSDP_MULTICAST_ADDR_V6),
+        }
+    }
 
-    /// Creates a new SSDP server bound to the specified address with the given UUID and HTTP port.
-    pub async fn new(address: SocketAddrV4, uuid: String, http_port: u16) -> Result<Self> {
-        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    /// Creates a new SSDP server with the given options, using IPv6 if [`DMROptions::ip`] is an IPv6 address.
+    pub fn new(options: &DMROptions) -> Result<Self> {
+        let unspecified = match options.ip {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        };
+        let socket = Socket::new(
+            Domain::for_address(SocketAddr::new(unspecified, 0)),
+            Type::DGRAM,
+            Some(Protocol::UDP),
+        )?;
+        if options.ip.is_ipv6() {
+            socket.set_only_v6(true)?;
+        }
         socket.set_nonblocking(true)?;
         socket.set_reuse_address(true)?;
-        socket.bind(&SockAddr::from(SocketAddrV4::new(
-            Ipv4Addr::UNSPECIFIED,
-            address.port(),
-        )))?;
+        let socket_buffer = options
+            .ssdp_recv_buffer
+            .saturating_mul(Self::SOCKET_RECV_BUFFER_MESSAGES);
+        if let Err(e) = socket.set_recv_buffer_size(socket_buffer) {
+            warn!("Failed to set SSDP socket receive buffer to {socket_buffer} bytes: {e}");
+        }
+        let port = bind_with_fallback(options.ssdp_port, options.ssdp_port_fallback, |port| {
+            socket.bind(&SockAddr::from(SocketAddr::new(unspecified, port)))
+        })?;
+        let port = socket
+            .local_addr()?
+            .as_socket()
+            .map_or(port, |local| local.port());
+        if port != options.ssdp_port {
+            warn!(
+                "SSDP port {} is in use, bound to port {port} instead; multicast searches won't be received",
+                options.ssdp_port
+            );
+        }
         // socket.set_read_timeout(Some(Duration::from_millis(Self::SOCKET_READ_TIMEOUT)))?; // FIXME: Do we need this?
-        // Set the socket to allow broadcast.
-        socket.set_broadcast(true)?;
-        // Join the SSDP multicast group.
-        socket.join_multicast_v4(
-            Self::SSDP_MULTICAST_ADDR.ip(), // Multicast address
-            address.ip(),                   // Use the unspecified address for the local interface
-        )?;
+        let interfaces = select_interfaces(options.ip, options.interfaces.as_deref(), || {
+            list_afinet_netifas().map_or_else(
+                |e| {
+                    warn!("Failed to list network interfaces: {e}");
+                    Vec::new()
+                },
+                |interfaces| interfaces.into_iter().map(|(_, ip)| ip).collect(),
+            )
+        });
+        match options.ip {
+            IpAddr::V4(_) => {
+                // Set the socket to allow broadcast.
+                socket.set_broadcast(true)?;
+                // Join the SSDP multicast group, on the interface of each address.
+                join_multicast_v4(&socket, *Self::SSDP_MULTICAST_ADDR.ip(), &interfaces)?;
+            }
+            IpAddr::V6(_) => {
+                // Join the link-local and site-local groups, on the default interface.
+                socket.join_multicast_v6(Self::SSDP_MULTICAST_ADDR_V6.ip(), 0)?;
+                socket.join_multicast_v6(Self::SSDP_SITE_LOCAL_ADDR_V6.ip(), 0)?;
+            }
+        }
         // Convert the socket to a Tokio UdpSocket.
-        let socket = UdpSocket::from_std(socket.into())?;
+        let socket = Arc::new(UdpSocket::from_std(socket.into())?);
 
         Ok(Self {
             socket,
-            address,
-            uuid,
-            http_port,
+            address: SocketAddr::new(options.ip, port),
+            uuid: options.uuid.clone(),
+            interfaces: interfaces
+                .into_iter()
+                .map(|ip| {
+                    // Each interface is only reachable via its own address if listening on all of them
+                    let location = if options.ip.is_unspecified() {
+                        options.location_at(ip)
+                    } else {
+                        options.location()
+                    };
+                    (ip, location)
+                })
+                .collect(),
+            multicast_lock: Mutex::default(),
+            recv_buffer: options.ssdp_recv_buffer,
+            multicast_addr: Self::multicast_group(options.ip),
+            service_versions: options.service_versions,
+            extra_search_targets: options.extra_search_targets.clone(),
+            server: options
+                .server_string
+                .clone()
+                .unwrap_or_else(|| Self::SSDP_SERVER_NAME.to_string()),
+            cache_max_age: options.ssdp_cache_max_age,
+            keep_alive_interval: options.ssdp_keep_alive(),
+            announce_repeats: options.ssdp_announce_repeats,
+            keep_alive_jitter: options.ssdp_keep_alive_jitter,
+            alive_on_new_search: options.ssdp_alive_on_new_search,
+            respond_to_subnets: options.respond_to_subnets.clone(),
+            collisions: Mutex::default(),
+            searchers: Mutex::default(),
+            search_latency: Mutex::default(),
+            nls: uuid::Uuid::new_v4().simple().to_string(),
+            boot_id: AtomicU32::new(boot_id(SystemTime::now())),
+            config_id: AtomicU32::new(config_id(options)),
+            search_hook: None,
         })
     }
 
-    /// Send a SSDP notify message with given Notification Type, Notification Sub Type and Unique Service Name.
+    /// Sets a hook invoked with the sender and `ST` header of each search before answering it, suppressing the answer if it returns `false`.
+    #[must_use]
+    pub fn with_search_hook(
+        mut self,
+        hook: impl Fn(SocketAddr, &str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.search_hook = Some(SearchHook(Box::new(hook)));
+        self
+    }
+
+    /// Redirects multicast messages, e.g. to a control point.
+    #[cfg(test)]
+    pub const fn set_multicast_addr(&mut self, multicast_addr: SocketAddr) {
+        self.multicast_addr = multicast_addr;
+    }
+
+    /// The port the SSDP server is bound to.
+    pub const fn port(&self) -> u16 {
+        self.address.port()
+    }
+
+    /// The `LOCATION` advertised in replies to `peer` sent on `socket`, i.e. that of the interface `socket` is bound to, if it's bound to one of them.
+    fn location_via(&self, socket: &UdpSocket, peer: IpAddr) -> &str {
+        let local = socket.local_addr().ok().map(|local| unmapped(local).ip());
+        self.interfaces
+            .iter()
+            .find(|(ip, _)| Some(*ip) == local)
+            .map_or_else(|| self.location_for(peer), |(_, location)| location)
+    }
+
+    /// The `LOCATION` advertised to `peer`, i.e. that of the interface the OS routes messages to it through.
+    fn location_for(&self, peer: IpAddr) -> &str {
+        let (first, rest) = self
+            .interfaces
+            .split_first()
+            .expect("There's at least one interface");
+        if rest.is_empty() {
+            return &first.1;
+        }
+        let local = local_address_towards(peer);
+        self.interfaces
+            .iter()
+            .find(|(ip, _)| Some(*ip) == local)
+            .unwrap_or(first)
+            .1
+            .as_str()
+    }
+
+    /// Build a SSDP notify message with given `LOCATION`, Notification Type, Notification Sub Type and Unique Service Name.
     ///
     /// ## Arguments
     ///
+    /// - `location`: URL of the device description
     /// - `nt`: Notification Type
     /// - `nts`: Notification Sub Type
     /// - `usn`: Unique Service Name
-    async fn notify(&self, nt: &str, nts: &str, usn: &str) -> Result<()> {
-        let message = format!(
+    fn notify_message(&self, location: &str, nt: &str, nts: &str, usn: &str) -> String {
+        format!(
             "NOTIFY * HTTP/1.1\r\n\
              HOST: {}\r\n\
              NT: {}\r\n\
              NTS: {}\r\n\
              USN: {}\r\n\
-             LOCATION: http://{}/description.xml\r\n\
-             CACHE-CONTROL: max-age=1800\r\n\
+             LOCATION: {}\r\n\
+             CACHE-CONTROL: max-age={}\r\n\
              SERVER: {}\r\n\
+             OPT: {}\r\n\
+             01-NLS: {}\r\n\
+             BOOTID.UPNP.ORG: {}\r\n\
+             CONFIGID.UPNP.ORG: {}\r\n\
              \r\n",
-            Self::SSDP_MULTICAST_ADDR,
+            Self::multicast_group(self.address.ip()),
             nt,
             nts,
             usn,
-            self.address,
-            Self::SSDP_SERVER_NAME
-        );
-        self.socket
-            .send_to(message.as_bytes(), &Self::SSDP_MULTICAST_ADDR)
-            .await?;
-        Ok(())
+            location,
+            self.cache_max_age,
+            self.server,
+            Self::OPT,
+            self.nls,
+            self.boot_id(),
+            self.config_id()
+        )
     }
 
-    /// Broadcast a notify message for given `service` with given Notification Sub Type.
-    async fn notify_service(&self, service: &str, nts: &str) -> Result<()> {
-        self.notify(
-            &format!("urn:schemas-upnp-org:service:{service}:1"),
-            nts,
-            &format!(
-                "uuid:{uuid}::urn:schemas-upnp-org:service:{service}:1",
-                uuid = self.uuid
-            ),
+    /// Build a `ssdp:update` notify message with given `LOCATION`, Notification Type and Unique Service Name, announcing that the boot ID changes to `next_boot_id`.
+    fn update_message(&self, location: &str, nt: &str, usn: &str, next_boot_id: u32) -> String {
+        format!(
+            "NOTIFY * HTTP/1.1\r\n\
+             HOST: {}\r\n\
+             LOCATION: {}\r\n\
+             NT: {}\r\n\
+             NTS: ssdp:update\r\n\
+             USN: {}\r\n\
+             OPT: {}\r\n\
+             01-NLS: {}\r\n\
+             BOOTID.UPNP.ORG: {}\r\n\
+             CONFIGID.UPNP.ORG: {}\r\n\
+             NEXTBOOTID.UPNP.ORG: {}\r\n\
+             \r\n",
+            Self::multicast_group(self.address.ip()),
+            location,
+            nt,
+            usn,
+            Self::OPT,
+            self.nls,
+            self.boot_id(),
+            self.config_id(),
+            next_boot_id
         )
-        .await
     }
 
-    /// Broadcast multiple relevant notify messages with given Notification Sub Type.
-    async fn notify_all(&self, nts: &str) -> Result<()> {
-        let uuid_with_prefix = format!("uuid:{}", self.uuid);
+    /// Build multiple relevant notify messages with given `LOCATION` and Notification Sub Type, one for each of [`Self::targets`].
+    fn notify_messages(&self, location: &str, nts: &str) -> Vec<String> {
+        self.targets()
+            .iter()
+            .map(|(nt, usn)| self.notify_message(location, nt, nts, usn))
+            .collect()
+    }
 
-        self.notify(
-            "upnp:rootdevice",
-            nts,
-            &format!("{uuid_with_prefix}::upnp:rootdevice"),
-        )
-        .await?;
-        self.notify(&uuid_with_prefix, nts, &uuid_with_prefix)
-            .await?;
-        for service in ["RenderingControl", "AVTransport", "ConnectionManager"] {
-            self.notify_service(service, nts).await?;
+    /// Send multiple relevant notify messages with given Notification Sub Type to `destination`.
+    async fn notify_all(&self, destination: SocketAddr, nts: &str) -> Result<()> {
+        for message in self.notify_messages(self.location_for(destination.ip()), nts) {
+            self.socket
+                .send_to(message.as_bytes(), &destination)
+                .await?;
         }
-
         Ok(())
     }
 
+    /// Send multiple relevant `ssdp:alive` messages to a single controller.
+    pub async fn announce_to(&self, controller: SocketAddr) -> Result<()> {
+        self.notify_all(controller, "ssdp:alive").await
+    }
+
     /// Broadcast multiple relevant `ssdp:alive` messages.
-    async fn alive(&self) -> Result<()> {
-        self.notify_all("ssdp:alive").await
+    fn alive(&self) -> Result<()> {
+        self.notify_multicast(|location| self.notify_messages(location, "ssdp:alive"))
     }
 
-    /// Broadcast multiple relevant `ssdp:alive` messages periodically. (Keep-alive / Heartbeat)
+    /// Tells controllers the device description changed, so that they fetch it again instead of using a cached one: broadcasts `ssdp:update` messages announcing the next `BOOTID.UPNP.ORG`, then switches to it and broadcasts `ssdp:alive` messages. Returns the new boot ID.
+    pub fn announce_update(&self) -> Result<u32> {
+        let next = self.boot_id().wrapping_add(1) & BOOT_ID_MAX;
+        self.notify_multicast(|location| {
+            self.targets()
+                .iter()
+                .map(|(nt, usn)| self.update_message(location, nt, usn, next))
+                .collect()
+        })?;
+        let boot_id = self.bump_bootid();
+        self.alive()?;
+        Ok(boot_id)
+    }
+
+    /// Broadcast the notify messages built by `messages` for each interface, given its `LOCATION`, out of that interface. Sent directly on the non-blocking socket instead of awaiting, so that it works while unwinding.
+    fn notify_multicast(&self, messages: impl Fn(&str) -> Vec<String>) -> Result<()> {
+        let socket = SockRef::from(&self.socket);
+        let destination = SockAddr::from(self.multicast_addr);
+        let _lock = lock(&self.multicast_lock);
+        for (ip, location) in &self.interfaces {
+            if let IpAddr::V4(ip) = ip {
+                socket.set_multicast_if_v4(ip)?;
+            }
+            for message in messages(location) {
+                socket.send_to(message.as_bytes(), &destination)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Broadcast multiple relevant `ssdp:alive` messages periodically, after [`Self::announce_startup`]. (Keep-alive / Heartbeat)
     pub async fn keep_alive(&self) {
         info!("Starting SSDP keep-alive thread");
+        self.announce_startup().await;
         loop {
-            if let Err(e) = self.alive().await {
-                error!("Failed to send SSDP alive message: {e}");
-            } else {
-                trace!("SSDP alive message sent");
+            sleep(jittered(
+                self.keep_alive_interval,
+                self.keep_alive_jitter,
+                fastrand::f64(),
+            ))
+            .await;
+            self.send_alive();
+        }
+    }
+
+    /// Broadcast multiple relevant `ssdp:alive` messages several times, with small random gaps in between, since the first multicast messages are frequently dropped.
+    async fn announce_startup(&self) {
+        for repeat in 0..self.announce_repeats {
+            if repeat > 0 {
+                sleep(jittered(Self::STARTUP_REPEAT_GAP, 50, fastrand::f64())).await;
             }
-            sleep(Self::KEEP_ALIVE_INTERVAL).await;
+            self.send_alive();
+        }
+    }
+
+    /// Broadcast multiple relevant `ssdp:alive` messages, logging failures.
+    fn send_alive(&self) {
+        if let Err(e) = self.alive() {
+            error!("Failed to send SSDP alive message: {e}");
+        } else {
+            trace!("SSDP alive message sent");
         }
     }
 
     /// Broadcast multiple relevant `ssdp:byebye` messages.
-    async fn byebye(&self) -> Result<()> {
-        self.notify_all("ssdp:byebye").await
+    fn byebye(&self) -> Result<()> {
+        self.notify_multicast(|location| self.notify_messages(location, "ssdp:byebye"))
     }
 
-    /// Answer a SSDP message from given address.
-    async fn answer(&self, address: SocketAddrV4, message: &str) -> Result<()> {
+    /// Answer a SSDP message from given address, received on `socket`.
+    async fn answer(&self, socket: &UdpSocket, address: SocketAddr, message: &str) -> Result<()> {
         if message.starts_with("M-SEARCH") {
-            self.answer_search(address, message).await
-        } else if message.starts_with("NOTIFY") {
+            if !self.responds_to(address.ip()) {
+                trace!("Ignoring M-SEARCH from {address} outside of the allowed subnets");
+                return Ok(());
+            }
+            let answered = self.answer_search(socket, address, message).await?;
+            if answered && self.alive_on_new_search && self.is_new_searcher(address.ip()) {
+                trace!("New controller {address} searched, sending SSDP alive burst");
+                self.alive()?;
+            }
+            Ok(())
+        } else if message.starts_with("NOTIFY") || message.starts_with("HTTP/1.1 200 OK") {
+            self.detect_uuid_collision(address, message);
             Ok(())
         } else {
             Err(Error::new(
@@ -157,49 +485,232 @@ impl SSDPServer {
         }
     }
 
-    /// Answer a M-SEARCH request.
-    async fn answer_search(&self, address: SocketAddrV4, _message: &str) -> Result<()> {
-        // TODO: Check if we should respond to this M-SEARCH request.
-        let response = format!(
+    /// Warns once per source if `message`, an announcement or search response, carries our UUID but didn't come from our own socket, i.e. another device shares our UUID. Returns whether a warning was logged.
+    fn detect_uuid_collision(&self, address: SocketAddr, message: &str) -> bool {
+        let own = address == self.address
+            || (address.port() == self.address.port()
+                && self.interfaces.iter().any(|(ip, _)| *ip == address.ip()));
+        if own {
+            return false;
+        }
+        let ours = header(message, "USN")
+            .and_then(|usn| usn.strip_prefix("uuid:"))
+            .is_some_and(|usn| {
+                usn.strip_prefix(self.uuid.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            });
+        if !ours {
+            return false;
+        }
+        let mut collisions = lock(&self.collisions);
+        if collisions.len() >= Self::MAX_SEARCHERS {
+            collisions.clear();
+        }
+        let new = collisions.insert(address);
+        drop(collisions);
+        if new {
+            error!(
+                "UUID collision: {address} announces our UUID {}! Controllers will confuse both devices; make sure each renderer has its own UUID",
+                self.uuid
+            );
+        }
+        new
+    }
+
+    /// Remembers a controller that searched, returning whether it hasn't searched before and an `ssdp:alive` burst is due, i.e. the last one was sent long enough ago.
+    fn is_new_searcher(&self, ip: IpAddr) -> bool {
+        let mut searchers = lock(&self.searchers);
+        let (seen, last_burst) = &mut *searchers;
+        if seen.len() >= Self::MAX_SEARCHERS {
+            seen.clear();
+        }
+        if !seen.insert(ip) {
+            return false;
+        }
+        let now = Instant::now();
+        if last_burst.is_some_and(|last| now.duration_since(last) < Self::ALIVE_BURST_DEBOUNCE) {
+            return false;
+        }
+        *last_burst = Some(now);
+        drop(searchers);
+        true
+    }
+
+    /// Whether searches from given IP are answered, according to [`DMROptions::respond_to_subnets`].
+    fn responds_to(&self, ip: IpAddr) -> bool {
+        self.respond_to_subnets
+            .as_ref()
+            .is_none_or(|subnets| subnets.iter().any(|subnet| subnet.contains(&ip)))
+    }
+
+    /// Answer a M-SEARCH request received on `socket`, if its `ST` header matches one of our search targets, echoing it in the responses. Returns whether it was answered.
+    ///
+    /// Replies are sent on the same socket, so that they leave through the interface the search arrived on and come from an address the control point can reach.
+    ///
+    /// Replies are delayed by a random duration up to the `MX` header, see [`reply_delay`], so that many devices answering the same search don't flood the network. The delay is included in [`Self::search_latency`], as controllers wait for it as well.
+    async fn answer_search(
+        &self,
+        socket: &UdpSocket,
+        address: SocketAddr,
+        message: &str,
+    ) -> Result<bool> {
+        // Replies go back to the searching control point only, never to a group.
+        let broadcast = matches!(address.ip(), IpAddr::V4(ip) if ip.is_broadcast());
+        if address.ip().is_multicast() || broadcast {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Refusing to answer M-SEARCH to non-unicast address {address}"),
+            ));
+        }
+        let st = header(message, "ST");
+        if let Some(SearchHook(hook)) = &self.search_hook
+            && !hook(address, st.unwrap_or_default())
+        {
+            trace!("Ignoring M-SEARCH from {address} as the search hook declined it");
+            return Ok(false);
+        }
+        let targets = self.matching_targets(st);
+        if targets.is_empty() {
+            trace!("Ignoring M-SEARCH from {address} for targets we don't provide");
+            return Ok(false);
+        }
+        let started = tokio::time::Instant::now();
+        sleep(reply_delay(header(message, "MX"), fastrand::f64())).await;
+        let location = self.location_via(socket, address.ip());
+        for (st, usn) in targets {
+            let response = self.search_response(location, &st, &usn);
+            trace!("Sending SSDP response to {address}: {response}");
+            socket.send_to(response.as_bytes(), address).await?;
+        }
+        let latency = started.elapsed();
+        debug!("Answered M-SEARCH from {address} in {latency:?}");
+        lock(&self.search_latency).record(latency);
+
+        Ok(true)
+    }
+
+    /// Targets we announce and answer searches for, as pairs of `NT` (or `ST`) and `USN`: the root device, the device itself, its type, each service and the additional targets.
+    fn targets(&self) -> Vec<(String, String)> {
+        let uuid_with_prefix = format!("uuid:{}", self.uuid);
+        let mut targets = vec![
+            (
+                "upnp:rootdevice".to_string(),
+                format!("{uuid_with_prefix}::upnp:rootdevice"),
+            ),
+            (uuid_with_prefix.clone(), uuid_with_prefix.clone()),
+        ];
+        let services = self.service_versions.service_types();
+        for st in std::iter::once(DEVICE_TYPE.to_string()).chain(services) {
+            let usn = format!("{uuid_with_prefix}::{st}");
+            targets.push((st, usn));
+        }
+        targets.extend(self.extra_search_targets.iter().cloned());
+        targets
+    }
+
+    /// Search targets matching the `ST` header of a search, i.e. all of them for `ssdp:all`, and none if it's missing or none matches. As required by UDA 1.1, searches for a lower version of a type we provide are answered too, echoing the searched `ST`, see [`answers_search_for`].
+    fn matching_targets(&self, st: Option<&str>) -> Vec<(String, String)> {
+        let targets = self.targets();
+        match st {
+            Some("ssdp:all") => targets,
+            Some(st) => targets
+                .into_iter()
+                .filter(|(target, _)| answers_search_for(target, st))
+                .map(|(_, usn)| (st.to_string(), usn))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The current `BOOTID.UPNP.ORG`.
+    pub fn boot_id(&self) -> u32 {
+        self.boot_id.load(Ordering::Relaxed)
+    }
+
+    /// Increments the `BOOTID.UPNP.ORG`, telling controllers the device rebooted or changed networks, e.g. after announcing `ssdp:update`. Returns the new boot ID.
+    pub fn bump_bootid(&self) -> u32 {
+        let previous = self
+            .boot_id
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| {
+                Some(id.wrapping_add(1) & BOOT_ID_MAX)
+            })
+            .unwrap_or_else(|id| id);
+        previous.wrapping_add(1) & BOOT_ID_MAX
+    }
+
+    /// The current `CONFIGID.UPNP.ORG`.
+    pub fn config_id(&self) -> u32 {
+        self.config_id.load(Ordering::Relaxed)
+    }
+
+    /// Updates the `CONFIGID.UPNP.ORG` after the device description was re-rendered with given options.
+    pub fn reconfigure(&self, options: &DMROptions) {
+        self.config_id.store(config_id(options), Ordering::Relaxed);
+    }
+
+    /// Statistics of the time taken to answer searches so far.
+    pub fn search_latency(&self) -> SearchLatency {
+        *lock(&self.search_latency)
+    }
+
+    /// Build a response to a search, for given `LOCATION`, Search Target and Unique Service Name.
+    fn search_response(&self, location: &str, st: &str, usn: &str) -> String {
+        format!(
             "HTTP/1.1 200 OK\r\n\
-             ST: upnp:rootdevice\r\n\
-             USN: uuid:{}::upnp:rootdevice\r\n\
-             Location: http://{}:{}/DeviceSpec\r\n\
-             OPT: \"http://schemas.upnp.org/upnp/1/0/\"; ns=01\r\n\
-             Cache-Control: max-age=900\r\n\
+             ST: {st}\r\n\
+             USN: {usn}\r\n\
+             Location: {}\r\n\
+             OPT: {}\r\n\
+             01-NLS: {}\r\n\
+             BOOTID.UPNP.ORG: {}\r\n\
+             CONFIGID.UPNP.ORG: {}\r\n\
+             Cache-Control: max-age={}\r\n\
              Server: {}\r\n\
              EXT:\r\n\
              Date: {}\r\n\
             \r\n",
-            self.uuid,
-            self.address.ip(),
-            self.http_port,
-            Self::SSDP_SERVER_NAME,
+            location,
+            Self::OPT,
+            self.nls,
+            self.boot_id(),
+            self.config_id(),
+            self.cache_max_age,
+            self.server,
             chrono::Utc::now().format("%a, %d %b %Y %H:%M:%S GMT")
-        );
-        trace!("Sending SSDP response to {address}: {response}");
-        self.socket.send_to(response.as_bytes(), address).await?;
+        )
+    }
 
-        Ok(())
+    /// Receives a SSDP message into `buf`, returning the socket it arrived on, its sender and content.
+    async fn receive<'a>(
+        &self,
+        buf: &'a mut [u8],
+    ) -> Result<(Arc<UdpSocket>, SocketAddr, Cow<'a, str>)> {
+        let (size, addr) = self.socket.recv_from(buf).await?;
+        Ok((
+            Arc::clone(&self.socket),
+            unmapped(addr),
+            String::from_utf8_lossy(&buf[..size]),
+        ))
     }
 
-    /// Starts the SSDP server.
-    pub async fn run(&self) {
+    /// Starts the SSDP server. Messages are answered in separate tasks, so that delayed search replies don't hold up receiving; they're aborted when this future is dropped.
+    pub async fn run(self: Arc<Self>) {
         info!("SSDP server running on {}", self.address);
 
-        let mut buf = [0u8; 4096];
+        let mut buf = vec![0u8; self.recv_buffer];
+        let mut replies = JoinSet::new();
         loop {
-            match self.socket.recv_from(&mut buf).await {
-                Ok((size, addr)) => {
-                    let message = String::from_utf8_lossy(&buf[..size]);
-                    let SocketAddr::V4(ipv4) = addr else {
-                        error!("Received non-IPv4 address: {addr:?}");
-                        continue;
-                    };
-                    trace!("Received SSDP message from {ipv4}: {message}");
-                    if let Err(e) = self.answer(ipv4, &message).await {
-                        error!("Error answering SSDP message: {e}");
-                    }
+            match self.receive(&mut buf).await {
+                Ok((socket, address, message)) => {
+                    trace!("Received SSDP message from {address}: {message}");
+                    while replies.try_join_next().is_some() {}
+                    let server = Arc::clone(&self);
+                    let message = message.into_owned();
+                    replies.spawn(async move {
+                        if let Err(e) = server.answer(&socket, address, &message).await {
+                            error!("Error answering SSDP message: {e}");
+                        }
+                    });
                 }
                 // FIXME: Do we need this?
                 Err(e) if e.kind() == ErrorKind::WouldBlock => {} // Non-blocking mode, just do nothing.
@@ -210,12 +721,1228 @@ impl SSDPServer {
         }
     }
 
-    /// Stops the SSDP server.
+    /// Runs [`keep_alive`](Self::keep_alive) and [`run`](Self::run) as independent tasks sharing the socket, so that neither cancels the other if it ever returns or panics.
+    pub async fn serve(self: Arc<Self>) {
+        let keep_alive = Arc::clone(&self);
+        run_independently(
+            async move {
+                keep_alive.keep_alive().await;
+            },
+            async move { self.run().await },
+        )
+        .await;
+    }
+
+    /// Stops the SSDP server, broadcasting `ssdp:byebye` messages right away, repeated a few times shortly apart like the startup announcements.
     pub async fn stop(&self) {
-        if let Err(e) = self.byebye().await {
+        for repeat in 0..self.announce_repeats {
+            if repeat > 0 {
+                sleep(Self::BYEBYE_REPEAT_GAP).await;
+            }
+            if let Err(e) = self.byebye() {
+                error!("Failed to send SSDP byebye message: {e}");
+                return;
+            }
+        }
+        info!("SSDP server stopped");
+    }
+
+    /// Stops the SSDP server like [`stop`](Self::stop), but broadcasting `ssdp:byebye` messages only once, without waiting. Safe to call from `Drop`, even while unwinding.
+    pub fn stop_now(&self) {
+        if let Err(e) = self.byebye() {
             error!("Failed to send SSDP byebye message: {e}");
-        } else {
-            info!("SSDP server stopped");
+            return;
+        }
+        info!("SSDP server stopped");
+    }
+}
+
+/// Whether `target` answers a search for `st`, i.e. they're equal, or both are `urn:` device or service types differing only in version, with `st` at most the version of `target`. For example, `urn:schemas-upnp-org:service:AVTransport:2` answers searches for version `1` but not `3`.
+fn answers_search_for(target: &str, st: &str) -> bool {
+    let split = |urn: &str| {
+        let (kind, version) = urn.strip_prefix("urn:")?.rsplit_once(':')?;
+        Some((kind.to_string(), version.parse::<u32>().ok()?))
+    };
+    target == st
+        || matches!(
+            (split(target), split(st)),
+            (Some((kind, version)), Some((searched_kind, searched)))
+                if kind == searched_kind && (1..=version).contains(&searched)
+        )
+}
+
+/// Locks `mutex`, ignoring poisoning as the protected data stays consistent.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Spawns both futures as tasks, waiting until both of them finish. Unlike racing them, one returning or panicking leaves the other running. Both tasks are aborted if the returned future is dropped.
+async fn run_independently(
+    a: impl Future<Output = ()> + Send + 'static,
+    b: impl Future<Output = ()> + Send + 'static,
+) {
+    let mut tasks = JoinSet::new();
+    tasks.spawn(a);
+    tasks.spawn(b);
+    while let Some(result) = tasks.join_next().await {
+        if let Err(e) = result {
+            error!("SSDP task failed: {e}");
+        }
+    }
+}
+
+/// Selects the interfaces, by address, to send and receive multicast messages on: the `pinned` ones if any, otherwise all non-loopback IPv4 ones of the `available` addresses if `ip` is `0.0.0.0` (falling back to `ip` itself if there's none), and only `ip` otherwise.
+fn select_interfaces(
+    ip: IpAddr,
+    pinned: Option<&[Ipv4Addr]>,
+    available: impl FnOnce() -> Vec<IpAddr>,
+) -> Vec<IpAddr> {
+    if let Some(pinned) = pinned.filter(|pinned| !pinned.is_empty()) {
+        return pinned.iter().copied().map(IpAddr::V4).collect();
+    }
+    if ip != IpAddr::V4(Ipv4Addr::UNSPECIFIED) {
+        return vec![ip];
+    }
+    let mut interfaces: Vec<IpAddr> = available()
+        .into_iter()
+        .filter(|available| available.is_ipv4() && !available.is_loopback())
+        .collect();
+    interfaces.dedup();
+    if interfaces.is_empty() {
+        warn!(
+            "No IPv4 network interface found, joining the SSDP multicast group on the default one"
+        );
+        interfaces.push(ip);
+    }
+    interfaces
+}
+
+/// Joins `group` on each IPv4 interface of `interfaces`, failing only if it can't be joined on any of them.
+fn join_multicast_v4(socket: &Socket, group: Ipv4Addr, interfaces: &[IpAddr]) -> Result<()> {
+    let mut joined = false;
+    let mut last_error = None;
+    for ip in interfaces {
+        let IpAddr::V4(ip) = ip else { continue };
+        match socket.join_multicast_v4(&group, ip) {
+            Ok(()) => joined = true,
+            Err(e) => {
+                warn!("Failed to join the SSDP multicast group on {ip}: {e}");
+                last_error = Some(e);
+            }
+        }
+    }
+    match last_error {
+        Some(e) if !joined => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// The local address the OS routes messages to `peer` from, if any.
+fn local_address_towards(peer: IpAddr) -> Option<IpAddr> {
+    let unspecified = if peer.is_ipv4() {
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+    } else {
+        IpAddr::V6(Ipv6Addr::UNSPECIFIED)
+    };
+    let socket = std::net::UdpSocket::bind(SocketAddr::new(unspecified, 0)).ok()?;
+    socket.connect(SocketAddr::new(peer, 1900)).ok()?;
+    socket.local_addr().ok().map(|local| local.ip())
+}
+
+/// Converts IPv4-mapped IPv6 addresses (`::ffff:a.b.c.d`) as surfaced by dual-stack sockets to IPv4 ones, leaving other addresses as is.
+fn unmapped(address: SocketAddr) -> SocketAddr {
+    match address {
+        SocketAddr::V6(ipv6) => ipv6.ip().to_ipv4_mapped().map_or(address, |ip| {
+            SocketAddr::V4(SocketAddrV4::new(ip, ipv6.port()))
+        }),
+        SocketAddr::V4(_) => address,
+    }
+}
+
+/// Extracts the value of header `name` (case-insensitive) from a SSDP message.
+fn header<'a>(message: &'a str, name: &str) -> Option<&'a str> {
+    message.lines().skip(1).find_map(|line| {
+        let (header, value) = line.split_once(':')?;
+        header
+            .trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim())
+    })
+}
+
+/// Largest `BOOTID.UPNP.ORG`, which is a 31-bit value.
+const BOOT_ID_MAX: u32 = 0x7FFF_FFFF;
+
+/// Largest `CONFIGID.UPNP.ORG`, which is a 24-bit value.
+const CONFIG_ID_MAX: u32 = 0xFF_FFFF;
+
+/// Initial `BOOTID.UPNP.ORG` of a server created at `now`, i.e. its Unix time in seconds.
+fn boot_id(now: SystemTime) -> u32 {
+    let secs = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "The ID only has to increase across reboots, which holds until 2038"
+    )]
+    let id = secs as u32;
+    id & BOOT_ID_MAX
+}
+
+/// `CONFIGID.UPNP.ORG` of the device description rendered with given options, i.e. a hash of the options it depends on.
+fn config_id(options: &DMROptions) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    (
+        &options.friendly_name,
+        &options.model_name,
+        &options.model_description,
+        &options.model_url,
+        &options.manufacturer,
+        &options.manufacturer_url,
+        &options.serial_number,
+        &options.uuid,
+        &options.advertise_location,
+        &options.service_versions,
+    )
+        .hash(&mut hasher);
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "Only the lower 24 bits are kept anyway"
+    )]
+    let id = hasher.finish() as u32;
+    id & CONFIG_ID_MAX
+}
+
+/// Longest delay of search replies, however large the `MX` header is.
+const MAX_REPLY_DELAY: Duration = Duration::from_secs(5);
+
+/// Delay of replies to a search with given `MX` header, uniformly distributed in `0..=MX` seconds given a `random` number in `0.0..1.0`, where `MX` is clamped to `1..=5`. Searches without a numeric `MX` are answered right away.
+fn reply_delay(mx: Option<&str>, random: f64) -> Duration {
+    mx.and_then(|mx| mx.parse::<u64>().ok())
+        .map_or(Duration::ZERO, |mx| {
+            Duration::from_secs(mx)
+                .clamp(Duration::from_secs(1), MAX_REPLY_DELAY)
+                .mul_f64(random)
+        })
+}
+
+/// Scales `interval` by a factor uniformly distributed in `1 ± jitter%`, given a `random` number in `0.0..1.0`. The average of the result is `interval`.
+fn jittered(interval: Duration, jitter: u8, random: f64) -> Duration {
+    let jitter = f64::from(jitter.min(100)) / 100.0;
+    interval.mul_f64(jitter.mul_add(random.mul_add(2.0, -1.0), 1.0))
+}
+
+/// Binds to `port` with the given `bind` function, retrying on an ephemeral port (`0`) if `port` is in use and `fallback` is enabled. Returns the port passed to the successful `bind` call.
+fn bind_with_fallback(
+    port: u16,
+    fallback: bool,
+    mut bind: impl FnMut(u16) -> Result<()>,
+) -> Result<u16> {
+    match bind(port) {
+        Ok(()) => Ok(port),
+        Err(e) if fallback && port != 0 && e.kind() == ErrorKind::AddrInUse => bind(0).map(|()| 0),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::timeout;
+
+    /// An M-SEARCH request for root devices, without `MX` so that it's answered right away.
+    const SEARCH: &str = "M-SEARCH * HTTP/1.1\r\n\
+        HOST: 239.255.255.250:1900\r\n\
+        MAN: \"ssdp:discover\"\r\n\
+        ST: upnp:rootdevice\r\n\
+        \r\n";
+
+    /// Creates a SSDP server on the loopback interface, bound to an ephemeral port.
+    fn server() -> SSDPServer {
+        let options = DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            ..Default::default()
+        };
+        SSDPServer::new(&options).expect("Failed to create SSDP server")
+    }
+
+    /// The `LOCATION` advertised by a server on a single interface.
+    fn location(server: &SSDPServer) -> &str {
+        &server.interfaces[0].1
+    }
+
+    /// Creates a socket acting as a control point on the loopback interface.
+    async fn control_point() -> (UdpSocket, SocketAddr) {
+        let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0))
+            .await
+            .expect("Failed to bind control point");
+        let address = socket.local_addr().expect("No local address");
+        (socket, address)
+    }
+
+    /// Receives a message on `socket`, failing after a second.
+    async fn receive(socket: &UdpSocket) -> (String, SocketAddr) {
+        let mut buf = [0u8; 4096];
+        let (size, from) = timeout(Duration::from_secs(1), socket.recv_from(&mut buf))
+            .await
+            .expect("Timed out waiting for a message")
+            .expect("Failed to receive a message");
+        (String::from_utf8_lossy(&buf[..size]).into_owned(), from)
+    }
+
+    #[tokio::test]
+    async fn test_search_reply_destination() {
+        let server = server();
+        let (socket, address) = control_point().await;
+        server
+            .answer(&server.socket, address, SEARCH)
+            .await
+            .expect("Failed to answer M-SEARCH");
+
+        let (reply, from) = receive(&socket).await;
+        assert!(reply.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert_eq!(from.port(), server.address.port());
+    }
+
+    #[tokio::test]
+    async fn test_receive_full_buffer() {
+        let server = server();
+        let (socket, address) = control_point().await;
+        // Pad the search with a header up to exactly the buffer size.
+        let padding = server.recv_buffer - SEARCH.len() - "X-PADDING: \r\n".len();
+        let search = SEARCH.replacen(
+            "\r\n\r\n",
+            &format!("\r\nX-PADDING: {}\r\n\r\n", "a".repeat(padding)),
+            1,
+        );
+        assert_eq!(search.len(), server.recv_buffer);
+        socket
+            .send_to(
+                search.as_bytes(),
+                SocketAddrV4::new(Ipv4Addr::LOCALHOST, server.address.port()),
+            )
+            .await
+            .expect("Failed to send M-SEARCH");
+
+        let mut buf = vec![0u8; server.recv_buffer];
+        let (_, from, message) = timeout(Duration::from_secs(1), server.receive(&mut buf))
+            .await
+            .expect("Timed out waiting for the M-SEARCH")
+            .expect("Failed to receive the M-SEARCH");
+        assert_eq!(from, address);
+        assert_eq!(message, search);
+    }
+
+    #[tokio::test]
+    async fn test_announce_to() {
+        let server = server();
+        let (socket, address) = control_point().await;
+        server
+            .announce_to(address)
+            .await
+            .expect("Failed to announce");
+
+        let mut targets = Vec::new();
+        for _ in 0..6 {
+            let (message, from) = receive(&socket).await;
+            assert!(message.starts_with("NOTIFY * HTTP/1.1\r\n"));
+            assert!(message.contains("NTS: ssdp:alive\r\n"));
+            assert!(message.contains(&format!("LOCATION: {}\r\n", location(&server))));
+            assert_eq!(from.port(), server.address.port());
+            let nt = message
+                .lines()
+                .find_map(|line| line.strip_prefix("NT: "))
+                .expect("Missing NT header");
+            targets.push(nt.to_string());
+        }
+        let uuid = format!("uuid:{}", server.uuid);
+        assert_eq!(
+            targets,
+            [
+                "upnp:rootdevice",
+                &uuid,
+                "urn:schemas-upnp-org:device:MediaRenderer:1",
+                "urn:schemas-upnp-org:service:RenderingControl:1",
+                "urn:schemas-upnp-org:service:AVTransport:1",
+                "urn:schemas-upnp-org:service:ConnectionManager:1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_byebye_device_type() {
+        let mut server = server();
+        let (group, group_address) = control_point().await;
+        server.multicast_addr = group_address;
+        server.byebye().expect("Failed to send byebye");
+
+        let mut byebyes = Vec::new();
+        for _ in 0..6 {
+            let (message, _) = receive(&group).await;
+            assert!(message.contains("\r\nNTS: ssdp:byebye\r\n"), "{message}");
+            byebyes.push((
+                header(&message, "NT").expect("Missing NT").to_string(),
+                header(&message, "USN").expect("Missing USN").to_string(),
+            ));
+        }
+        assert!(byebyes.contains(&(
+            DEVICE_TYPE.to_string(),
+            format!("uuid:{}::{DEVICE_TYPE}", server.uuid)
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_cache_max_age() {
+        let mut server = SSDPServer::new(&DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            ssdp_cache_max_age: 3600,
+            ..Default::default()
+        })
+        .expect("Failed to create SSDP server");
+        assert_eq!(server.keep_alive_interval, Duration::from_mins(20));
+        let (group, group_address) = control_point().await;
+        server.multicast_addr = group_address;
+        let (socket, address) = control_point().await;
+
+        server.alive().expect("Failed to send alive");
+        let (notify, _) = receive(&group).await;
+        assert_eq!(header(&notify, "CACHE-CONTROL"), Some("max-age=3600"));
+        server
+            .answer_search(&server.socket, address, SEARCH)
+            .await
+            .expect("Failed to answer M-SEARCH");
+        let (reply, _) = receive(&socket).await;
+        assert_eq!(header(&reply, "CACHE-CONTROL"), Some("max-age=3600"));
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_interval() {
+        let options = DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            ssdp_keep_alive_ms: Some(30_000),
+            ..Default::default()
+        };
+        let server = SSDPServer::new(&options).expect("Failed to create SSDP server");
+        assert_eq!(server.keep_alive_interval, Duration::from_secs(30));
+
+        let spinning = DMROptions {
+            ssdp_keep_alive_ms: Some(0),
+            ..options
+        };
+        assert!(spinning.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_announce_repeats() {
+        let (group, group_address) = control_point().await;
+        let mut server = SSDPServer::new(&DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            ssdp_announce_repeats: 2,
+            ..Default::default()
+        })
+        .expect("Failed to create SSDP server");
+        server.set_multicast_addr(group_address);
+
+        server.announce_startup().await;
+        let targets = server.targets().len();
+        for _ in 0..2 * targets {
+            let (notify, _) = receive(&group).await;
+            assert!(notify.contains("NTS: ssdp:alive\r\n"), "{notify}");
+        }
+        let mut buf = [0u8; 4096];
+        assert!(
+            timeout(Duration::from_millis(200), group.recv_from(&mut buf))
+                .await
+                .is_err()
+        );
+
+        server.stop().await;
+        for _ in 0..2 * targets {
+            let (notify, _) = receive(&group).await;
+            assert!(notify.contains("NTS: ssdp:byebye\r\n"), "{notify}");
+        }
+        assert!(
+            timeout(Duration::from_millis(200), group.recv_from(&mut buf))
+                .await
+                .is_err()
+        );
+
+        // Stopping from `Drop` doesn't wait to repeat
+        server.stop_now();
+        for _ in 0..targets {
+            let (notify, _) = receive(&group).await;
+            assert!(notify.contains("NTS: ssdp:byebye\r\n"), "{notify}");
+        }
+        assert!(
+            timeout(Duration::from_millis(200), group.recv_from(&mut buf))
+                .await
+                .is_err()
+        );
+        assert_eq!(DMROptions::default().ssdp_announce_repeats, 3);
+    }
+
+    #[tokio::test]
+    async fn test_server_string() {
+        let server = server();
+        let notify =
+            server.notify_message(location(&server), "upnp:rootdevice", "ssdp:alive", "uuid:x");
+        let value = header(&notify, "SERVER").expect("Missing SERVER");
+        assert!(value.contains(" UPnP/1.0 dlna-dmr/"), "{value}");
+
+        let server = SSDPServer::new(&DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            server_string: Some("Linux/6.1 UPnP/1.0 MyRenderer/2.0".to_string()),
+            ..Default::default()
+        })
+        .expect("Failed to create SSDP server");
+        let notify =
+            server.notify_message(location(&server), "upnp:rootdevice", "ssdp:alive", "uuid:x");
+        assert_eq!(
+            header(&notify, "SERVER"),
+            Some("Linux/6.1 UPnP/1.0 MyRenderer/2.0")
+        );
+        let response = server.search_response(location(&server), "upnp:rootdevice", "uuid:x");
+        assert_eq!(
+            header(&response, "SERVER"),
+            Some("Linux/6.1 UPnP/1.0 MyRenderer/2.0")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_boot_and_config_id() {
+        let server = server();
+        let boot = server.boot_id().to_string();
+        let config = server.config_id().to_string();
+        for message in [
+            server.notify_message(location(&server), "upnp:rootdevice", "ssdp:alive", "uuid:x"),
+            server.search_response(location(&server), "upnp:rootdevice", "uuid:x"),
+        ] {
+            assert_eq!(header(&message, "BOOTID.UPNP.ORG"), Some(boot.as_str()));
+            assert_eq!(header(&message, "CONFIGID.UPNP.ORG"), Some(config.as_str()));
+        }
+
+        let bumped = server.bump_bootid();
+        assert_eq!(bumped, server.boot_id());
+        assert_eq!(bumped, boot.parse::<u32>().unwrap() + 1);
+        assert_eq!(
+            boot_id(UNIX_EPOCH + Duration::from_secs(u64::from(u32::MAX))),
+            BOOT_ID_MAX
+        );
+
+        let options = DMROptions::default();
+        let renamed = DMROptions {
+            friendly_name: "Kitchen".to_string(),
+            ..options.clone()
+        };
+        assert_eq!(config_id(&options), config_id(&options.clone()));
+        assert_ne!(config_id(&options), config_id(&renamed));
+        assert!(config_id(&renamed) <= CONFIG_ID_MAX);
+        server.reconfigure(&renamed);
+        assert_eq!(server.config_id(), config_id(&renamed));
+    }
+
+    #[tokio::test]
+    async fn test_announce_update() {
+        let (group, group_address) = control_point().await;
+        let mut server = server();
+        server.set_multicast_addr(group_address);
+        let boot = server.boot_id();
+        let next = (boot + 1).to_string();
+
+        assert_eq!(
+            server.announce_update().expect("Failed to announce update"),
+            boot + 1
+        );
+        let targets = server.targets().len();
+        for _ in 0..targets {
+            let (update, _) = receive(&group).await;
+            assert_eq!(header(&update, "NTS"), Some("ssdp:update"));
+            assert_eq!(
+                header(&update, "BOOTID.UPNP.ORG"),
+                Some(boot.to_string().as_str())
+            );
+            assert_eq!(header(&update, "NEXTBOOTID.UPNP.ORG"), Some(next.as_str()));
+            assert_eq!(header(&update, "LOCATION"), Some(location(&server)));
+        }
+        for _ in 0..targets {
+            let (alive, _) = receive(&group).await;
+            assert_eq!(header(&alive, "NTS"), Some("ssdp:alive"));
+            assert_eq!(header(&alive, "BOOTID.UPNP.ORG"), Some(next.as_str()));
+            assert_eq!(header(&alive, "NEXTBOOTID.UPNP.ORG"), None);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_nls_header() {
+        let server = server();
+        let messages = server.notify_messages(location(&server), "ssdp:alive");
+        let nls = header(&messages[0], "01-NLS").expect("NOTIFY lacks 01-NLS");
+        assert_eq!(nls.len(), 32);
+        for message in &messages {
+            assert_eq!(header(message, "01-NLS"), Some(nls));
+            assert_eq!(
+                header(message, "OPT"),
+                Some("\"http://schemas.upnp.org/upnp/1/0/\"; ns=01")
+            );
+        }
+        let response = server.search_response(
+            location(&server),
+            "upnp:rootdevice",
+            "uuid:x::upnp:rootdevice",
+        );
+        assert_eq!(header(&response, "01-NLS"), Some(nls));
+        // A new server, e.g. after a restart, gets a new signature
+        assert_ne!(self::server().nls, nls);
+    }
+
+    #[tokio::test]
+    async fn test_respond_to_subnets() {
+        let mut server = server();
+        server.alive_on_new_search = false;
+        let (socket, address) = control_point().await;
+
+        server.respond_to_subnets = Some(vec!["127.0.0.0/8".parse().unwrap()]);
+        server
+            .answer(&server.socket, address, SEARCH)
+            .await
+            .expect("Failed to answer M-SEARCH");
+        let (reply, _) = receive(&socket).await;
+        assert!(reply.starts_with("HTTP/1.1 200 OK\r\n"));
+
+        server.respond_to_subnets = Some(vec![
+            "172.16.0.0/12".parse().unwrap(),
+            "fd00::/8".parse().unwrap(),
+        ]);
+        server
+            .answer(&server.socket, address, SEARCH)
+            .await
+            .expect("Failed to ignore M-SEARCH");
+        let mut buf = [0u8; 4096];
+        assert!(
+            timeout(Duration::from_millis(200), socket.recv_from(&mut buf))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_hook() {
+        let searches = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&searches);
+        let server = server().with_search_hook(move |from, st| {
+            recorded.lock().unwrap().push((from, st.to_string()));
+            false
+        });
+        let (socket, address) = control_point().await;
+        assert!(
+            !server
+                .answer_search(&server.socket, address, SEARCH)
+                .await
+                .expect("Failed to ignore M-SEARCH")
+        );
+        let mut buf = [0u8; 4096];
+        assert!(
+            timeout(Duration::from_millis(200), socket.recv_from(&mut buf))
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            *searches.lock().unwrap(),
+            [(address, "upnp:rootdevice".to_string())]
+        );
+
+        let server = self::server().with_search_hook(|_, st| st == "upnp:rootdevice");
+        assert!(
+            server
+                .answer_search(&server.socket, address, SEARCH)
+                .await
+                .expect("Failed to answer M-SEARCH")
+        );
+        receive(&socket).await;
+    }
+
+    #[tokio::test]
+    async fn test_search_alive_burst() {
+        let mut server = server();
+        let (group, group_address) = control_point().await;
+        server.multicast_addr = group_address;
+        let (socket, address) = control_point().await;
+
+        server
+            .answer(&server.socket, address, SEARCH)
+            .await
+            .expect("Failed to answer M-SEARCH");
+        let (reply, _) = receive(&socket).await;
+        assert!(reply.starts_with("HTTP/1.1 200 OK\r\n"));
+        for _ in 0..6 {
+            let (notify, _) = receive(&group).await;
+            assert!(notify.starts_with("NOTIFY * HTTP/1.1\r\n"));
+            assert!(notify.contains("NTS: ssdp:alive\r\n"));
+        }
+
+        // The same controller searching again only gets the directed response.
+        server
+            .answer(&server.socket, address, SEARCH)
+            .await
+            .expect("Failed to answer M-SEARCH");
+        receive(&socket).await;
+        let mut buf = [0u8; 4096];
+        assert!(
+            timeout(Duration::from_millis(200), group.recv_from(&mut buf))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_uuid_collision() {
+        let server = server();
+        let notify = server.notify_message(
+            location(&server),
+            "upnp:rootdevice",
+            "ssdp:alive",
+            &format!("uuid:{}::upnp:rootdevice", server.uuid),
+        );
+        // Our own announcement looping back
+        assert!(!server.detect_uuid_collision(server.address, &notify));
+        let foreign = SocketAddr::from((Ipv4Addr::new(192, 168, 1, 2), 1900));
+        assert!(server.detect_uuid_collision(foreign, &notify));
+        // Warned only once per source
+        assert!(!server.detect_uuid_collision(foreign, &notify));
+        // Other devices are fine
+        let other = notify.replace(&server.uuid, "12345678-1234-5678-1234-567812345678");
+        let another = SocketAddr::from((Ipv4Addr::new(192, 168, 1, 3), 1900));
+        assert!(!server.detect_uuid_collision(another, &other));
+        server
+            .answer(&server.socket, another, &notify)
+            .await
+            .expect("Failed to handle NOTIFY");
+        assert!(!server.detect_uuid_collision(another, &notify));
+    }
+
+    #[tokio::test]
+    async fn test_extra_search_target() {
+        let st = "urn:my-company:service:Foo:1";
+        let usn = "uuid:12345678-1234-5678-1234-567812345678::urn:my-company:service:Foo:1";
+        let mut server = SSDPServer::new(&DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            extra_search_targets: vec![(st.to_string(), usn.to_string())],
+            ..Default::default()
+        })
+        .expect("Failed to create SSDP server");
+        let (group, group_address) = control_point().await;
+        server.multicast_addr = group_address;
+        let (socket, address) = control_point().await;
+
+        server.alive().expect("Failed to send alive");
+        for _ in 0..6 {
+            receive(&group).await;
+        }
+        let (notify, _) = receive(&group).await;
+        assert!(notify.contains(&format!("\r\nNT: {st}\r\n")), "{notify}");
+        assert!(notify.contains(&format!("\r\nUSN: {usn}\r\n")), "{notify}");
+
+        let search = SEARCH.replace("ST: upnp:rootdevice", &format!("ST: {st}"));
+        server
+            .answer_search(&server.socket, address, &search)
+            .await
+            .expect("Failed to answer M-SEARCH");
+        let (reply, _) = receive(&socket).await;
+        assert!(reply.contains(&format!("\r\nST: {st}\r\n")), "{reply}");
+        assert!(reply.contains(&format!("\r\nUSN: {usn}\r\n")), "{reply}");
+    }
+
+    #[tokio::test]
+    async fn test_search_target_filtering() {
+        let server = server();
+        let uuid = format!("uuid:{}", server.uuid);
+        for (st, expected) in [
+            ("upnp:rootdevice", Some(format!("{uuid}::upnp:rootdevice"))),
+            (uuid.as_str(), Some(uuid.clone())),
+            (
+                "urn:schemas-upnp-org:device:MediaRenderer:1",
+                Some(format!(
+                    "{uuid}::urn:schemas-upnp-org:device:MediaRenderer:1"
+                )),
+            ),
+            (
+                "urn:schemas-upnp-org:service:AVTransport:1",
+                Some(format!(
+                    "{uuid}::urn:schemas-upnp-org:service:AVTransport:1"
+                )),
+            ),
+            ("urn:schemas-upnp-org:service:ContentDirectory:1", None),
+            ("urn:schemas-upnp-org:device:MediaServer:1", None),
+            ("uuid:00000000-0000-0000-0000-000000000000", None),
+        ] {
+            let search = SEARCH.replace("ST: upnp:rootdevice", &format!("ST: {st}"));
+            let targets = server.matching_targets(header(&search, "ST"));
+            let expected: Vec<_> = expected
+                .into_iter()
+                .map(|usn| (st.to_string(), usn))
+                .collect();
+            assert_eq!(targets, expected, "{st}");
+        }
+
+        let all = SEARCH.replace("ST: upnp:rootdevice", "ST: ssdp:all");
+        assert_eq!(
+            server.matching_targets(header(&all, "ST")),
+            server.targets()
+        );
+        let missing = SEARCH.replace("ST: upnp:rootdevice\r\n", "");
+        assert!(server.matching_targets(header(&missing, "ST")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_reply_echoes_target() {
+        let server = server();
+        let (socket, address) = control_point().await;
+
+        let unrelated = SEARCH.replace(
+            "ST: upnp:rootdevice",
+            "ST: urn:schemas-upnp-org:service:ContentDirectory:1",
+        );
+        let answered = server
+            .answer_search(&server.socket, address, &unrelated)
+            .await
+            .expect("Failed to handle M-SEARCH");
+        assert!(!answered);
+
+        let st = "urn:schemas-upnp-org:service:RenderingControl:1";
+        let search = SEARCH.replace("ST: upnp:rootdevice", &format!("ST: {st}"));
+        let answered = server
+            .answer_search(&server.socket, address, &search)
+            .await
+            .expect("Failed to answer M-SEARCH");
+        assert!(answered);
+        // The unrelated search sent nothing, so this is the first datagram received
+        let (reply, _) = receive(&socket).await;
+        assert!(reply.contains(&format!("\r\nST: {st}\r\n")), "{reply}");
+    }
+
+    #[tokio::test]
+    async fn test_service_versions() {
+        let server = SSDPServer::new(&DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            service_versions: ServiceVersions {
+                av_transport: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .expect("Failed to create SSDP server");
+        // Only the configured version is announced
+        let targets: Vec<_> = server.targets().into_iter().map(|(st, _)| st).collect();
+        assert!(targets.contains(&"urn:schemas-upnp-org:service:AVTransport:2".to_string()));
+        assert!(!targets.contains(&"urn:schemas-upnp-org:service:AVTransport:1".to_string()));
+        assert!(targets.contains(&"urn:schemas-upnp-org:service:RenderingControl:1".to_string()));
+
+        let (socket, address) = control_point().await;
+        let search = SEARCH.replace(
+            "ST: upnp:rootdevice",
+            "ST: urn:schemas-upnp-org:service:AVTransport:2",
+        );
+        assert!(
+            server
+                .answer_search(&server.socket, address, &search)
+                .await
+                .expect("Failed to answer M-SEARCH")
+        );
+        let (reply, _) = receive(&socket).await;
+        assert_eq!(
+            header(&reply, "ST"),
+            Some("urn:schemas-upnp-org:service:AVTransport:2")
+        );
+
+        // Searches for a lower version are answered, echoing it, but not for a higher one
+        let search = SEARCH.replace(
+            "ST: upnp:rootdevice",
+            "ST: urn:schemas-upnp-org:service:AVTransport:1",
+        );
+        assert!(
+            server
+                .answer_search(&server.socket, address, &search)
+                .await
+                .expect("Failed to answer M-SEARCH")
+        );
+        let (reply, _) = receive(&socket).await;
+        assert_eq!(
+            header(&reply, "ST"),
+            Some("urn:schemas-upnp-org:service:AVTransport:1")
+        );
+        assert_eq!(
+            header(&reply, "USN"),
+            Some(
+                format!(
+                    "uuid:{}::urn:schemas-upnp-org:service:AVTransport:2",
+                    server.uuid
+                )
+                .as_str()
+            )
+        );
+        for st in [
+            "urn:schemas-upnp-org:service:AVTransport:3",
+            "urn:schemas-upnp-org:service:AVTransport:0",
+            "urn:schemas-upnp-org:service:AVTransport:",
+            "urn:schemas-upnp-org:service:AVTransport",
+            "urn:schemas-upnp-org:service:RenderingControl:2",
+        ] {
+            assert!(server.matching_targets(Some(st)).is_empty(), "{st}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_all() {
+        let server = server();
+        let (socket, address) = control_point().await;
+        let search = SEARCH.replace("ST: upnp:rootdevice", "ST: ssdp:all");
+        server
+            .answer_search(&server.socket, address, &search)
+            .await
+            .expect("Failed to answer M-SEARCH");
+
+        let mut replies = Vec::new();
+        for _ in 0..6 {
+            let (reply, _) = receive(&socket).await;
+            assert!(reply.contains(&format!("\r\nLocation: {}\r\n", location(&server))));
+            assert!(reply.contains(&format!("\r\nServer: {}\r\n", server.server)));
+            let st = header(&reply, "ST").expect("Missing ST header");
+            let usn = header(&reply, "USN").expect("Missing USN header");
+            replies.push((st.to_string(), usn.to_string()));
+        }
+        assert_eq!(replies, server.targets());
+        let uuid = format!("uuid:{}", server.uuid);
+        assert!(replies.contains(&(
+            "urn:schemas-upnp-org:service:AVTransport:1".to_string(),
+            format!("{uuid}::urn:schemas-upnp-org:service:AVTransport:1")
+        )));
+    }
+
+    #[test]
+    fn test_reply_delay() {
+        for mx in [None, Some(""), Some("soon"), Some("-1"), Some("1.5")] {
+            assert_eq!(reply_delay(mx, 0.99), Duration::ZERO, "{mx:?}");
+        }
+        assert_eq!(reply_delay(Some("3"), 0.5), Duration::from_millis(1500));
+        assert_eq!(reply_delay(Some("0"), 0.5), Duration::from_millis(500));
+        assert_eq!(reply_delay(Some("120"), 0.5), Duration::from_millis(2500));
+        assert_eq!(reply_delay(Some("120"), 0.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_jittered() {
+        let interval = Duration::from_mins(1);
+        assert_eq!(jittered(interval, 10, 0.0), Duration::from_secs(54));
+        assert_eq!(jittered(interval, 10, 0.5), interval);
+        assert_eq!(jittered(interval, 0, 0.9), interval);
+        let mut total = Duration::ZERO;
+        for _ in 0..1000 {
+            let jittered = jittered(interval, 10, fastrand::f64());
+            assert!(jittered >= Duration::from_secs(54), "{jittered:?}");
+            assert!(jittered <= Duration::from_secs(66), "{jittered:?}");
+            total += jittered;
+        }
+        let average = total / 1000;
+        assert!(average > Duration::from_secs(58), "{average:?}");
+        assert!(average < Duration::from_secs(62), "{average:?}");
+    }
+
+    #[tokio::test]
+    async fn test_alive_burst_debounce() {
+        let server = server();
+        assert!(server.is_new_searcher(Ipv4Addr::new(192, 168, 1, 2).into()));
+        assert!(!server.is_new_searcher(Ipv4Addr::new(192, 168, 1, 2).into()));
+        // A new controller within the debounce interval is remembered, but doesn't trigger another burst.
+        assert!(!server.is_new_searcher(Ipv4Addr::new(192, 168, 1, 3).into()));
+        assert!(!server.is_new_searcher(Ipv4Addr::new(192, 168, 1, 3).into()));
+    }
+
+    #[tokio::test]
+    async fn test_advertise_location() {
+        let mut server = SSDPServer::new(&DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            advertise_location: Some("http://proxy.example.com:9000/renderer/".to_string()),
+            ..Default::default()
+        })
+        .expect("Failed to create SSDP server");
+        let (group, group_address) = control_point().await;
+        server.multicast_addr = group_address;
+        let (socket, address) = control_point().await;
+        let location = "http://proxy.example.com:9000/renderer/DeviceSpec";
+
+        server.alive().expect("Failed to send alive");
+        let (notify, _) = receive(&group).await;
+        assert!(notify.contains(&format!("\r\nLOCATION: {location}\r\n")));
+
+        server
+            .answer_search(&server.socket, address, SEARCH)
+            .await
+            .expect("Failed to answer M-SEARCH");
+        let (reply, _) = receive(&socket).await;
+        assert!(reply.contains(&format!("\r\nLocation: {location}\r\n")));
+    }
+
+    #[tokio::test]
+    async fn test_location_consistent() {
+        let mut server = SSDPServer::new(&DMROptions {
+            ip: Ipv4Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            http_port: 8081,
+            description_path: "/description.xml".to_string(),
+            ..Default::default()
+        })
+        .expect("Failed to create SSDP server");
+        let (group, group_address) = control_point().await;
+        server.multicast_addr = group_address;
+        let (socket, address) = control_point().await;
+
+        server.alive().expect("Failed to send alive");
+        let (notify, _) = receive(&group).await;
+        server
+            .answer_search(&server.socket, address, SEARCH)
+            .await
+            .expect("Failed to answer M-SEARCH");
+        let (reply, _) = receive(&socket).await;
+        let location = header(&notify, "LOCATION").expect("Missing LOCATION");
+        assert_eq!(location, "http://127.0.0.1:8081/description.xml");
+        assert_eq!(header(&reply, "LOCATION"), Some(location));
+    }
+
+    #[tokio::test]
+    async fn test_search_reply_to_multicast() {
+        let server = server();
+        let err = server
+            .answer(
+                &server.socket,
+                SSDPServer::SSDP_MULTICAST_ADDR.into(),
+                SEARCH,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_bind_fallback() {
+        let mut attempts = Vec::new();
+        let port = bind_with_fallback(1900, true, |port| {
+            attempts.push(port);
+            if port == 1900 {
+                Err(Error::from(ErrorKind::AddrInUse))
+            } else {
+                Ok(())
+            }
+        })
+        .expect("Failed to bind");
+        assert_eq!(port, 0);
+        assert_eq!(attempts, [1900, 0]);
+    }
+
+    #[test]
+    fn test_bind_no_fallback() {
+        let mut attempts = Vec::new();
+        let err = bind_with_fallback(1900, false, |port| {
+            attempts.push(port);
+            Err(Error::from(ErrorKind::AddrInUse))
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AddrInUse);
+        assert_eq!(attempts, [1900]);
+    }
+
+    #[tokio::test]
+    async fn test_tasks_independent() {
+        // Keep-alive dying doesn't stop answering searches
+        let server = Arc::new(server());
+        let (socket, address) = control_point().await;
+        let tasks = tokio::spawn(run_independently(
+            async { panic!("Injected keep-alive failure") },
+            {
+                let server = Arc::clone(&server);
+                async move {
+                    server.run().await;
+                }
+            },
+        ));
+        sleep(Duration::from_millis(50)).await;
+        socket
+            .send_to(SEARCH.as_bytes(), server.address)
+            .await
+            .expect("Failed to send M-SEARCH");
+        let (reply, _) = receive(&socket).await;
+        assert!(reply.starts_with("HTTP/1.1 200 OK\r\n"), "{reply}");
+        assert!(!tasks.is_finished());
+        tasks.abort();
+
+        // The receive loop dying doesn't stop announcements
+        let mut announcer = self::tests::server();
+        announcer.set_multicast_addr(address);
+        let announcer = Arc::new(announcer);
+        let tasks = tokio::spawn(run_independently(
+            async { panic!("Injected receive failure") },
+            async move {
+                announcer.keep_alive().await;
+            },
+        ));
+        let (notify, _) = receive(&socket).await;
+        assert!(notify.contains("NTS: ssdp:alive\r\n"), "{notify}");
+        assert!(!tasks.is_finished());
+        tasks.abort();
+    }
+
+    #[tokio::test]
+    async fn test_search_latency() {
+        let server = server();
+        assert_eq!(server.search_latency().average(), None);
+        let (socket, address) = control_point().await;
+        for _ in 0..2 {
+            server
+                .answer(&server.socket, address, SEARCH)
+                .await
+                .expect("Failed to answer M-SEARCH");
+            receive(&socket).await;
+        }
+        let latency = server.search_latency();
+        assert_eq!(latency.count, 2);
+        assert!(latency.min <= latency.max);
+        // `SEARCH` has no `MX`, so replies are sent right away
+        assert!(latency.max < Duration::from_millis(100), "{latency:?}");
+        assert!(
+            latency
+                .average()
+                .is_some_and(|average| average <= latency.max)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_search_latency_includes_mx() {
+        let server = server();
+        let (_socket, address) = control_point().await;
+        let search = SEARCH.replace("\r\n\r\n", "\r\nMX: 3\r\n\r\n");
+        // The delay drawn while answering, given the same seed
+        fastrand::seed(7);
+        let delay = reply_delay(Some("3"), fastrand::f64());
+        assert!(delay > Duration::ZERO);
+        fastrand::seed(7);
+        server
+            .answer(&server.socket, address, &search)
+            .await
+            .expect("Failed to answer M-SEARCH");
+        let latency = server.search_latency();
+        assert_eq!(latency.count, 1);
+        assert!(latency.max >= delay, "{latency:?} < {delay:?}");
+    }
+
+    #[tokio::test]
+    async fn test_ipv6_messages() {
+        let server = SSDPServer::new(&DMROptions {
+            ip: Ipv6Addr::LOCALHOST.into(),
+            ssdp_port: 0,
+            http_port: 8080,
+            ..Default::default()
+        })
+        .expect("Failed to create SSDP server");
+        assert!(server.address.is_ipv6());
+
+        let notify =
+            server.notify_message(location(&server), "upnp:rootdevice", "ssdp:alive", "uuid:x");
+        assert_eq!(header(&notify, "HOST"), Some("[ff02::c]:1900"));
+        assert_eq!(
+            header(&notify, "LOCATION"),
+            Some("http://[::1]:8080/DeviceSpec")
+        );
+        let response = server.search_response(location(&server), "upnp:rootdevice", "uuid:x");
+        assert_eq!(
+            header(&response, "LOCATION"),
+            Some("http://[::1]:8080/DeviceSpec")
+        );
+    }
+
+    #[tokio::test]
+    // Binding other loopback addresses than `127.0.0.1` requires aliases elsewhere
+    #[cfg(target_os = "linux")]
+    async fn test_reply_on_receiving_socket() {
+        let first = Ipv4Addr::LOCALHOST;
+        let second = Ipv4Addr::new(127, 0, 0, 2);
+        let server = SSDPServer::new(&DMROptions {
+            ip: Ipv4Addr::UNSPECIFIED.into(),
+            interfaces: Some(vec![first, second]),
+            ssdp_port: 0,
+            http_port: 8080,
+            ..Default::default()
+        })
+        .expect("Failed to create SSDP server");
+        let (control_point, address) = control_point().await;
+        for ip in [first, second] {
+            // Stands in for a socket bound to a single interface
+            let socket = UdpSocket::bind(SocketAddrV4::new(ip, 0))
+                .await
+                .expect("Failed to bind mock socket");
+            assert!(
+                server
+                    .answer_search(&socket, address, SEARCH)
+                    .await
+                    .expect("Failed to answer M-SEARCH")
+            );
+            let (reply, from) = receive(&control_point).await;
+            assert_eq!(from, socket.local_addr().unwrap());
+            assert_eq!(
+                header(&reply, "LOCATION"),
+                Some(format!("http://{ip}:8080/DeviceSpec").as_str())
+            );
         }
     }
+
+    #[test]
+    fn test_select_interfaces() {
+        let wired = Ipv4Addr::new(192, 168, 1, 20);
+        let wifi = Ipv4Addr::new(10, 0, 0, 5);
+        let available = || {
+            vec![
+                Ipv4Addr::LOCALHOST.into(),
+                wired.into(),
+                Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1).into(),
+                wifi.into(),
+            ]
+        };
+        let unspecified = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+        // All non-loopback IPv4 interfaces
+        assert_eq!(
+            select_interfaces(unspecified, None, available),
+            [IpAddr::from(wired), wifi.into()]
+        );
+        // Pinned interfaces, without enumerating them
+        assert_eq!(
+            select_interfaces(unspecified, Some(&[wifi]), || unreachable!()),
+            [IpAddr::from(wifi)]
+        );
+        // Only the interface of a specific address
+        assert_eq!(
+            select_interfaces(wired.into(), None, || unreachable!()),
+            [IpAddr::from(wired)]
+        );
+        // Falls back to the default interface
+        assert_eq!(
+            select_interfaces(unspecified, None, Vec::new),
+            [unspecified]
+        );
+    }
+
+    #[test]
+    fn test_ipv4_mapped_address() {
+        let mapped: SocketAddr = "[::ffff:192.168.1.5]:1900".parse().unwrap();
+        let ipv4: SocketAddr = "192.168.1.5:1900".parse().unwrap();
+        assert_eq!(unmapped(mapped), ipv4);
+        assert_eq!(unmapped(ipv4), ipv4);
+        let ipv6: SocketAddr = "[fe80::1]:1900".parse().unwrap();
+        assert_eq!(unmapped(ipv6), ipv6);
+    }
 }
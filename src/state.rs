@@ -0,0 +1,775 @@
+//! Module for modelling the playback state of a renderer.
+
+use crate::{
+    UpnpError,
+    xml::{
+        AVTransport, RenderingControl,
+        av_transport::{PlayMode, Seek, SeekUnit},
+        didl::DidlLite,
+        rendering_control::{self, Channel},
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    str::FromStr,
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// Possible values of the `TransportState` state variable of `AVTransport`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransportState {
+    /// No media is playing, and playback starts from the current position.
+    #[serde(rename = "STOPPED")]
+    Stopped,
+    /// Playback is paused, and resumes from the current position.
+    #[serde(rename = "PAUSED_PLAYBACK")]
+    PausedPlayback,
+    /// Media is playing.
+    #[serde(rename = "PLAYING")]
+    Playing,
+    /// The renderer is switching between states, e.g. buffering.
+    #[serde(rename = "TRANSITIONING")]
+    Transitioning,
+    /// No media has been set.
+    #[default]
+    #[serde(rename = "NO_MEDIA_PRESENT")]
+    NoMediaPresent,
+}
+
+impl TransportState {
+    /// Actions that make sense in this state, as reported by `GetCurrentTransportActions`.
+    #[must_use]
+    pub const fn available_actions(self) -> &'static [TransportAction] {
+        use TransportAction::{Next, Pause, Play, Previous, Seek, Stop};
+        match self {
+            Self::Stopped => &[Play],
+            Self::PausedPlayback => &[Play, Stop, Seek, Next, Previous],
+            Self::Playing => &[Pause, Stop, Seek, Next, Previous],
+            Self::Transitioning => &[Stop],
+            Self::NoMediaPresent => &[],
+        }
+    }
+}
+
+impl Display for TransportState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stopped => write!(f, "STOPPED"),
+            Self::PausedPlayback => write!(f, "PAUSED_PLAYBACK"),
+            Self::Playing => write!(f, "PLAYING"),
+            Self::Transitioning => write!(f, "TRANSITIONING"),
+            Self::NoMediaPresent => write!(f, "NO_MEDIA_PRESENT"),
+        }
+    }
+}
+
+/// Possible values in the `CurrentTransportActions` state variable of `AVTransport`, a comma-separated list of the actions currently available.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::state::{TransportAction, format_action_list, parse_action_list};
+///
+/// let actions = parse_action_list("Play, Pause,Stop").expect("Failed to parse actions");
+/// assert_eq!(actions, [TransportAction::Play, TransportAction::Pause, TransportAction::Stop]);
+/// assert_eq!(format_action_list(&actions), "Play,Pause,Stop");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransportAction {
+    /// Start or resume playback.
+    Play,
+    /// Stop playback.
+    Stop,
+    /// Pause playback.
+    Pause,
+    /// Seek to a position.
+    Seek,
+    /// Advance to the next track.
+    Next,
+    /// Go back to the previous track.
+    Previous,
+    /// Start recording.
+    Record,
+}
+
+impl FromStr for TransportAction {
+    type Err = TransportActionError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Play" => Ok(Self::Play),
+            "Stop" => Ok(Self::Stop),
+            "Pause" => Ok(Self::Pause),
+            "Seek" => Ok(Self::Seek),
+            "Next" => Ok(Self::Next),
+            "Previous" => Ok(Self::Previous),
+            "Record" => Ok(Self::Record),
+            _ => Err(TransportActionError(s.to_string())),
+        }
+    }
+}
+
+impl Display for TransportAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Play => write!(f, "Play"),
+            Self::Stop => write!(f, "Stop"),
+            Self::Pause => write!(f, "Pause"),
+            Self::Seek => write!(f, "Seek"),
+            Self::Next => write!(f, "Next"),
+            Self::Previous => write!(f, "Previous"),
+            Self::Record => write!(f, "Record"),
+        }
+    }
+}
+
+/// Error returned when parsing an unknown transport action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransportActionError(String);
+
+impl Display for TransportActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown transport action `{}`", self.0)
+    }
+}
+
+impl std::error::Error for TransportActionError {}
+
+/// The value of the `CurrentTransportActions` state variable, i.e. the actions currently available, as a comma-separated list (see [`format_action_list`] and [`parse_action_list`]).
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::state::{TransportAction, TransportActions};
+///
+/// let actions = TransportActions::from(vec![TransportAction::Play, TransportAction::Seek]);
+/// assert_eq!(actions.to_string(), "Play,Seek");
+/// assert_eq!("Play, Seek".parse(), Ok(actions));
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct TransportActions(pub Vec<TransportAction>);
+
+impl From<Vec<TransportAction>> for TransportActions {
+    fn from(actions: Vec<TransportAction>) -> Self {
+        Self(actions)
+    }
+}
+
+impl FromStr for TransportActions {
+    type Err = TransportActionError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_action_list(s).map(Self)
+    }
+}
+
+impl TryFrom<String> for TransportActions {
+    type Error = TransportActionError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<TransportActions> for String {
+    fn from(actions: TransportActions) -> Self {
+        actions.to_string()
+    }
+}
+
+impl Display for TransportActions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format_action_list(&self.0))
+    }
+}
+
+/// Parses a comma-separated list of transport actions, ignoring whitespace around commas. An empty (or blank) list yields no actions.
+///
+/// ## Errors
+///
+/// Returns [`TransportActionError`] on the first unknown action.
+pub fn parse_action_list(s: &str) -> Result<Vec<TransportAction>, TransportActionError> {
+    if s.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    s.split(',').map(|action| action.trim().parse()).collect()
+}
+
+/// Formats transport actions as a comma-separated list.
+#[must_use]
+pub fn format_action_list(actions: &[TransportAction]) -> String {
+    actions
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Formats a duration as `H+:MM:SS`, with at least two digits for hours, e.g. `00:01:30` for the `RelTime` of `GetPositionInfo`. Fractions of a second are truncated.
+#[must_use]
+pub fn format_time(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// A clock tracking the playback position of the current track, advancing while playing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlaybackClock {
+    /// Position when the clock was last started, paused or sought.
+    offset: Duration,
+    /// When the clock was last started, if it's running.
+    started: Option<Instant>,
+}
+
+impl PlaybackClock {
+    /// Creates a stopped clock at position zero.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            offset: Duration::ZERO,
+            started: None,
+        }
+    }
+
+    /// The current position.
+    #[must_use]
+    pub fn position(&self) -> Duration {
+        self.offset
+            + self
+                .started
+                .map_or(Duration::ZERO, |started| started.elapsed())
+    }
+
+    /// Whether the clock is advancing.
+    #[must_use]
+    pub const fn is_running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    /// Starts advancing from the current position. Does nothing if already running.
+    pub fn start(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    /// Stops advancing, keeping the current position.
+    pub fn pause(&mut self) {
+        self.offset = self.position();
+        self.started = None;
+    }
+
+    /// Jumps to given position, advancing from there if running.
+    pub fn seek(&mut self, position: Duration) {
+        self.offset = position;
+        if self.started.is_some() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    /// Stops the clock and rewinds it to zero.
+    pub const fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// Identifier of a virtual instance of the `AVTransport` and `RenderingControl` services (`InstanceID`).
+pub type InstanceId = u32;
+
+/// The `AVTransport` and `RenderingControl` state of a single virtual instance.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstanceState {
+    /// The `TransportState` state variable.
+    pub transport_state: TransportState,
+    /// The URI of the current track.
+    pub current_uri: String,
+    /// The DIDL-Lite metadata of the current track.
+    pub current_uri_metadata: String,
+    /// The URI of the next track, if announced.
+    pub next_uri: String,
+    /// The DIDL-Lite metadata of the next track.
+    pub next_uri_metadata: String,
+    /// The playback position of the current track.
+    pub clock: PlaybackClock,
+    /// The duration of the current track, if declared by its DIDL-Lite metadata. Seeking beyond it is rejected.
+    pub track_duration: Option<Duration>,
+    /// The `CurrentPlayMode` state variable, set via `SetPlayMode`.
+    pub play_mode: PlayMode,
+    /// When the current URI was last set.
+    uri_set_at: Option<Instant>,
+    /// Volumes set per channel, in the range `0..=100` seen by controllers.
+    volumes: HashMap<Channel, u16>,
+    /// Mute states set per channel.
+    mutes: HashMap<Channel, bool>,
+}
+
+/// Outcome of applying an action to the renderer state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Applied {
+    /// The action was applied.
+    Changed,
+    /// The action repeated the previous `SetAVTransportURI` within [`InstanceState::DUPLICATE_WINDOW`], and was ignored. It should still be answered with success, but without reloading the media.
+    Duplicate,
+}
+
+impl InstanceState {
+    /// Volume of channels that haven't been set, in the range `0..=100` seen by controllers.
+    pub const DEFAULT_VOLUME: u16 = 50;
+
+    /// How long an identical `SetAVTransportURI` is considered a resend by a flaky controller, rather than a request to reload the media.
+    pub const DUPLICATE_WINDOW: Duration = Duration::from_secs(3);
+
+    /// Sets the current URI and its metadata, see [`apply`](InstanceState::apply).
+    pub fn set_uri(&mut self, uri: &str, metadata: &str) -> Applied {
+        let now = Instant::now();
+        let recent = self
+            .uri_set_at
+            .is_some_and(|at| now.duration_since(at) < Self::DUPLICATE_WINDOW);
+        if recent && self.current_uri == uri && self.current_uri_metadata == metadata {
+            return Applied::Duplicate;
+        }
+        self.current_uri = uri.to_string();
+        self.current_uri_metadata = metadata.to_string();
+        self.uri_set_at = Some(now);
+        self.clock.reset();
+        self.track_duration = DidlLite::from_metadata(metadata)
+            .and_then(|didl| didl.first_item()?.res.as_ref()?.duration_or_estimate(None));
+        self.transport_state = match self.transport_state {
+            TransportState::Playing | TransportState::Transitioning => {
+                TransportState::Transitioning
+            }
+            _ => TransportState::Stopped,
+        };
+        Applied::Changed
+    }
+
+    /// Updates the state according to an `AVTransport` action addressed to this instance. Queries leave the state unchanged.
+    ///
+    /// Setting a new current URI rewinds the clock to zero, takes the track duration from its metadata, and changes the transport state to `TRANSITIONING` if playing (until you switch to `PLAYING` again once the new track is loaded) or to `STOPPED` otherwise. Setting the same URI and metadata again within [`DUPLICATE_WINDOW`](InstanceState::DUPLICATE_WINDOW) leaves the state unchanged and yields [`Applied::Duplicate`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`UpnpError::transition_not_available`] if the action isn't possible in the current transport state, e.g. `Pause` while stopped, or the errors of [`seek_target`](InstanceState::seek_target) for `Seek`.
+    pub fn apply(&mut self, action: &AVTransport) -> Result<Applied, UpnpError> {
+        use TransportState::{NoMediaPresent, PausedPlayback, Playing, Stopped};
+        match action {
+            AVTransport::SetAVTransportURI(set) => {
+                return Ok(self.set_uri(&set.current_uri, &set.current_uri_meta_data));
+            }
+            AVTransport::SetNextAVTransportURI(set) => {
+                self.next_uri.clone_from(&set.next_uri);
+                self.next_uri_metadata.clone_from(&set.next_uri_meta_data);
+            }
+            AVTransport::Play(_) => {
+                if self.transport_state == NoMediaPresent {
+                    return Err(UpnpError::transition_not_available());
+                }
+                self.transport_state = Playing;
+                self.clock.start();
+            }
+            AVTransport::Pause(_) => {
+                if self.transport_state != Playing {
+                    return Err(UpnpError::transition_not_available());
+                }
+                self.transport_state = PausedPlayback;
+                self.clock.pause();
+            }
+            AVTransport::Stop(_) => {
+                if self.transport_state == NoMediaPresent {
+                    return Err(UpnpError::transition_not_available());
+                }
+                self.transport_state = Stopped;
+                self.clock.reset();
+            }
+            AVTransport::Seek(seek) => {
+                if self.transport_state == NoMediaPresent {
+                    return Err(UpnpError::transition_not_available());
+                }
+                self.clock.seek(self.seek_target(seek)?);
+            }
+            _ => {}
+        }
+        Ok(Applied::Changed)
+    }
+
+    /// The position a `Seek` action targets in the current track.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`UpnpError::seek_mode_not_supported`] for units other than `REL_TIME`, or [`UpnpError::illegal_seek_target`] if the target isn't a valid time or lies beyond [`track_duration`](InstanceState::track_duration).
+    pub fn seek_target(&self, seek: &Seek) -> Result<Duration, UpnpError> {
+        if seek.unit != SeekUnit::RelTime {
+            return Err(UpnpError::seek_mode_not_supported());
+        }
+        seek.target_duration()
+            .filter(|target| {
+                self.track_duration
+                    .is_none_or(|duration| *target <= duration)
+            })
+            .ok_or_else(UpnpError::illegal_seek_target)
+    }
+
+    /// The volume of given channel, in the range `0..=100` seen by controllers, or [`DEFAULT_VOLUME`](InstanceState::DEFAULT_VOLUME) if it hasn't been set.
+    #[must_use]
+    pub fn volume(&self, channel: &Channel) -> u16 {
+        self.volumes
+            .get(channel)
+            .copied()
+            .unwrap_or(Self::DEFAULT_VOLUME)
+    }
+
+    /// Sets the volume of given channel, in the range `0..=100` seen by controllers.
+    pub fn set_volume(&mut self, channel: Channel, volume: u16) {
+        self.volumes.insert(channel, volume);
+    }
+
+    /// Whether given channel is muted, `false` if it hasn't been set.
+    #[must_use]
+    pub fn mute(&self, channel: &Channel) -> bool {
+        self.mutes.get(channel).copied().unwrap_or_default()
+    }
+
+    /// Sets whether given channel is muted.
+    pub fn set_mute(&mut self, channel: Channel, mute: bool) {
+        self.mutes.insert(channel, mute);
+    }
+
+    /// Updates the state according to a `RenderingControl` action addressed to this instance, i.e. `SetVolume` (clamped to `0..=100`) and `SetMute`. Other actions leave the state unchanged.
+    pub fn apply_rendering_control(&mut self, action: &RenderingControl) -> Applied {
+        match action {
+            RenderingControl::SetVolume(set) => {
+                self.set_volume(
+                    set.channel.clone(),
+                    set.clamped_volume(rendering_control::VOLUME_MAX),
+                );
+            }
+            RenderingControl::SetMute(set) => self.set_mute(set.channel.clone(), set.desired_mute),
+            _ => {}
+        }
+        Applied::Changed
+    }
+}
+
+/// The state of a renderer, per virtual instance. Only instance `0` exists by default.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::state::{RendererState, TransportState, format_time};
+///
+/// let xml = r#"<?xml version="1.0"?>
+/// <s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+///     <s:Body>
+///         <u:SetAVTransportURI xmlns:u="urn:schemas-upnp-org:service:AVTransport:1">
+///             <InstanceID>0</InstanceID>
+///             <CurrentURI>http://example.com/sample.mp4</CurrentURI>
+///             <CurrentURIMetaData></CurrentURIMetaData>
+///         </u:SetAVTransportURI>
+///     </s:Body>
+/// </s:Envelope>"#;
+/// let mut state = RendererState::new();
+/// state.apply(&xml.parse().expect("Failed to parse action")).expect("Failed to apply action");
+/// let instance = state.instance(0).expect("Instance 0 exists");
+/// assert_eq!(instance.transport_state, TransportState::Stopped);
+/// assert_eq!(format_time(instance.clock.position()), "00:00:00");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RendererState {
+    instances: BTreeMap<u32, InstanceState>,
+}
+
+impl Default for RendererState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RendererState {
+    /// Creates the state of a renderer with the single instance `0`, without media.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            instances: BTreeMap::from([(0, InstanceState::default())]),
+        }
+    }
+
+    /// Creates the state of a renderer with the instances `0..=max_instance_id`, without media.
+    #[must_use]
+    pub fn with_max_instance_id(max_instance_id: InstanceId) -> Self {
+        Self {
+            instances: (0..=max_instance_id)
+                .map(|instance_id| (instance_id, InstanceState::default()))
+                .collect(),
+        }
+    }
+
+    /// The instances and their state, by ascending ID.
+    pub fn instances(&self) -> impl Iterator<Item = (InstanceId, &InstanceState)> {
+        self.instances
+            .iter()
+            .map(|(instance_id, instance)| (*instance_id, instance))
+    }
+
+    /// The state of given instance, if it exists.
+    #[must_use]
+    pub fn instance(&self, instance_id: InstanceId) -> Option<&InstanceState> {
+        self.instances.get(&instance_id)
+    }
+
+    /// The mutable state of given instance, if it exists, e.g. to switch from `TRANSITIONING` to `PLAYING` once a track is loaded.
+    pub fn instance_mut(&mut self, instance_id: InstanceId) -> Option<&mut InstanceState> {
+        self.instances.get_mut(&instance_id)
+    }
+
+    /// Updates the state of the addressed instance according to an `AVTransport` action, see [`InstanceState::apply`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`UpnpError::invalid_instance_id`] if the instance doesn't exist, or the error of [`InstanceState::apply`].
+    pub fn apply(&mut self, action: &AVTransport) -> Result<Applied, UpnpError> {
+        self.instance_mut(action.instance_id())
+            .ok_or_else(UpnpError::invalid_instance_id)?
+            .apply(action)
+    }
+
+    /// Updates the state of the addressed instance according to a `RenderingControl` action, see [`InstanceState::apply_rendering_control`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`UpnpError::invalid_instance_id`] if the instance doesn't exist, including for queries like `GetVolume`.
+    pub fn apply_rendering_control(
+        &mut self,
+        action: &RenderingControl,
+    ) -> Result<Applied, UpnpError> {
+        Ok(self
+            .instance_mut(action.instance_id())
+            .ok_or_else(UpnpError::invalid_instance_id)?
+            .apply_rendering_control(action))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read_to_string;
+
+    fn action(path: &str) -> AVTransport {
+        read_to_string(format!("tests/AVTransport/{path}"))
+            .expect("Failed to read XML file")
+            .parse()
+            .expect("Failed to parse AVTransport")
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(Duration::ZERO), "00:00:00");
+        assert_eq!(format_time(Duration::from_millis(90_500)), "00:01:30");
+        assert_eq!(
+            format_time(Duration::from_secs(100 * 3600 + 61)),
+            "100:01:01"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_set_uri_resets_position() {
+        let set_uri = action("SetAVTransportURI.xml");
+        let mut state = RendererState::new();
+        state.apply(&set_uri).expect("Failed to set URI A");
+        state.apply(&action("Play.xml")).expect("Failed to play");
+        tokio::time::advance(Duration::from_secs(42)).await;
+        let instance = state.instance(0).expect("Instance 0 exists");
+        assert_eq!(instance.transport_state, TransportState::Playing);
+        assert_eq!(format_time(instance.clock.position()), "00:00:42");
+
+        let AVTransport::SetAVTransportURI(mut set) = set_uri else {
+            unreachable!("Parsed SetAVTransportURI")
+        };
+        set.current_uri = "http://example.com/b.mp4".to_string();
+        state
+            .apply(&AVTransport::SetAVTransportURI(set))
+            .expect("Failed to set URI B");
+        let instance = state.instance(0).expect("Instance 0 exists");
+        assert_eq!(instance.current_uri, "http://example.com/b.mp4");
+        assert_eq!(instance.transport_state, TransportState::Transitioning);
+        assert_eq!(instance.clock.position(), Duration::ZERO);
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(format_time(instance.clock.position()), "00:00:00");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_seek() {
+        let AVTransport::Seek(mut seek) = action("Seek.xml") else {
+            unreachable!("Parsed Seek")
+        };
+        let mut instance = InstanceState::default();
+        assert_eq!(
+            instance.apply(&AVTransport::Seek(seek.clone())),
+            Err(UpnpError::transition_not_available())
+        );
+        let metadata = r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/" xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <item id="1" parentID="0" restricted="1">
+        <dc:title>Sample</dc:title>
+        <res protocolInfo="http-get:*:video/mp4:*" duration="0:03:00.000">http://example.com/sample.mp4</res>
+    </item>
+</DIDL-Lite>"#;
+        instance.set_uri("http://example.com/sample.mp4", metadata);
+        assert_eq!(instance.track_duration, Some(Duration::from_mins(3)));
+
+        seek.target = "0:01:30".to_string();
+        instance
+            .apply(&AVTransport::Seek(seek.clone()))
+            .expect("Failed to seek");
+        assert_eq!(format_time(instance.clock.position()), "00:01:30");
+
+        for target in ["0:03:01", "soon"] {
+            seek.target = target.to_string();
+            assert_eq!(
+                instance.apply(&AVTransport::Seek(seek.clone())),
+                Err(UpnpError::illegal_seek_target()),
+                "{target}"
+            );
+        }
+        seek.unit = SeekUnit::TrackNr;
+        assert_eq!(
+            instance.seek_target(&seek),
+            Err(UpnpError::seek_mode_not_supported())
+        );
+        assert_eq!(format_time(instance.clock.position()), "00:01:30");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_duplicate_set_uri() {
+        let set_uri = action("SetAVTransportURI.xml");
+        let mut state = RendererState::new();
+        assert_eq!(state.apply(&set_uri), Ok(Applied::Changed));
+        state.apply(&action("Play.xml")).expect("Failed to play");
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(state.apply(&set_uri), Ok(Applied::Duplicate));
+        let instance = state.instance(0).expect("Instance 0 exists");
+        assert_eq!(instance.transport_state, TransportState::Playing);
+        assert_eq!(format_time(instance.clock.position()), "00:00:01");
+
+        tokio::time::advance(InstanceState::DUPLICATE_WINDOW).await;
+        assert_eq!(state.apply(&set_uri), Ok(Applied::Changed));
+    }
+
+    #[test]
+    fn test_volume_per_instance() {
+        let set_volume = read_to_string("tests/RenderingControl/SetVolume.xml")
+            .expect("Failed to read XML file")
+            .replace(
+                "<DesiredVolume>50</DesiredVolume>",
+                "<DesiredVolume>30</DesiredVolume>",
+            );
+        let mut state = RendererState::with_max_instance_id(1);
+        let action: RenderingControl = set_volume.parse().expect("Failed to parse SetVolume");
+        let RenderingControl::SetVolume(set) = &action else {
+            unreachable!("Parsed SetVolume")
+        };
+        assert_ne!(set.desired_volume, InstanceState::DEFAULT_VOLUME);
+        state
+            .apply_rendering_control(&action)
+            .expect("Failed to set volume");
+        let volume = |instance_id| {
+            state
+                .instance(instance_id)
+                .expect("Instance exists")
+                .volume(&set.channel)
+        };
+        assert_eq!(volume(0), set.desired_volume);
+        assert_eq!(volume(1), InstanceState::DEFAULT_VOLUME);
+
+        // Out-of-range volumes are tracked as clamped, like controllers see them
+        let loud = set_volume.replace(
+            "<DesiredVolume>30</DesiredVolume>",
+            "<DesiredVolume>150</DesiredVolume>",
+        );
+        state
+            .apply_rendering_control(&loud.parse().expect("Failed to parse SetVolume"))
+            .expect("Failed to set volume");
+        assert_eq!(
+            state
+                .instance(0)
+                .expect("Instance exists")
+                .volume(&set.channel),
+            rendering_control::VOLUME_MAX
+        );
+
+        let unknown =
+            set_volume.replace("<InstanceID>0</InstanceID>", "<InstanceID>2</InstanceID>");
+        assert_eq!(
+            state.apply_rendering_control(&unknown.parse().expect("Failed to parse SetVolume")),
+            Err(UpnpError::invalid_instance_id())
+        );
+    }
+
+    #[test]
+    fn test_invalid_transitions() {
+        let mut state = RendererState::new();
+        assert_eq!(
+            state.apply(&action("Play.xml")),
+            Err(UpnpError::transition_not_available())
+        );
+        let xml = read_to_string("tests/AVTransport/Play.xml")
+            .expect("Failed to read XML file")
+            .replace("<InstanceID>0</InstanceID>", "<InstanceID>1</InstanceID>");
+        assert_eq!(
+            state.apply(&xml.parse().expect("Failed to parse AVTransport")),
+            Err(UpnpError::invalid_instance_id())
+        );
+    }
+
+    #[test]
+    fn test_stopped_actions() {
+        assert_eq!(
+            TransportState::Stopped.available_actions(),
+            [TransportAction::Play]
+        );
+    }
+
+    #[test]
+    fn test_playing_actions() {
+        assert_eq!(
+            format_action_list(TransportState::Playing.available_actions()),
+            "Pause,Stop,Seek,Next,Previous"
+        );
+    }
+
+    #[test]
+    fn test_action_list_round_trip() {
+        let actions = parse_action_list("Play,Pause,Stop").expect("Failed to parse actions");
+        assert_eq!(
+            actions,
+            [
+                TransportAction::Play,
+                TransportAction::Pause,
+                TransportAction::Stop
+            ]
+        );
+        assert_eq!(format_action_list(&actions), "Play,Pause,Stop");
+    }
+
+    #[test]
+    fn test_action_list_whitespace() {
+        assert_eq!(
+            parse_action_list(" Seek ,  Next,Previous , Record "),
+            Ok(vec![
+                TransportAction::Seek,
+                TransportAction::Next,
+                TransportAction::Previous,
+                TransportAction::Record
+            ])
+        );
+        assert_eq!(parse_action_list(""), Ok(Vec::new()));
+        assert_eq!(
+            parse_action_list("Play,Rewind"),
+            Err(TransportActionError("Rewind".to_string()))
+        );
+    }
+}
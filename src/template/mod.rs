@@ -0,0 +1,219 @@
+//! Templates for the XML documents served over HTTP.
+
+use super::{DMROptions, DmrError, xml::device_description::DeviceDescription};
+use quick_xml::escape::escape;
+use std::{
+    borrow::Cow,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+/// The built-in device description template, equivalent to [`DeviceDescription`] and the starting point for custom templates.
+pub const DEVICE_SPEC: &str = include_str!("./DeviceSpec.tmpl.xml");
+
+/// The service description (SCPD) of `AVTransport`, listing [`AVTransport::ACTIONS`](crate::xml::AVTransport::ACTIONS).
+pub const AV_TRANSPORT_SCPD: &str = include_str!("./AVTransport.xml");
+
+/// The service description (SCPD) of `RenderingControl`, listing [`RenderingControl::ACTIONS`](crate::xml::RenderingControl::ACTIONS).
+pub const RENDERING_CONTROL_SCPD: &str = include_str!("./RenderingControl.xml");
+
+/// The service description (SCPD) of `ConnectionManager`, listing [`ConnectionManager::ACTIONS`](crate::xml::ConnectionManager::ACTIONS).
+pub const CONNECTION_MANAGER_SCPD: &str = include_str!("./ConnectionManager.xml");
+
+/// The device description serialized from [`DeviceDescription`] once, since it only depends on the options, so that controllers fetching it right after discovery are answered immediately.
+#[derive(Debug)]
+pub struct DescriptionCache(RwLock<Result<Arc<str>, DmrError>>);
+
+impl DescriptionCache {
+    /// Renders the description with given options.
+    pub fn new(options: &DMROptions) -> Self {
+        Self(RwLock::new(Self::render(options)))
+    }
+
+    fn render(options: &DMROptions) -> Result<Arc<str>, DmrError> {
+        DeviceDescription::from(options)
+            .to_xml()
+            .map(Arc::from)
+            .map_err(|e| DmrError::Config(format!("Failed to serialize device description: {e}")))
+    }
+
+    /// The cached description, or the error rendering it.
+    pub fn get(&self) -> Result<Arc<str>, DmrError> {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Re-renders the description with given options, keeping the cached one on error.
+    pub fn reload(&self, options: &DMROptions) -> Result<(), DmrError> {
+        let description = Self::render(options)?;
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = Ok(description);
+        Ok(())
+    }
+}
+
+/// Names of the actions listed in a service description, in order.
+#[cfg(test)]
+pub fn scpd_actions(scpd: &str) -> Vec<&str> {
+    scpd.split("<action>")
+        .skip(1)
+        .filter_map(|action| {
+            let name = action.trim_start().strip_prefix("<name>")?;
+            name.split_once("</name>").map(|(name, _)| name)
+        })
+        .collect()
+}
+
+/// Number of placeholders that must appear in a device description template, which come first in [`device_spec_fields`].
+const REQUIRED_FIELDS: usize = 8;
+
+/// Placeholders available in a device description template, paired with the (escaped) values they expand to.
+fn device_spec_fields(options: &DMROptions) -> [(&'static str, Cow<'_, str>); 9] {
+    [
+        ("friendlyName", escape(&options.friendly_name)),
+        ("modelName", escape(&options.model_name)),
+        ("modelDescription", escape(&options.model_description)),
+        ("modelURL", escape(&options.model_url)),
+        ("manufacturer", escape(&options.manufacturer)),
+        ("manufacturerURL", escape(&options.manufacturer_url)),
+        ("serialNumber", escape(&options.serial_number)),
+        ("uuid", escape(&options.uuid)),
+        // Optional
+        ("baseURL", escape(options.base_url())),
+    ]
+}
+
+/// Renders a device description template with the given options.
+///
+/// The template follows the syntax of [`format!`]: `{name}` is a placeholder, while `{{` and `}}` are literal braces. Available placeholders are `friendlyName`, `modelName`, `modelDescription`, `modelURL`, `manufacturer`, `manufacturerURL`, `serialNumber` and `uuid`, and each of them must appear at least once.
+///
+/// The optional placeholder `baseURL` expands to [`DMROptions::advertise_location`] without trailing slashes, or to nothing if it isn't set, and is meant to prefix the service URLs.
+///
+/// ## Errors
+///
+/// Returns [`DmrError::Config`] listing the unknown and missing placeholders if the template doesn't match the fields above, or if it contains unbalanced braces.
+pub fn render_device_spec(template: &str, options: &DMROptions) -> Result<String, DmrError> {
+    let fields = device_spec_fields(options);
+    let mut used = [false; 9];
+    let mut unknown: Vec<&str> = Vec::new();
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        output.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            output.push_str(&tail[..1]);
+            rest = &tail[2..];
+        } else if tail.starts_with('{') {
+            let Some(end) = tail.find('}') else {
+                return Err(DmrError::Config(
+                    "Unclosed `{` in device description template".to_string(),
+                ));
+            };
+            let name = &tail[1..end];
+            if let Some(j) = fields.iter().position(|(field, _)| *field == name) {
+                used[j] = true;
+                output.push_str(&fields[j].1);
+            } else if !unknown.contains(&name) {
+                unknown.push(name);
+            }
+            rest = &tail[end + 1..];
+        } else {
+            return Err(DmrError::Config(
+                "Unmatched `}` in device description template".to_string(),
+            ));
+        }
+    }
+    output.push_str(rest);
+
+    let missing: Vec<&str> = fields[..REQUIRED_FIELDS]
+        .iter()
+        .zip(used)
+        .filter_map(|((field, _), used)| (!used).then_some(*field))
+        .collect();
+    if unknown.is_empty() && missing.is_empty() {
+        return Ok(output);
+    }
+
+    let mut problems = Vec::new();
+    if !unknown.is_empty() {
+        problems.push(format!("unknown placeholders: {}", unknown.join(", ")));
+    }
+    if !missing.is_empty() {
+        problems.push(format!("missing placeholders: {}", missing.join(", ")));
+    }
+    Err(DmrError::Config(format!(
+        "Device description template has {}",
+        problems.join("; ")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> DMROptions {
+        DMROptions {
+            friendly_name: "Tom & Jerry".to_string(),
+            uuid: "12345678-1234-5678-1234-567812345678".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_builtin() {
+        let xml = render_device_spec(DEVICE_SPEC, &options()).expect("Failed to render template");
+        assert!(xml.contains("<friendlyName>Tom &amp; Jerry</friendlyName>"));
+        assert!(xml.contains("<UDN>uuid:12345678-1234-5678-1234-567812345678</UDN>"));
+    }
+
+    #[test]
+    fn test_render_base_url() {
+        let xml = render_device_spec(DEVICE_SPEC, &options()).expect("Failed to render template");
+        assert!(xml.contains("<controlURL>/AVTransport</controlURL>"));
+
+        let options = DMROptions {
+            advertise_location: Some("http://proxy.example.com:9000/renderer/".to_string()),
+            ..options()
+        };
+        let xml = render_device_spec(DEVICE_SPEC, &options).expect("Failed to render template");
+        assert!(xml.contains(
+            "<controlURL>http://proxy.example.com:9000/renderer/AVTransport</controlURL>"
+        ));
+        assert!(xml.contains(
+            "<SCPDURL>http://proxy.example.com:9000/renderer/RenderingControl</SCPDURL>"
+        ));
+    }
+
+    #[test]
+    fn test_render_escaped_braces() {
+        let template = DEVICE_SPEC.replace("<modelNumber>1</modelNumber>", "{{1}}");
+        let xml = render_device_spec(&template, &options()).expect("Failed to render template");
+        assert!(xml.contains("{1}"));
+    }
+
+    #[test]
+    fn test_unknown_placeholder() {
+        let template = DEVICE_SPEC.replace("<modelNumber>1</modelNumber>", "{modelNumber}");
+        let err = render_device_spec(&template, &options()).unwrap_err();
+        assert_eq!(
+            err,
+            DmrError::Config(
+                "Device description template has unknown placeholders: modelNumber".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_missing_placeholder() {
+        let template = DEVICE_SPEC.replace("{uuid}", "fixed");
+        let err = render_device_spec(&template, &options()).unwrap_err();
+        assert_eq!(
+            err,
+            DmrError::Config(
+                "Device description template has missing placeholders: uuid".to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,264 @@
+//! Module for classifying and validating the URIs controllers hand to the renderer.
+
+use super::{UpnpError, client};
+use axum::http::Method;
+use log::debug;
+use std::{fmt::Display, time::Duration};
+
+/// The scheme of a URI, as far as renderers usually care.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::uri::UriScheme;
+///
+/// assert_eq!(UriScheme::of("http://example.com/sample.mp4"), UriScheme::Http);
+/// assert_eq!(UriScheme::of("FILE:///home/user/sample.mp4"), UriScheme::File);
+/// assert_eq!(UriScheme::of("smb://nas/sample.mp4"), UriScheme::Other);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UriScheme {
+    /// `http:`
+    Http,
+    /// `https:`
+    Https,
+    /// `file:`, pointing to the local file system of the renderer.
+    File,
+    /// `data:`, embedding the resource itself.
+    Data,
+    /// `rtsp:`
+    Rtsp,
+    /// Any other scheme, or a URI without a scheme.
+    Other,
+}
+
+impl UriScheme {
+    /// Classifies `uri` by its scheme, case-insensitively.
+    #[must_use]
+    pub fn of(uri: &str) -> Self {
+        let Some((scheme, _)) = uri.trim_start().split_once(':') else {
+            return Self::Other;
+        };
+        [
+            ("http", Self::Http),
+            ("https", Self::Https),
+            ("file", Self::File),
+            ("data", Self::Data),
+            ("rtsp", Self::Rtsp),
+        ]
+        .into_iter()
+        .find_map(|(name, kind)| scheme.eq_ignore_ascii_case(name).then_some(kind))
+        .unwrap_or(Self::Other)
+    }
+}
+
+impl Display for UriScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Http => write!(f, "http"),
+            Self::Https => write!(f, "https"),
+            Self::File => write!(f, "file"),
+            Self::Data => write!(f, "data"),
+            Self::Rtsp => write!(f, "rtsp"),
+            Self::Other => write!(f, "other"),
+        }
+    }
+}
+
+/// Checks the scheme of `uri`, rejecting `file:` URIs unless `allow_file` is set, since they give controllers access to the local file system of the renderer.
+///
+/// ## Errors
+///
+/// Returns [`UpnpError::resource_not_found`] for rejected URIs.
+pub fn validate_scheme(uri: &str, allow_file: bool) -> Result<UriScheme, UpnpError> {
+    match UriScheme::of(uri) {
+        UriScheme::File if !allow_file => Err(UpnpError::resource_not_found()),
+        scheme => Ok(scheme),
+    }
+}
+
+/// Checks that an `http:` URI can be retrieved with a `HEAD` request completing within `timeout`.
+///
+/// This way, controllers learn about unreachable media right away rather than once playback fails. Other schemes, including `https:`, aren't checked.
+///
+/// ## Errors
+///
+/// Returns [`UpnpError::resource_not_found`] if the request fails or times out, or [`UpnpError::illegal_mime_type`] if it's answered with a non-success status.
+pub async fn check_reachable(uri: &str, timeout: Duration) -> Result<(), UpnpError> {
+    if UriScheme::of(uri) != UriScheme::Http {
+        return Ok(());
+    }
+    let request = client::request(Method::HEAD, uri.trim(), &[], String::new());
+    match tokio::time::timeout(timeout, request).await {
+        Ok(Ok(response)) if response.status().is_success() => Ok(()),
+        Ok(Ok(response)) => {
+            debug!("HEAD {uri} answered with {}", response.status());
+            Err(UpnpError::illegal_mime_type())
+        }
+        Ok(Err(e)) => {
+            debug!("HEAD {uri} failed: {e}");
+            Err(UpnpError::resource_not_found())
+        }
+        Err(_) => {
+            debug!("HEAD {uri} timed out after {timeout:?}");
+            Err(UpnpError::resource_not_found())
+        }
+    }
+}
+
+/// Parses `uri` into a [`Url`](url::Url), after trimming surrounding whitespace and unescaping XML entities some controllers leave in it by escaping it twice, e.g. `&amp;` between query parameters.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::uri::parse_url;
+///
+/// let url = parse_url(" http://example.com/sample.mp4?a=1&amp;b=2 ").expect("Failed to parse URL");
+/// assert_eq!(url.query(), Some("a=1&b=2"));
+/// ```
+///
+/// ## Errors
+///
+/// Returns [`UrlError`] if `uri` isn't a valid absolute URL.
+#[cfg(feature = "url")]
+pub fn parse_url(uri: &str) -> Result<url::Url, UrlError> {
+    let unescaped = uri
+        .trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    url::Url::parse(&unescaped).map_err(|error| UrlError {
+        uri: uri.to_string(),
+        error,
+    })
+}
+
+/// Error returned by [`parse_url`] when a URI isn't a valid URL.
+#[cfg(feature = "url")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrlError {
+    /// The URI as sent by the controller.
+    pub uri: String,
+    /// Why it isn't a valid URL.
+    pub error: url::ParseError,
+}
+
+#[cfg(feature = "url")]
+impl Display for UrlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid URL `{}`: {}", self.uri, self.error)
+    }
+}
+
+#[cfg(feature = "url")]
+impl std::error::Error for UrlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// Serves a single connection, answering with `response` after `delay`, and returns the URL of the server.
+    async fn stub(response: &'static str, delay: Duration) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            tokio::time::sleep(delay).await;
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+        format!("http://{address}/sample.mp4")
+    }
+
+    #[test]
+    fn test_classify() {
+        for (uri, scheme) in [
+            ("http://example.com/sample.mp4", UriScheme::Http),
+            ("HTTPS://example.com/sample.mp4", UriScheme::Https),
+            ("file:///home/user/sample.mp4", UriScheme::File),
+            ("data:audio/mpeg;base64,SUQz", UriScheme::Data),
+            ("rtsp://example.com/stream", UriScheme::Rtsp),
+            ("smb://nas/sample.mp4", UriScheme::Other),
+            ("sample.mp4", UriScheme::Other),
+            ("", UriScheme::Other),
+        ] {
+            assert_eq!(UriScheme::of(uri), scheme, "{uri}");
+        }
+    }
+
+    #[test]
+    fn test_validate_file() {
+        let uri = "file:///etc/passwd";
+        assert_eq!(
+            validate_scheme(uri, false),
+            Err(UpnpError::resource_not_found())
+        );
+        assert_eq!(validate_scheme(uri, true), Ok(UriScheme::File));
+        assert_eq!(
+            validate_scheme("data:text/plain,hi", false),
+            Ok(UriScheme::Data)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_reachable() {
+        const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
+        const NOT_FOUND: &str = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n";
+        let timeout = Duration::from_millis(200);
+
+        let uri = stub(OK, Duration::ZERO).await;
+        assert_eq!(check_reachable(&uri, timeout).await, Ok(()));
+        let uri = stub(NOT_FOUND, Duration::ZERO).await;
+        assert_eq!(
+            check_reachable(&uri, timeout).await,
+            Err(UpnpError::illegal_mime_type())
+        );
+        let uri = stub(OK, Duration::from_secs(5)).await;
+        assert_eq!(
+            check_reachable(&uri, timeout).await,
+            Err(UpnpError::resource_not_found())
+        );
+        assert_eq!(
+            check_reachable("https://example.invalid/sample.mp4", timeout).await,
+            Ok(())
+        );
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn test_parse_url() {
+        for (uri, scheme, host, query) in [
+            ("http://h/x?a=1&amp;b=2", "http", "h", Some("a=1&b=2")),
+            ("http://h/x?a=1&b=2", "http", "h", Some("a=1&b=2")),
+            ("\n  https://h/x.mp4  ", "https", "h", None),
+            ("rtsp://example.com:554/stream", "rtsp", "example.com", None),
+        ] {
+            let url = parse_url(uri).expect(uri);
+            assert_eq!(url.scheme(), scheme, "{uri}");
+            assert_eq!(url.host_str(), Some(host), "{uri}");
+            assert_eq!(url.query(), query, "{uri}");
+        }
+        assert_eq!(
+            parse_url("rtsp://example.com:554/stream")
+                .expect("Failed to parse URL")
+                .port(),
+            Some(554)
+        );
+        for uri in ["", "sample.mp4", "http://", "http://[::1/x"] {
+            let error = parse_url(uri).expect_err(uri);
+            assert_eq!(error.uri, uri);
+            assert!(error.to_string().starts_with("Invalid URL"), "{error}");
+        }
+    }
+}
@@ -2,9 +2,20 @@
 //!
 //! See [`AVTransportEnvelope`] and [`AVTransport`] for more details. Documentation on `AVTransport` v1 can be found [here](https://www.upnp.org/specs/av/UPnP-av-AVTransport-v1-Service.pdf).
 
-use quick_xml::{DeError, de};
+use super::{Body, Envelope, didl::DidlLite, soap};
+use crate::{
+    ParseError,
+    media::parse_duration,
+    response::ActionResponse,
+    state::{TransportActions, TransportState},
+    uri::UriScheme,
+};
+use quick_xml::{SeError, se};
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, str::FromStr};
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+/// The service type of `AVTransport`.
+pub const SERVICE_TYPE: &str = "urn:schemas-upnp-org:service:AVTransport:1";
 
 /// The envelope structure for `AVTransport` XML messages.
 ///
@@ -33,32 +44,10 @@ use std::{fmt::Display, str::FromStr};
 /// assert_eq!(play_action.instance_id, 0);
 /// assert_eq!(play_action.speed, PlaySpeed::One);
 /// ```
-#[allow(missing_docs, reason = "Wrapper struct")]
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-pub struct AVTransportEnvelope {
-    #[serde(rename = "@encodingStyle")]
-    pub s_encoding_style: String,
-    #[serde(rename = "@xmlns:s")]
-    pub xmlns_s: String,
-    #[serde(rename = "Body")]
-    pub s_body: SBody,
-}
-
-impl AVTransportEnvelope {
-    /// Take ownership of the [`AVTransport`] value contained in the envelope, consuming the envelope.
-    #[must_use]
-    pub fn into_inner(self) -> AVTransport {
-        self.s_body.content
-    }
-}
+pub type AVTransportEnvelope = Envelope<AVTransport>;
 
-/// Container structure.
-#[allow(missing_docs, reason = "Wrapper struct")]
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-pub struct SBody {
-    #[serde(rename = "$value")]
-    content: AVTransport,
-}
+/// Container structure of [`AVTransportEnvelope`].
+pub type SBody = Body<AVTransport>;
 
 /// Different types of action that can be invoked in the `AVTransport` service. Can be directly parsed from an XML envelope string, IGNORING the outer envelope structure.
 ///
@@ -106,23 +95,118 @@ pub enum AVTransport {
     Play(Play),
     /// While the device is in a playing state, e.g. `TransportState` is “PLAYING”, this action halts the progression of the resource that is associated with the specified instance Id.
     Pause(Simple),
-    // TODO: Record?
+    /// Starts recording on the specified transport instance, at the current position on the media, according to the currently specified recording quality, and returns immediately.
+    Record(Simple),
     /// Start seeking through the resource controlled by the specified instance - as fast as possible - to the specified target position.
     Seek(Seek),
     /// Convenient action to advance to the next track.
     Next(Simple),
     /// Convenient action to advance to the previous track.
     Previous(Simple),
-    // TODO: SetPlayMode, SetRecordQualityMode?
+    /// Sets the play mode of the specified instance, e.g. shuffle or repeat.
+    SetPlayMode(SetPlayMode),
+    /// Sets the record quality mode of the specified instance.
+    SetRecordQualityMode(SetRecordQualityMode),
     /// Returns the `CurrentTransportActions` state variable for the specified instance.
     GetCurrentTransportActions(Simple),
 }
 
+actions!(AVTransport {
+    SetAVTransportURI,
+    SetNextAVTransportURI,
+    GetMediaInfo,
+    GetTransportInfo,
+    GetPositionInfo,
+    GetDeviceCapabilities,
+    GetTransportSettings,
+    Stop,
+    Play,
+    Pause,
+    Record,
+    Seek,
+    Next,
+    Previous,
+    SetPlayMode,
+    SetRecordQualityMode,
+    GetCurrentTransportActions,
+});
+
+impl AVTransport {
+    /// The virtual instance the action applies to.
+    #[must_use]
+    pub const fn instance_id(&self) -> u32 {
+        match self {
+            Self::SetAVTransportURI(SetAVTransportURI { instance_id, .. })
+            | Self::SetNextAVTransportURI(SetNextAVTransportURI { instance_id, .. })
+            | Self::Play(Play { instance_id, .. })
+            | Self::Seek(Seek { instance_id, .. })
+            | Self::SetPlayMode(SetPlayMode { instance_id, .. })
+            | Self::SetRecordQualityMode(SetRecordQualityMode { instance_id, .. })
+            | Self::GetMediaInfo(Simple { instance_id, .. })
+            | Self::GetTransportInfo(Simple { instance_id, .. })
+            | Self::GetPositionInfo(Simple { instance_id, .. })
+            | Self::GetDeviceCapabilities(Simple { instance_id, .. })
+            | Self::GetTransportSettings(Simple { instance_id, .. })
+            | Self::Stop(Simple { instance_id, .. })
+            | Self::Pause(Simple { instance_id, .. })
+            | Self::Record(Simple { instance_id, .. })
+            | Self::Next(Simple { instance_id, .. })
+            | Self::Previous(Simple { instance_id, .. })
+            | Self::GetCurrentTransportActions(Simple { instance_id, .. }) => *instance_id,
+        }
+    }
+
+    /// Whether the action changes state, as opposed to only querying it.
+    #[must_use]
+    pub const fn is_command(&self) -> bool {
+        !matches!(
+            self,
+            Self::GetMediaInfo(_)
+                | Self::GetTransportInfo(_)
+                | Self::GetPositionInfo(_)
+                | Self::GetDeviceCapabilities(_)
+                | Self::GetTransportSettings(_)
+                | Self::GetCurrentTransportActions(_)
+        )
+    }
+
+    /// Serializes the action into a SOAP envelope, e.g. to forward it to another renderer.
+    ///
+    /// The action element is prefixed with `u`, which is bound to the `xmlns_u` field of the arguments, so it should be [`SERVICE_TYPE`] (as it is when parsed from a valid envelope). The arguments are in the order of the SCPD.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the error of [`quick_xml::se`] if the arguments can't be serialized.
+    pub fn to_envelope_string(&self) -> Result<String, SeError> {
+        let root = format!("u:{}", self.name());
+        let body = match self {
+            Self::SetAVTransportURI(set) => se::to_string_with_root(&root, set),
+            Self::SetNextAVTransportURI(set) => se::to_string_with_root(&root, set),
+            Self::Play(play) => se::to_string_with_root(&root, play),
+            Self::Seek(seek) => se::to_string_with_root(&root, seek),
+            Self::SetPlayMode(set) => se::to_string_with_root(&root, set),
+            Self::SetRecordQualityMode(set) => se::to_string_with_root(&root, set),
+            Self::GetMediaInfo(simple)
+            | Self::GetTransportInfo(simple)
+            | Self::GetPositionInfo(simple)
+            | Self::GetDeviceCapabilities(simple)
+            | Self::GetTransportSettings(simple)
+            | Self::Stop(simple)
+            | Self::Pause(simple)
+            | Self::Record(simple)
+            | Self::Next(simple)
+            | Self::Previous(simple)
+            | Self::GetCurrentTransportActions(simple) => se::to_string_with_root(&root, simple),
+        }?;
+        Ok(soap::envelope(soap::DEFAULT_PREFIX, &body))
+    }
+}
+
 impl FromStr for AVTransport {
-    type Err = DeError;
+    type Err = ParseError;
     /// Deserialize from an envelope, IGNORING the outer envelope structure.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let envelope: AVTransportEnvelope = de::from_str(s)?;
+        let envelope: AVTransportEnvelope = s.parse()?;
         Ok(envelope.into_inner())
     }
 }
@@ -144,6 +228,30 @@ pub struct SetAVTransportURI {
     pub current_uri_meta_data: String,
 }
 
+impl SetAVTransportURI {
+    /// The scheme of [`current_uri`](SetAVTransportURI::current_uri).
+    #[must_use]
+    pub fn scheme(&self) -> UriScheme {
+        UriScheme::of(&self.current_uri)
+    }
+
+    /// [`current_uri`](SetAVTransportURI::current_uri) parsed as a URL, see [`parse_url`](crate::uri::parse_url).
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`UrlError`](crate::uri::UrlError) if the URI isn't a valid URL.
+    #[cfg(feature = "url")]
+    pub fn url(&self) -> Result<url::Url, crate::uri::UrlError> {
+        crate::uri::parse_url(&self.current_uri)
+    }
+
+    /// The DIDL-Lite metadata of [`current_uri`](SetAVTransportURI::current_uri), see [`DidlLite::from_metadata`].
+    #[must_use]
+    pub fn metadata(&self) -> Option<DidlLite> {
+        DidlLite::from_metadata(&self.current_uri_meta_data)
+    }
+}
+
 /// Arguments for [`AVTransport::SetNextAVTransportURI`].
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct SetNextAVTransportURI {
@@ -161,6 +269,30 @@ pub struct SetNextAVTransportURI {
     pub next_uri_meta_data: String,
 }
 
+impl SetNextAVTransportURI {
+    /// The scheme of [`next_uri`](SetNextAVTransportURI::next_uri).
+    #[must_use]
+    pub fn scheme(&self) -> UriScheme {
+        UriScheme::of(&self.next_uri)
+    }
+
+    /// [`next_uri`](SetNextAVTransportURI::next_uri) parsed as a URL, see [`parse_url`](crate::uri::parse_url).
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`UrlError`](crate::uri::UrlError) if the URI isn't a valid URL.
+    #[cfg(feature = "url")]
+    pub fn url(&self) -> Result<url::Url, crate::uri::UrlError> {
+        crate::uri::parse_url(&self.next_uri)
+    }
+
+    /// The DIDL-Lite metadata of [`next_uri`](SetNextAVTransportURI::next_uri), see [`DidlLite::from_metadata`].
+    #[must_use]
+    pub fn metadata(&self) -> Option<DidlLite> {
+        DidlLite::from_metadata(&self.next_uri_meta_data)
+    }
+}
+
 /// A single `instance_id` argument. For the following actions in [`AVTransport`]:
 ///
 /// - [`AVTransport::GetMediaInfo`]
@@ -183,34 +315,206 @@ pub struct Simple {
     pub instance_id: u32,
 }
 
+/// Arguments for [`AVTransport::SetPlayMode`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SetPlayMode {
+    /// The XML namespace for the `AVTransport` service.
+    #[serde(rename = "@xmlns:u")]
+    pub xmlns_u: String,
+    /// The virtual instance of the `AVTransport` service to which the action applies.
+    #[serde(rename = "InstanceID")]
+    pub instance_id: u32,
+    /// The desired play mode.
+    #[serde(rename = "NewPlayMode")]
+    pub new_play_mode: PlayMode,
+}
+
+/// Possible values for the [`new_play_mode`](SetPlayMode::new_play_mode) field of [`SetPlayMode`], i.e. the `CurrentPlayMode` state variable.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PlayMode {
+    /// Tracks are played in order, once.
+    #[default]
+    #[serde(rename = "NORMAL")]
+    Normal,
+    /// Tracks are played in random order, repeatedly.
+    #[serde(rename = "SHUFFLE")]
+    Shuffle,
+    /// Tracks are played in random order, once. Sent by some controllers for shuffle without repeat.
+    #[serde(rename = "SHUFFLE_NOREPEAT")]
+    ShuffleNoRepeat,
+    /// The current track is repeated.
+    #[serde(rename = "REPEAT_ONE")]
+    RepeatOne,
+    /// Tracks are played in order, repeatedly.
+    #[serde(rename = "REPEAT_ALL")]
+    RepeatAll,
+    /// Tracks are played in random order, possibly repeating some before others are played.
+    #[serde(rename = "RANDOM")]
+    Random,
+    /// Only the current track is played.
+    #[serde(rename = "DIRECT_1")]
+    Direct1,
+    /// The first few seconds of each track are played.
+    #[serde(rename = "INTRO")]
+    Intro,
+}
+
+impl PlayMode {
+    /// All play modes.
+    pub const ALL: [Self; 8] = [
+        Self::Normal,
+        Self::Shuffle,
+        Self::ShuffleNoRepeat,
+        Self::RepeatOne,
+        Self::RepeatAll,
+        Self::Random,
+        Self::Direct1,
+        Self::Intro,
+    ];
+}
+
+impl Display for PlayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Normal => write!(f, "NORMAL"),
+            Self::Shuffle => write!(f, "SHUFFLE"),
+            Self::ShuffleNoRepeat => write!(f, "SHUFFLE_NOREPEAT"),
+            Self::RepeatOne => write!(f, "REPEAT_ONE"),
+            Self::RepeatAll => write!(f, "REPEAT_ALL"),
+            Self::Random => write!(f, "RANDOM"),
+            Self::Direct1 => write!(f, "DIRECT_1"),
+            Self::Intro => write!(f, "INTRO"),
+        }
+    }
+}
+
+/// Arguments for [`AVTransport::SetRecordQualityMode`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SetRecordQualityMode {
+    /// The XML namespace for the `AVTransport` service.
+    #[serde(rename = "@xmlns:u")]
+    pub xmlns_u: String,
+    /// The virtual instance of the `AVTransport` service to which the action applies.
+    #[serde(rename = "InstanceID")]
+    pub instance_id: u32,
+    /// The desired record quality mode, e.g. `1:HIGH`.
+    #[serde(rename = "NewRecordQualityMode")]
+    pub new_record_quality_mode: String,
+}
+
 /// Arguments for [`AVTransport::Play`].
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Play {
     /// The XML namespace for the `AVTransport` service.
     #[serde(rename = "@xmlns:u")]
     pub xmlns_u: String,
-    /// The speed at which to play the resource.
-    #[serde(rename = "Speed")]
-    pub speed: PlaySpeed,
     /// The virtual instance of the `AVTransport` service to which the action applies.
     #[serde(rename = "InstanceID")]
     pub instance_id: u32,
+    /// The speed at which to play the resource.
+    #[serde(rename = "Speed")]
+    pub speed: PlaySpeed,
 }
 
-/// Possible values for the [`speed`](`Play::speed`) field of [`Play`].
+/// Possible values for the [`speed`](`Play::speed`) field of [`Play`], i.e. the `TransportPlaySpeed` state variable.
 ///
-/// Currently, only `1` is supported, which means normal speed playback.
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+/// `1` means normal speed playback. The common DLNA speeds have their own variants, e.g. `2` for fast-forward, `-2` for rewind or `1/2` for slow motion, while unexpected ones are kept verbatim. Speeds other than `1` are only accepted if listed in [`DMROptions::supported_play_speeds`](crate::DMROptions::supported_play_speeds).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
 pub enum PlaySpeed {
-    /// Normal speed playback.
-    #[serde(rename = "1")]
+    /// Normal speed playback, `1`.
     One,
+    /// Slow motion at a quarter of the normal speed, `1/4`.
+    Quarter,
+    /// Slow motion at half the normal speed, `1/2`.
+    Half,
+    /// Fast-forward at twice the normal speed, `2`.
+    Two,
+    /// Fast-forward at four times the normal speed, `4`.
+    Four,
+    /// Fast-forward at eight times the normal speed, `8`.
+    Eight,
+    /// Fast-forward at sixteen times the normal speed, `16`.
+    Sixteen,
+    /// Slow reverse at half the normal speed, `-1/2`.
+    MinusHalf,
+    /// Reverse at normal speed, `-1`.
+    MinusOne,
+    /// Rewind at twice the normal speed, `-2`.
+    MinusTwo,
+    /// Rewind at four times the normal speed, `-4`.
+    MinusFour,
+    /// Rewind at eight times the normal speed, `-8`.
+    MinusEight,
+    /// Rewind at sixteen times the normal speed, `-16`.
+    MinusSixteen,
+    /// Any other speed, e.g. `32`.
+    Other(String),
+}
+
+impl PlaySpeed {
+    /// All speeds with their own variant, i.e. other than [`Other`](PlaySpeed::Other).
+    pub const KNOWN: [Self; 13] = [
+        Self::One,
+        Self::Quarter,
+        Self::Half,
+        Self::Two,
+        Self::Four,
+        Self::Eight,
+        Self::Sixteen,
+        Self::MinusHalf,
+        Self::MinusOne,
+        Self::MinusTwo,
+        Self::MinusFour,
+        Self::MinusEight,
+        Self::MinusSixteen,
+    ];
+}
+
+impl From<String> for PlaySpeed {
+    fn from(speed: String) -> Self {
+        match speed.trim() {
+            "1" => Self::One,
+            "1/4" => Self::Quarter,
+            "1/2" => Self::Half,
+            "2" => Self::Two,
+            "4" => Self::Four,
+            "8" => Self::Eight,
+            "16" => Self::Sixteen,
+            "-1/2" => Self::MinusHalf,
+            "-1" => Self::MinusOne,
+            "-2" => Self::MinusTwo,
+            "-4" => Self::MinusFour,
+            "-8" => Self::MinusEight,
+            "-16" => Self::MinusSixteen,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<PlaySpeed> for String {
+    fn from(speed: PlaySpeed) -> Self {
+        speed.to_string()
+    }
 }
 
 impl Display for PlaySpeed {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::One => write!(f, "1"),
+            Self::Quarter => write!(f, "1/4"),
+            Self::Half => write!(f, "1/2"),
+            Self::Two => write!(f, "2"),
+            Self::Four => write!(f, "4"),
+            Self::Eight => write!(f, "8"),
+            Self::Sixteen => write!(f, "16"),
+            Self::MinusHalf => write!(f, "-1/2"),
+            Self::MinusOne => write!(f, "-1"),
+            Self::MinusTwo => write!(f, "-2"),
+            Self::MinusFour => write!(f, "-4"),
+            Self::MinusEight => write!(f, "-8"),
+            Self::MinusSixteen => write!(f, "-16"),
+            Self::Other(speed) => write!(f, "{speed}"),
         }
     }
 }
@@ -221,15 +525,35 @@ pub struct Seek {
     /// The XML namespace for the `AVTransport` service.
     #[serde(rename = "@xmlns:u")]
     pub xmlns_u: String,
-    /// The target position of the seek action, in terms of units defined by the [`unit`](`Seek::unit`) field.
-    #[serde(rename = "Target")]
-    pub target: String,
-    /// The unit in which the amount of seeking to be performed is specified.
-    #[serde(rename = "Unit")]
-    pub unit: SeekUnit,
     /// The virtual instance of the `AVTransport` service to which the action applies.
     #[serde(rename = "InstanceID")]
     pub instance_id: u32,
+    /// The unit in which the amount of seeking to be performed is specified.
+    #[serde(rename = "Unit")]
+    pub unit: SeekUnit,
+    /// The target position of the seek action, in terms of units defined by the [`unit`](`Seek::unit`) field.
+    #[serde(rename = "Target")]
+    pub target: String,
+}
+
+impl Seek {
+    /// The [`target`](Seek::target) as a position, if the unit is `REL_TIME` or `ABS_TIME` and the target is a valid time like `0:03:12` or `1:00:00.500`, see [`parse_duration`].
+    #[must_use]
+    pub fn target_duration(&self) -> Option<Duration> {
+        match self.unit {
+            SeekUnit::RelTime | SeekUnit::AbsTime => parse_duration(&self.target),
+            SeekUnit::AbsCount | SeekUnit::TrackNr => None,
+        }
+    }
+
+    /// The [`target`](Seek::target) as a track number, if the unit is `TRACK_NR`.
+    #[must_use]
+    pub fn target_track(&self) -> Option<u32> {
+        match self.unit {
+            SeekUnit::TrackNr => self.target.trim().parse().ok(),
+            SeekUnit::AbsCount | SeekUnit::RelTime | SeekUnit::AbsTime => None,
+        }
+    }
 }
 
 /// Possible values for the [`unit`](`Seek::unit`) field of [`Seek`].
@@ -244,22 +568,181 @@ pub enum SeekUnit {
     /// Seeking by relative time.
     #[serde(rename = "REL_TIME")]
     RelTime,
+    /// Seeking by absolute time, i.e. from the start of the whole media.
+    #[serde(rename = "ABS_TIME")]
+    AbsTime,
     // TODO: The rest?
 }
 
+impl SeekUnit {
+    /// All seek units.
+    pub const ALL: [Self; 4] = [Self::AbsCount, Self::TrackNr, Self::RelTime, Self::AbsTime];
+}
+
 impl Display for SeekUnit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::AbsCount => write!(f, "ABS_COUNT"),
-            Self::RelTime => write!(f, "TRACK_NR"),
-            Self::TrackNr => write!(f, "REL_TIME"),
+            Self::TrackNr => write!(f, "TRACK_NR"),
+            Self::RelTime => write!(f, "REL_TIME"),
+            Self::AbsTime => write!(f, "ABS_TIME"),
         }
     }
 }
 
+/// Output arguments of [`AVTransport::GetMediaInfo`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GetMediaInfoResponse {
+    /// Number of tracks of the current media, `0` if there's none.
+    #[serde(rename = "NrTracks")]
+    pub nr_tracks: u32,
+    /// Duration of the current media, e.g. `0:03:25`.
+    #[serde(rename = "MediaDuration")]
+    pub media_duration: String,
+    /// The URI set via `SetAVTransportURI`.
+    #[serde(rename = "CurrentURI")]
+    pub current_uri: String,
+    /// The DIDL-Lite metadata of [`current_uri`](GetMediaInfoResponse::current_uri).
+    #[serde(rename = "CurrentURIMetaData")]
+    pub current_uri_meta_data: String,
+    /// The URI set via `SetNextAVTransportURI`.
+    #[serde(rename = "NextURI")]
+    pub next_uri: String,
+    /// The DIDL-Lite metadata of [`next_uri`](GetMediaInfoResponse::next_uri).
+    #[serde(rename = "NextURIMetaData")]
+    pub next_uri_meta_data: String,
+    /// The storage medium played from, e.g. `NETWORK`.
+    #[serde(rename = "PlayMedium")]
+    pub play_medium: String,
+    /// The storage medium recorded to, `NOT_IMPLEMENTED` for renderers.
+    #[serde(rename = "RecordMedium")]
+    pub record_medium: String,
+    /// Whether the medium is writable, `NOT_IMPLEMENTED` for renderers.
+    #[serde(rename = "WriteStatus")]
+    pub write_status: String,
+}
+
+impl ActionResponse for GetMediaInfoResponse {
+    const SERVICE_TYPE: &'static str = SERVICE_TYPE;
+    const ACTION: &'static str = "GetMediaInfo";
+
+    fn arguments(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("NrTracks", self.nr_tracks.to_string()),
+            ("MediaDuration", self.media_duration.clone()),
+            ("CurrentURI", self.current_uri.clone()),
+            ("CurrentURIMetaData", self.current_uri_meta_data.clone()),
+            ("NextURI", self.next_uri.clone()),
+            ("NextURIMetaData", self.next_uri_meta_data.clone()),
+            ("PlayMedium", self.play_medium.clone()),
+            ("RecordMedium", self.record_medium.clone()),
+            ("WriteStatus", self.write_status.clone()),
+        ]
+    }
+}
+
+/// Output arguments of [`AVTransport::GetTransportInfo`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GetTransportInfoResponse {
+    /// The transport state, e.g. [`TransportState::Playing`].
+    #[serde(rename = "CurrentTransportState")]
+    pub current_transport_state: TransportState,
+    /// Whether an asynchronous error occurred, `OK` or `ERROR_OCCURRED`.
+    #[serde(rename = "CurrentTransportStatus")]
+    pub current_transport_status: String,
+    /// The current playback speed.
+    #[serde(rename = "CurrentSpeed")]
+    pub current_speed: PlaySpeed,
+}
+
+impl ActionResponse for GetTransportInfoResponse {
+    const SERVICE_TYPE: &'static str = SERVICE_TYPE;
+    const ACTION: &'static str = "GetTransportInfo";
+
+    fn arguments(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "CurrentTransportState",
+                self.current_transport_state.to_string(),
+            ),
+            (
+                "CurrentTransportStatus",
+                self.current_transport_status.clone(),
+            ),
+            ("CurrentSpeed", self.current_speed.to_string()),
+        ]
+    }
+}
+
+/// Output arguments of [`AVTransport::GetCurrentTransportActions`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct GetCurrentTransportActionsResponse {
+    /// The actions currently available, e.g. `Pause,Stop,Seek`.
+    #[serde(rename = "Actions")]
+    pub actions: TransportActions,
+}
+
+impl ActionResponse for GetCurrentTransportActionsResponse {
+    const SERVICE_TYPE: &'static str = SERVICE_TYPE;
+    const ACTION: &'static str = "GetCurrentTransportActions";
+
+    fn arguments(&self) -> Vec<(&'static str, String)> {
+        vec![("Actions", self.actions.to_string())]
+    }
+}
+
+/// Output arguments of [`AVTransport::GetPositionInfo`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GetPositionInfoResponse {
+    /// Index of the current track, `0` if there's none.
+    #[serde(rename = "Track")]
+    pub track: u32,
+    /// Duration of the current track, e.g. `0:03:25`.
+    #[serde(rename = "TrackDuration")]
+    pub track_duration: String,
+    /// The DIDL-Lite metadata of the current track.
+    #[serde(rename = "TrackMetaData")]
+    pub track_meta_data: String,
+    /// The URI of the current track.
+    #[serde(rename = "TrackURI")]
+    pub track_uri: String,
+    /// The position within the current track, e.g. `0:01:10`.
+    #[serde(rename = "RelTime")]
+    pub rel_time: String,
+    /// The position within the whole media, or `NOT_IMPLEMENTED`.
+    #[serde(rename = "AbsTime")]
+    pub abs_time: String,
+    /// The counter position within the current track, `2147483647` if unsupported.
+    #[serde(rename = "RelCount")]
+    pub rel_count: i32,
+    /// The counter position within the whole media, `2147483647` if unsupported.
+    #[serde(rename = "AbsCount")]
+    pub abs_count: i32,
+}
+
+impl ActionResponse for GetPositionInfoResponse {
+    const SERVICE_TYPE: &'static str = SERVICE_TYPE;
+    const ACTION: &'static str = "GetPositionInfo";
+
+    fn arguments(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("Track", self.track.to_string()),
+            ("TrackDuration", self.track_duration.clone()),
+            ("TrackMetaData", self.track_meta_data.clone()),
+            ("TrackURI", self.track_uri.clone()),
+            ("RelTime", self.rel_time.clone()),
+            ("AbsTime", self.abs_time.clone()),
+            ("RelCount", self.rel_count.to_string()),
+            ("AbsCount", self.abs_count.to_string()),
+        ]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::response::action_response;
+    use serde::de::DeserializeOwned;
     use std::fs::read_to_string;
 
     fn get_xml(path: &str) -> AVTransport {
@@ -269,6 +752,15 @@ mod tests {
         av_transport
     }
 
+    #[test]
+    fn test_action_registry() {
+        use crate::template::{AV_TRANSPORT_SCPD, scpd_actions};
+
+        assert_eq!(scpd_actions(AV_TRANSPORT_SCPD), AVTransport::ACTIONS);
+        for name in AVTransport::ACTIONS {
+            assert_eq!(get_xml(&format!("{name}.xml")).name(), *name);
+        }
+    }
     #[test]
     fn test_set_av_transport_uri() {
         let av_transport: AVTransport = get_xml("SetAVTransportURI.xml");
@@ -281,6 +773,72 @@ mod tests {
             "http://example.com/sample.mp4?param1=a&param2=b"
         );
         assert_eq!(set_action.current_uri_meta_data, "");
+        assert_eq!(set_action.scheme(), UriScheme::Http);
+        assert_eq!(set_action.metadata(), None);
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn test_set_av_transport_uri_url() {
+        let xml = read_to_string("tests/AVTransport/SetAVTransportURI.xml")
+            .expect("Failed to read XML file");
+        for (uri, expected) in [
+            (
+                "http://example.com/sample.mp4?param1=a&amp;param2=b",
+                "http://example.com/sample.mp4?param1=a&param2=b",
+            ),
+            (
+                "http://example.com/sample.mp4?param1=a&amp;amp;param2=b",
+                "http://example.com/sample.mp4?param1=a&param2=b",
+            ),
+            ("rtsp://example.com/stream", "rtsp://example.com/stream"),
+        ] {
+            let xml = xml.replace("http://example.com/sample.mp4?param1=a&amp;param2=b", uri);
+            let Ok(AVTransport::SetAVTransportURI(set)) = xml.parse() else {
+                panic!("Expected SetAVTransportURI variant for {uri}");
+            };
+            assert_eq!(set.url().map(String::from).as_deref(), Ok(expected));
+        }
+        let xml = xml.replace(
+            "http://example.com/sample.mp4?param1=a&amp;param2=b",
+            "not a URL",
+        );
+        let Ok(AVTransport::SetAVTransportURI(set)) = xml.parse() else {
+            panic!("Expected SetAVTransportURI variant");
+        };
+        assert_eq!(set.current_uri, "not a URL");
+        assert!(set.url().is_err());
+    }
+
+    #[test]
+    fn test_set_av_transport_uri_metadata() {
+        let xml = read_to_string("tests/AVTransport/SetAVTransportURI.xml")
+            .expect("Failed to read XML file");
+        let didl = read_to_string("tests/DIDL-Lite/Windows.xml").expect("Failed to read XML file");
+        let escaped = quick_xml::escape::escape(didl.trim()).into_owned();
+        // Single escaping is the norm, while some controllers escape the metadata twice
+        for metadata in [
+            escaped.clone(),
+            quick_xml::escape::escape(&escaped).into_owned(),
+        ] {
+            let xml = xml.replace(
+                "<CurrentURIMetaData/>",
+                &format!("<CurrentURIMetaData>{metadata}</CurrentURIMetaData>"),
+            );
+            let Ok(AVTransport::SetAVTransportURI(set)) = xml.parse() else {
+                panic!("Expected SetAVTransportURI variant");
+            };
+            let metadata = set.metadata().expect("Failed to parse metadata");
+            let item = metadata.first_item().expect("Expected an item");
+            assert_eq!(item.title, "Sleep Away");
+            assert_eq!(
+                item.class.as_deref(),
+                Some("object.item.audioItem.musicTrack")
+            );
+            let res = item.res.as_ref().expect("Expected a res element");
+            assert_eq!(res.duration.as_deref(), Some("0:03:20.000"));
+            assert_eq!(res.protocol_info.content_format, "audio/mpeg");
+        }
     }
 
     #[test]
@@ -306,6 +864,40 @@ mod tests {
         assert_eq!(get_action.instance_id, 0);
     }
 
+    #[test]
+    fn test_record() {
+        let AVTransport::Record(record) = get_xml("Record.xml") else {
+            panic!("Expected Record variant")
+        };
+        assert_eq!(record.instance_id, 0);
+        let AVTransport::SetRecordQualityMode(set) = get_xml("SetRecordQualityMode.xml") else {
+            panic!("Expected SetRecordQualityMode variant")
+        };
+        assert_eq!(set.instance_id, 0);
+        assert_eq!(set.new_record_quality_mode, "1:HIGH");
+    }
+
+    #[test]
+    fn test_set_play_mode() {
+        let AVTransport::SetPlayMode(set) = get_xml("SetPlayMode.xml") else {
+            panic!("Expected SetPlayMode variant")
+        };
+        assert_eq!(set.instance_id, 0);
+        assert_eq!(set.new_play_mode, PlayMode::RepeatAll);
+
+        let xml =
+            read_to_string("tests/AVTransport/SetPlayMode.xml").expect("Failed to read XML file");
+        for mode in PlayMode::ALL {
+            let token = mode.to_string();
+            let xml = xml.replace("REPEAT_ALL", &token);
+            let AVTransport::SetPlayMode(set) = xml.parse().expect("Failed to parse SetPlayMode")
+            else {
+                panic!("Expected SetPlayMode variant")
+            };
+            assert_eq!(set.new_play_mode, mode, "{token}");
+        }
+    }
+
     #[test]
     fn test_get_transport_info() {
         let av_transport: AVTransport = get_xml("GetTransportInfo.xml");
@@ -327,6 +919,44 @@ mod tests {
         assert_eq!(play_action.speed, PlaySpeed::One);
     }
 
+    #[test]
+    fn test_play_speeds() {
+        let xml = read_to_string("tests/AVTransport/Play.xml").expect("Failed to read XML file");
+        for (speed, expected) in [
+            ("2", PlaySpeed::Two),
+            ("1/2", PlaySpeed::Half),
+            ("-1", PlaySpeed::MinusOne),
+            ("32", PlaySpeed::Other("32".to_string())),
+        ] {
+            let xml = xml.replace("<Speed>1</Speed>", &format!("<Speed>{speed}</Speed>"));
+            let AVTransport::Play(play) = xml.parse().expect("Failed to parse Play") else {
+                panic!("Expected Play variant")
+            };
+            assert_eq!(play.speed, expected, "{speed}");
+            assert_eq!(play.speed.to_string(), speed);
+        }
+        for speed in PlaySpeed::KNOWN {
+            assert_eq!(PlaySpeed::from(speed.to_string()), speed);
+        }
+    }
+
+    #[test]
+    fn test_parse_error_names_action() {
+        let xml = read_to_string("tests/AVTransport/Play.xml")
+            .expect("Failed to read XML file")
+            .replace("<Speed>1</Speed>", "");
+        let error = xml
+            .parse::<AVTransport>()
+            .expect_err("Parsed Play without Speed");
+        assert_eq!(error.action.as_deref(), Some("Play"));
+        let message = error.to_string();
+        assert!(
+            message.starts_with("Failed to parse Play action: "),
+            "{message}"
+        );
+        assert!(message.contains("Speed"), "{message}");
+    }
+
     #[test]
     fn test_seek() {
         let av_transport: AVTransport = get_xml("Seek.xml");
@@ -337,4 +967,164 @@ mod tests {
         assert_eq!(seek_action.target, "12");
         assert_eq!(seek_action.unit, SeekUnit::RelTime);
     }
+
+    #[test]
+    fn test_seek_target() {
+        let AVTransport::Seek(mut seek) = get_xml("Seek.xml") else {
+            panic!("Expected Seek variant")
+        };
+        for (unit, target, expected) in [
+            (SeekUnit::RelTime, "0:03:12", Some(Duration::from_secs(192))),
+            (
+                SeekUnit::AbsTime,
+                "1:00:00.500",
+                Some(Duration::from_millis(3_600_500)),
+            ),
+            (SeekUnit::RelTime, "03:12", Some(Duration::from_secs(192))),
+            (
+                SeekUnit::RelTime,
+                "25:00:00",
+                Some(Duration::from_hours(25)),
+            ),
+            (SeekUnit::RelTime, "0:03:60", None),
+            (SeekUnit::RelTime, "three minutes", None),
+            (SeekUnit::RelTime, "", None),
+            (SeekUnit::TrackNr, "0:03:12", None),
+        ] {
+            seek.unit = unit;
+            seek.target = target.to_string();
+            assert_eq!(seek.target_duration(), expected, "{unit} {target}");
+            assert_eq!(seek.target_track(), None, "{unit} {target}");
+        }
+        seek.unit = SeekUnit::TrackNr;
+        seek.target = "3".to_string();
+        assert_eq!(seek.target_track(), Some(3));
+        seek.target = "-1".to_string();
+        assert_eq!(seek.target_track(), None);
+    }
+
+    #[test]
+    fn test_seek_unit_round_trip() {
+        let xml = read_to_string("tests/AVTransport/Seek.xml").expect("Failed to read XML file");
+        for unit in SeekUnit::ALL {
+            let token = unit.to_string();
+            let xml = xml.replace("<Unit>REL_TIME</Unit>", &format!("<Unit>{token}</Unit>"));
+            let AVTransport::Seek(seek) = xml.parse().expect("Failed to parse Seek") else {
+                panic!("Expected Seek variant")
+            };
+            assert_eq!(seek.unit, unit, "{token}");
+            assert_eq!(seek.unit.to_string(), token);
+        }
+        assert_eq!(SeekUnit::RelTime.to_string(), "REL_TIME");
+        assert_eq!(SeekUnit::TrackNr.to_string(), "TRACK_NR");
+    }
+
+    #[test]
+    fn test_to_envelope_string() {
+        for name in AVTransport::ACTIONS {
+            let action = get_xml(&format!("{name}.xml"));
+            let xml = action
+                .to_envelope_string()
+                .expect("Failed to serialize action");
+            assert!(
+                xml.contains(&format!(r#"<u:{name} xmlns:u="{SERVICE_TYPE}">"#)),
+                "{xml}"
+            );
+            assert_eq!(soap::action(&xml).as_deref(), Some(*name));
+            let parsed: AVTransport = xml.parse().expect("Failed to parse serialized action");
+            assert_eq!(parsed, action, "{xml}");
+        }
+        let AVTransport::Seek(seek) = get_xml("Seek.xml") else {
+            panic!("Expected Seek variant");
+        };
+        let xml = AVTransport::Seek(seek)
+            .to_envelope_string()
+            .expect("Failed to serialize Seek");
+        let (instance_id, unit, target) = (
+            xml.find("<InstanceID>"),
+            xml.find("<Unit>"),
+            xml.find("<Target>"),
+        );
+        assert!(instance_id < unit && unit < target, "{xml}");
+    }
+
+    /// Serializes a response into a SOAP envelope, then parses its output arguments back.
+    fn round_trip<R: ActionResponse + DeserializeOwned>(response: &R) -> R {
+        let arguments = response.arguments();
+        let arguments: Vec<_> = arguments
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+        let xml = action_response(R::SERVICE_TYPE, R::ACTION, &arguments);
+        assert!(xml.contains(&format!("<u:{}Response", R::ACTION)), "{xml}");
+        xml.parse::<Envelope<R>>()
+            .expect("Failed to parse response")
+            .into_inner()
+    }
+
+    #[test]
+    fn test_media_info_response() {
+        let response = GetMediaInfoResponse {
+            nr_tracks: 1,
+            media_duration: "00:03:25".to_string(),
+            current_uri: "http://example.com/sample.mp4?param1=a&param2=b".to_string(),
+            current_uri_meta_data: r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/"><item><dc:title>Sample &amp; more</dc:title></item></DIDL-Lite>"#.to_string(),
+            next_uri: String::new(),
+            next_uri_meta_data: String::new(),
+            play_medium: "NETWORK".to_string(),
+            record_medium: "NOT_IMPLEMENTED".to_string(),
+            write_status: "NOT_IMPLEMENTED".to_string(),
+        };
+        assert_eq!(round_trip(&response), response);
+    }
+
+    #[test]
+    fn test_transport_info_response() {
+        let response = GetTransportInfoResponse {
+            current_transport_state: TransportState::PausedPlayback,
+            current_transport_status: "OK".to_string(),
+            current_speed: PlaySpeed::Half,
+        };
+        assert_eq!(
+            response.arguments(),
+            [
+                ("CurrentTransportState", "PAUSED_PLAYBACK".to_string()),
+                ("CurrentTransportStatus", "OK".to_string()),
+                ("CurrentSpeed", "1/2".to_string()),
+            ]
+        );
+        assert_eq!(round_trip(&response), response);
+    }
+
+    #[test]
+    fn test_position_info_response() {
+        let response = GetPositionInfoResponse {
+            track: 1,
+            track_duration: "00:03:25".to_string(),
+            track_meta_data: String::new(),
+            track_uri: "http://example.com/sample.mp4".to_string(),
+            rel_time: "00:01:10".to_string(),
+            abs_time: "NOT_IMPLEMENTED".to_string(),
+            rel_count: i32::MAX,
+            abs_count: i32::MAX,
+        };
+        assert_eq!(round_trip(&response), response);
+    }
+
+    #[test]
+    fn test_current_transport_actions_response() {
+        use crate::state::TransportAction::{Pause, Seek, Stop};
+
+        let response = GetCurrentTransportActionsResponse {
+            actions: vec![Pause, Stop, Seek].into(),
+        };
+        assert_eq!(
+            response.arguments(),
+            [("Actions", "Pause,Stop,Seek".to_string())]
+        );
+        assert_eq!(round_trip(&response), response);
+        let empty = GetCurrentTransportActionsResponse::default();
+        assert_eq!(empty.arguments(), [("Actions", String::new())]);
+        assert_eq!(round_trip(&empty), empty);
+    }
 }
@@ -0,0 +1,48 @@
+//! Lenient parsing of `UPnP` booleans.
+//!
+//! `UPnP` allows booleans to be written as `0`/`1`, `false`/`true` or `no`/`yes`, while serde only accepts `true`/`false`. Use [`deserialize`] via `#[serde(deserialize_with = "...")]` on boolean fields.
+
+use serde::{Deserialize, Deserializer, de};
+use std::borrow::Cow;
+
+/// Parses a `UPnP` boolean, case-insensitively and ignoring surrounding whitespace.
+pub fn parse(s: &str) -> Option<bool> {
+    let s = s.trim();
+    if ["1", "true", "yes"]
+        .iter()
+        .any(|t| s.eq_ignore_ascii_case(t))
+    {
+        Some(true)
+    } else if ["0", "false", "no"]
+        .iter()
+        .any(|f| s.eq_ignore_ascii_case(f))
+    {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Deserializes a `UPnP` boolean.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    let s = Cow::<str>::deserialize(deserializer)?;
+    parse(&s).ok_or_else(|| de::Error::custom(format!("Invalid UPnP boolean `{s}`")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        for s in ["1", "true", "TRUE", "yes", "Yes", " 1 "] {
+            assert_eq!(parse(s), Some(true), "{s}");
+        }
+        for s in ["0", "false", "False", "no", "NO"] {
+            assert_eq!(parse(s), Some(false), "{s}");
+        }
+        for s in ["", "2", "on", "truee"] {
+            assert_eq!(parse(s), None, "{s}");
+        }
+    }
+}
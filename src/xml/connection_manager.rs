@@ -0,0 +1,173 @@
+//! Module for deserializing and extracting information from `ConnectionManager` XML messages.
+//!
+//! Documentation on `ConnectionManager` v1 can be found [here](http://upnp.org/specs/av/UPnP-av-ConnectionManager-v1-Service.pdf).
+
+use super::{Body, Envelope};
+use crate::ParseError;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// The service type of `ConnectionManager`.
+pub const SERVICE_TYPE: &str = "urn:schemas-upnp-org:service:ConnectionManager:1";
+
+/// The only connection of a renderer that doesn't implement `PrepareForConnection`, as reported by `GetCurrentConnectionIDs`.
+pub const DEFAULT_CONNECTION_ID: i32 = 0;
+
+/// The envelope structure for `ConnectionManager` XML messages.
+///
+/// Usually, once deserialized, you'll call [`ConnectionManagerEnvelope::into_inner`] to consume it and get the actual content of the message, which you could match against the [`ConnectionManager`] enum to determine the specific action type. For an even simpler usage, [`ConnectionManager`] implements `FromStr`, allowing you to directly deserialize from a XML envelope string.
+///
+/// ## Example
+///
+/// ```rust
+/// use quick_xml::de::from_str;
+/// use dlna_dmr::xml::connection_manager::{ConnectionManagerEnvelope, ConnectionManager};
+///
+/// let xml = r#"<?xml version="1.0" ?>
+/// <s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+///     <s:Body>
+///         <u:GetCurrentConnectionInfo xmlns:u="urn:schemas-upnp-org:service:ConnectionManager:1">
+///             <ConnectionID>0</ConnectionID>
+///         </u:GetCurrentConnectionInfo>
+///     </s:Body>
+/// </s:Envelope>"#;
+/// let deserialized: ConnectionManagerEnvelope = from_str(xml).expect("Failed to deserialize XML");
+/// let ConnectionManager::GetCurrentConnectionInfo(get) = deserialized.into_inner() else {
+///     panic!("Expected GetCurrentConnectionInfo variant");
+/// };
+/// assert_eq!(get.connection_id, 0);
+/// ```
+pub type ConnectionManagerEnvelope = Envelope<ConnectionManager>;
+
+/// Container structure of [`ConnectionManagerEnvelope`].
+pub type SBody = Body<ConnectionManager>;
+
+/// Different types of action that can be invoked in the `ConnectionManager` service. Can be directly parsed from an XML envelope string, IGNORING the outer envelope structure.
+///
+/// Only the required actions are supported: `PrepareForConnection` and `ConnectionComplete` are optional, and renderers without them have a single connection, [`DEFAULT_CONNECTION_ID`].
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::xml::connection_manager::ConnectionManager;
+///
+/// let xml = r#"<?xml version="1.0" ?>
+/// <s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+///     <s:Body>
+///         <u:GetProtocolInfo xmlns:u="urn:schemas-upnp-org:service:ConnectionManager:1"/>
+///     </s:Body>
+/// </s:Envelope>"#;
+/// let connection_manager: ConnectionManager = xml.parse().expect("Failed to parse ConnectionManager");
+/// assert!(matches!(connection_manager, ConnectionManager::GetProtocolInfo(_)));
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionManager {
+    /// Returns the protocol-related info that this service supports when sourcing and sinking content.
+    GetProtocolInfo(GetProtocolInfo),
+    /// Returns a comma-separated list of the currently active connections.
+    GetCurrentConnectionIDs(GetCurrentConnectionIDs),
+    /// Returns information associated with the specified connection.
+    GetCurrentConnectionInfo(GetCurrentConnectionInfo),
+}
+
+actions!(ConnectionManager {
+    GetProtocolInfo,
+    GetCurrentConnectionIDs,
+    GetCurrentConnectionInfo,
+});
+
+impl ConnectionManager {
+    /// Whether the action changes state, as opposed to only querying it. None of the supported actions does.
+    #[must_use]
+    pub const fn is_command(&self) -> bool {
+        false
+    }
+}
+
+impl FromStr for ConnectionManager {
+    type Err = ParseError;
+    /// Deserialize from an envelope, IGNORING the outer envelope structure.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let envelope: ConnectionManagerEnvelope = s.parse()?;
+        Ok(envelope.into_inner())
+    }
+}
+
+/// Arguments for [`ConnectionManager::GetProtocolInfo`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GetProtocolInfo {
+    /// The XML namespace for the `ConnectionManager` service.
+    #[serde(rename = "@xmlns:u", default)]
+    pub xmlns_u: String,
+}
+
+/// Arguments for [`ConnectionManager::GetCurrentConnectionIDs`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GetCurrentConnectionIDs {
+    /// The XML namespace for the `ConnectionManager` service.
+    #[serde(rename = "@xmlns:u", default)]
+    pub xmlns_u: String,
+}
+
+/// Arguments for [`ConnectionManager::GetCurrentConnectionInfo`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GetCurrentConnectionInfo {
+    /// The XML namespace for the `ConnectionManager` service.
+    #[serde(rename = "@xmlns:u", default)]
+    pub xmlns_u: String,
+    /// The connection to get information about, as listed by `GetCurrentConnectionIDs`.
+    #[serde(rename = "ConnectionID")]
+    pub connection_id: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read_to_string;
+
+    fn get_xml(path: &str) -> ConnectionManager {
+        let xml = read_to_string(format!("tests/ConnectionManager/{path}"))
+            .expect("Failed to read XML file");
+        xml.parse().expect("Failed to parse ConnectionManager")
+    }
+
+    #[test]
+    fn test_action_registry() {
+        use crate::template::{CONNECTION_MANAGER_SCPD, scpd_actions};
+
+        assert_eq!(
+            scpd_actions(CONNECTION_MANAGER_SCPD),
+            ConnectionManager::ACTIONS
+        );
+        for name in ConnectionManager::ACTIONS {
+            assert_eq!(get_xml(&format!("{name}.xml")).name(), *name);
+        }
+    }
+
+    #[test]
+    fn test_get_protocol_info() {
+        let ConnectionManager::GetProtocolInfo(get) = get_xml("GetProtocolInfo.xml") else {
+            panic!("Expected GetProtocolInfo variant");
+        };
+        assert_eq!(get.xmlns_u, SERVICE_TYPE);
+
+        // Self-closing, as most controllers send it
+        let xml = read_to_string("tests/ConnectionManager/GetProtocolInfo.xml")
+            .expect("Failed to read XML file")
+            .replace("></u:GetProtocolInfo>", "/>");
+        assert!(matches!(
+            xml.parse(),
+            Ok(ConnectionManager::GetProtocolInfo(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_current_connection_info() {
+        let ConnectionManager::GetCurrentConnectionInfo(get) =
+            get_xml("GetCurrentConnectionInfo.xml")
+        else {
+            panic!("Expected GetCurrentConnectionInfo variant");
+        };
+        assert_eq!(get.connection_id, DEFAULT_CONNECTION_ID);
+    }
+}
@@ -0,0 +1,317 @@
+//! Module for building the device description served at `/DeviceSpec`.
+//!
+//! Documentation on the device description can be found in the [`UPnP` Device Architecture v1.0](https://upnp.org/specs/arch/UPnP-arch-DeviceArchitecture-v1.0.pdf).
+
+use crate::DMROptions;
+use quick_xml::{DeError, SeError, de, se};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// The device type of a media renderer, advertised in the description and over SSDP.
+pub const DEVICE_TYPE: &str = "urn:schemas-upnp-org:device:MediaRenderer:1";
+
+/// The device description of a media renderer, serialized to the XML document controllers fetch after discovery.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::{DMROptions, xml::device_description::DeviceDescription};
+///
+/// let options = DMROptions {
+///     friendly_name: "Tom & Jerry".to_string(),
+///     ..Default::default()
+/// };
+/// let xml = DeviceDescription::from(&options)
+///     .to_xml()
+///     .expect("Failed to serialize device description");
+/// assert!(xml.contains("<friendlyName>Tom &amp; Jerry</friendlyName>"));
+/// let parsed: DeviceDescription = xml.parse().expect("Failed to parse device description");
+/// assert_eq!(parsed.device.friendly_name, "Tom & Jerry");
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename = "root")]
+pub struct DeviceDescription {
+    /// The XML namespace for device descriptions.
+    #[serde(rename = "@xmlns")]
+    pub xmlns: String,
+    /// The version of the `UPnP` Device Architecture.
+    #[serde(rename = "specVersion")]
+    pub spec_version: SpecVersion,
+    /// The root device.
+    pub device: Device,
+}
+
+impl DeviceDescription {
+    /// The XML namespace for device descriptions.
+    pub const XMLNS: &str = "urn:schemas-upnp-org:device-1-0";
+
+    /// Serializes the description into an XML document, including the XML declaration.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_xml(&self) -> Result<String, SeError> {
+        let mut xml = r#"<?xml version="1.0" encoding="utf-8"?>"#.to_string();
+        xml.push('\n');
+        se::to_writer(&mut xml, self)?;
+        Ok(xml)
+    }
+}
+
+impl From<&DMROptions> for DeviceDescription {
+    /// Builds the description of a media renderer with `RenderingControl`, `AVTransport` and `ConnectionManager` services of [`DMROptions::service_versions`], whose URLs are prefixed with [`DMROptions::advertise_location`] if set.
+    fn from(options: &DMROptions) -> Self {
+        let base_url = options.base_url();
+        let [rendering_control, av_transport, connection_manager] =
+            options.service_versions.service_types();
+        let service = |service_type: &str, id: &str, path: &str| Service {
+            service_type: service_type.to_string(),
+            service_id: format!("urn:upnp-org:serviceId:{id}"),
+            control_url: format!("{base_url}/{path}"),
+            event_sub_url: format!("{base_url}/{path}/event"),
+            scpd_url: format!("{base_url}/{path}"),
+        };
+        Self {
+            xmlns: Self::XMLNS.to_string(),
+            spec_version: SpecVersion { major: 1, minor: 0 },
+            device: Device {
+                device_type: DEVICE_TYPE.to_string(),
+                friendly_name: options.friendly_name.clone(),
+                model_number: "1".to_string(),
+                model_name: options.model_name.clone(),
+                model_description: options.model_description.clone(),
+                model_url: options.model_url.clone(),
+                manufacturer: options.manufacturer.clone(),
+                manufacturer_url: options.manufacturer_url.clone(),
+                serial_number: options.serial_number.clone(),
+                udn: format!("uuid:{}", options.uuid),
+                dlna_doc: DlnaDoc {
+                    xmlns_dlna: DlnaDoc::XMLNS.to_string(),
+                    value: "DMR-1.50".to_string(),
+                },
+                icon_list: IconList::default(),
+                service_list: ServiceList {
+                    services: vec![
+                        service(&rendering_control, "RenderingControl", "RenderingControl"),
+                        service(&av_transport, "AVTransport", "AVTransport"),
+                        service(
+                            &connection_manager,
+                            "ConnectionManager",
+                            "ConnectionManager",
+                        ),
+                    ],
+                },
+            },
+        }
+    }
+}
+
+impl FromStr for DeviceDescription {
+    type Err = DeError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        de::from_str(s)
+    }
+}
+
+/// The version of the `UPnP` Device Architecture a description conforms to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpecVersion {
+    /// Major version, always `1`.
+    pub major: u8,
+    /// Minor version.
+    pub minor: u8,
+}
+
+/// A device in a description.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    /// The type of the device, e.g. `urn:schemas-upnp-org:device:MediaRenderer:1`.
+    #[serde(rename = "deviceType")]
+    pub device_type: String,
+    /// Short name shown to users.
+    #[serde(rename = "friendlyName")]
+    pub friendly_name: String,
+    /// Model number.
+    #[serde(rename = "modelNumber")]
+    pub model_number: String,
+    /// Model name.
+    #[serde(rename = "modelName")]
+    pub model_name: String,
+    /// Long description of the model.
+    #[serde(rename = "modelDescription")]
+    pub model_description: String,
+    /// Web site of the model.
+    #[serde(rename = "modelURL")]
+    pub model_url: String,
+    /// Manufacturer name.
+    pub manufacturer: String,
+    /// Web site of the manufacturer.
+    #[serde(rename = "manufacturerURL")]
+    pub manufacturer_url: String,
+    /// Serial number.
+    #[serde(rename = "serialNumber")]
+    pub serial_number: String,
+    /// Unique Device Name, i.e. `uuid:` followed by the UUID.
+    #[serde(rename = "UDN")]
+    pub udn: String,
+    /// The DLNA device class and version.
+    #[serde(rename = "dlna:X_DLNADOC", alias = "X_DLNADOC")]
+    pub dlna_doc: DlnaDoc,
+    /// Icons of the device.
+    #[serde(rename = "iconList", default)]
+    pub icon_list: IconList,
+    /// Services of the device.
+    #[serde(rename = "serviceList")]
+    pub service_list: ServiceList,
+}
+
+/// The DLNA device class and version of a device (`dlna:X_DLNADOC`), e.g. `DMR-1.50`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DlnaDoc {
+    /// The XML namespace for DLNA extensions.
+    #[serde(rename = "@xmlns:dlna")]
+    pub xmlns_dlna: String,
+    /// The device class and version.
+    #[serde(rename = "$text")]
+    pub value: String,
+}
+
+impl DlnaDoc {
+    /// The XML namespace for DLNA extensions.
+    pub const XMLNS: &str = "urn:schemas-dlna-org:device-1-0";
+}
+
+/// Icons of a device.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct IconList {
+    /// The icons.
+    #[serde(rename = "icon", default)]
+    pub icons: Vec<Icon>,
+}
+
+/// An icon of a device.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Icon {
+    /// MIME type of the image, e.g. `image/png`.
+    pub mimetype: String,
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Color depth in bits.
+    pub depth: u32,
+    /// URL of the image, relative to the description.
+    pub url: String,
+}
+
+/// Services of a device.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServiceList {
+    /// The services.
+    #[serde(rename = "service", default)]
+    pub services: Vec<Service>,
+}
+
+/// A service of a device.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Service {
+    /// The type of the service, e.g. `urn:schemas-upnp-org:service:AVTransport:1`.
+    #[serde(rename = "serviceType")]
+    pub service_type: String,
+    /// The identifier of the service, e.g. `urn:upnp-org:serviceId:AVTransport`.
+    #[serde(rename = "serviceId")]
+    pub service_id: String,
+    /// URL for control, where SOAP actions are posted.
+    #[serde(rename = "controlURL")]
+    pub control_url: String,
+    /// URL for eventing, empty if unsupported.
+    #[serde(rename = "eventSubURL", default)]
+    pub event_sub_url: String,
+    /// URL of the service description.
+    #[serde(rename = "SCPDURL")]
+    pub scpd_url: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{render_device_spec, template::DEVICE_SPEC};
+
+    fn options() -> DMROptions {
+        DMROptions {
+            friendly_name: "Tom & Jerry <Living Room>".to_string(),
+            uuid: "12345678-1234-5678-1234-567812345678".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let description = DeviceDescription::from(&options());
+        let xml = description.to_xml().expect("Failed to serialize");
+        assert!(xml.contains("<friendlyName>Tom &amp; Jerry &lt;Living Room&gt;</friendlyName>"));
+        assert!(xml.contains("<UDN>uuid:12345678-1234-5678-1234-567812345678</UDN>"));
+        let parsed: DeviceDescription = xml.parse().expect("Failed to parse");
+        assert_eq!(parsed, description);
+    }
+
+    #[test]
+    fn test_base_url() {
+        let options = DMROptions {
+            advertise_location: Some("http://proxy.example.com:9000/renderer/".to_string()),
+            ..options()
+        };
+        let description = DeviceDescription::from(&options);
+        let urls: Vec<_> = description
+            .device
+            .service_list
+            .services
+            .iter()
+            .map(|service| service.control_url.as_str())
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                "http://proxy.example.com:9000/renderer/RenderingControl",
+                "http://proxy.example.com:9000/renderer/AVTransport",
+                "http://proxy.example.com:9000/renderer/ConnectionManager",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_service_versions() {
+        let options = DMROptions {
+            service_versions: crate::ServiceVersions {
+                av_transport: 2,
+                rendering_control: 2,
+                ..Default::default()
+            },
+            ..options()
+        };
+        let description = DeviceDescription::from(&options);
+        let types: Vec<_> = description
+            .device
+            .service_list
+            .services
+            .iter()
+            .map(|service| service.service_type.as_str())
+            .collect();
+        assert_eq!(
+            types,
+            [
+                "urn:schemas-upnp-org:service:RenderingControl:2",
+                "urn:schemas-upnp-org:service:AVTransport:2",
+                "urn:schemas-upnp-org:service:ConnectionManager:1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_matches_template() {
+        let options = options();
+        let rendered = render_device_spec(DEVICE_SPEC, &options).expect("Failed to render");
+        let parsed: DeviceDescription = rendered.parse().expect("Failed to parse template");
+        assert_eq!(parsed, DeviceDescription::from(&options));
+    }
+}
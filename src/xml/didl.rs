@@ -0,0 +1,347 @@
+//! Module for deserializing DIDL-Lite metadata, as sent along with URIs in `SetAVTransportURI` and `SetNextAVTransportURI`.
+//!
+//! Documentation on DIDL-Lite can be found in the [`ContentDirectory` v1 specification](https://upnp.org/specs/av/UPnP-av-ContentDirectory-v1-Service.pdf).
+
+use super::protocol_info::ProtocolInfo;
+use crate::media::{duration_from_bytes, parse_duration};
+use quick_xml::{DeError, de, escape::unescape};
+use serde::{
+    Deserialize, Deserializer,
+    de::{MapAccess, Visitor},
+};
+use std::{str::FromStr, time::Duration};
+
+/// A DIDL-Lite document describing one or more media items, e.g. a whole playlist.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::xml::didl::DidlLite;
+///
+/// let xml = r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/">
+///     <item id="1" parentID="0" restricted="1">
+///         <dc:title>Sample</dc:title>
+///         <upnp:class>object.item.videoItem</upnp:class>
+///         <res protocolInfo="http-get:*:video/mp4:*">http://example.com/sample.mp4</res>
+///     </item>
+/// </DIDL-Lite>"#;
+/// let didl: DidlLite = xml.parse().expect("Failed to parse DIDL-Lite");
+/// assert_eq!(didl.len(), 1);
+/// assert_eq!(didl.first_item().map(|item| item.title.as_str()), Some("Sample"));
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DidlLite {
+    /// The media items described, in document order.
+    #[serde(rename = "item", default)]
+    pub items: Vec<Item>,
+}
+
+impl DidlLite {
+    /// Parses the DIDL-Lite metadata embedded in an action argument, e.g. [`SetAVTransportURI::current_uri_meta_data`](super::av_transport::SetAVTransportURI::current_uri_meta_data), returning `None` if it's empty or malformed. Some controllers escape the metadata twice, so that it's still escaped after the SOAP envelope is parsed; such metadata is unescaped once more before parsing.
+    #[must_use]
+    pub fn from_metadata(metadata: &str) -> Option<Self> {
+        let metadata = metadata.trim();
+        if metadata.starts_with("&lt;") {
+            return unescape(metadata).ok()?.parse().ok();
+        }
+        metadata.parse().ok()
+    }
+
+    /// The first media item, which is the one to play for single-item documents.
+    #[must_use]
+    pub fn first_item(&self) -> Option<&Item> {
+        self.items.first()
+    }
+
+    /// The number of media items.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether the document describes no media item.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl FromStr for DidlLite {
+    type Err = DeError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        de::from_str(s)
+    }
+}
+
+/// A media item in a DIDL-Lite document.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    /// The title of the item (`dc:title`).
+    pub title: String,
+    /// The class of the item (`upnp:class`), e.g. `object.item.videoItem`.
+    #[serde(default)]
+    pub class: Option<String>,
+    /// The album art or thumbnails of the item (`upnp:albumArtURI`), one per image format offered.
+    #[serde(rename = "albumArtURI", default)]
+    pub album_art_uris: Vec<String>,
+    /// The resource of the item, i.e. where and how to fetch it.
+    #[serde(default)]
+    pub res: Option<Res>,
+}
+
+impl Item {
+    /// The URI of the first album art or thumbnail of the item, if any.
+    #[must_use]
+    pub fn album_art_uri(&self) -> Option<&str> {
+        self.album_art_uris.first().map(String::as_str)
+    }
+}
+
+/// A resource (`res` element) of a media item.
+///
+/// Attributes are matched by their local name, and only the first of the same name is kept, since some controllers add vendor attributes like `microsoft:bitrate` next to `bitrate`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(try_from = "RawRes")]
+pub struct Res {
+    /// How the resource is transported and what format it's in.
+    #[serde(rename = "@protocolInfo")]
+    pub protocol_info: ProtocolInfo,
+    /// The duration of the resource, in the form `H+:MM:SS[.F+]`.
+    #[serde(rename = "@duration", default)]
+    pub duration: Option<String>,
+    /// The size of the resource, in bytes.
+    #[serde(rename = "@size", default)]
+    pub size: Option<u64>,
+    /// The bitrate of the resource, in bytes per second.
+    #[serde(rename = "@bitrate", default)]
+    pub bitrate: Option<u32>,
+    /// The URI of the resource.
+    #[serde(rename = "$text")]
+    pub uri: String,
+}
+
+/// The attributes and text of a `res` element, in document order, before [`Res`] picks the first of each.
+#[derive(Deserialize)]
+struct RawRes(#[serde(deserialize_with = "deserialize_entries")] Vec<(String, String)>);
+
+/// Deserializes the entries of a map as strings, keeping duplicate keys.
+fn deserialize_entries<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(String, String)>, D::Error> {
+    struct Entries;
+
+    impl<'de> Visitor<'de> for Entries {
+        type Value = Vec<(String, String)>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "a res element")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut entries = Vec::new();
+            while let Some(entry) = map.next_entry()? {
+                entries.push(entry);
+            }
+            Ok(entries)
+        }
+    }
+
+    deserializer.deserialize_map(Entries)
+}
+
+impl TryFrom<RawRes> for Res {
+    type Error = String;
+
+    fn try_from(RawRes(entries): RawRes) -> Result<Self, Self::Error> {
+        let first = |key: &str| {
+            entries
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.trim())
+        };
+        Ok(Self {
+            protocol_info: first("@protocolInfo")
+                .ok_or("Missing `protocolInfo` attribute")?
+                .parse()
+                .map_err(|e| format!("{e}"))?,
+            duration: first("@duration").map(str::to_string),
+            size: parse_attribute("size", first("@size"))?,
+            bitrate: parse_attribute("bitrate", first("@bitrate"))?,
+            uri: first("$text").unwrap_or_default().to_string(),
+        })
+    }
+}
+
+/// Parses the value of an optional numeric attribute.
+fn parse_attribute<T: FromStr>(name: &str, value: Option<&str>) -> Result<Option<T>, String> {
+    value
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("Invalid `{name}` attribute `{value}`"))
+        })
+        .transpose()
+}
+
+impl Res {
+    /// The duration of the resource, parsed from [`duration`](Res::duration), or else estimated from [`size`](Res::size) and [`bitrate`](Res::bitrate) (see [`duration_from_bytes`]). Pass the HTTP `Content-Length` as `content_length` to use it if the size is missing.
+    #[must_use]
+    pub fn duration_or_estimate(&self, content_length: Option<u64>) -> Option<Duration> {
+        if let Some(duration) = self.duration.as_deref().and_then(parse_duration) {
+            return Some(duration);
+        }
+        let size = self.size.or(content_length)?;
+        let bitrate = self.bitrate.filter(|&bitrate| bitrate > 0)?;
+        Some(duration_from_bytes(size, bitrate.saturating_mul(8)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read_to_string;
+
+    #[test]
+    fn test_res() {
+        let xml = r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/">
+            <item id="1" parentID="0" restricted="1">
+                <dc:title>Sample</dc:title>
+                <upnp:class>object.item.videoItem</upnp:class>
+                <res protocolInfo="http-get:*:video/mp4:DLNA.ORG_PN=AVC_MP4_BL_CIF15_AAC_520" duration="0:01:30.000">http://example.com/sample.mp4</res>
+            </item>
+        </DIDL-Lite>"#;
+        let didl: DidlLite = xml.parse().expect("Failed to parse DIDL-Lite");
+        let item = didl.first_item().expect("Expected an item");
+        assert_eq!(item.title, "Sample");
+        assert_eq!(item.class.as_deref(), Some("object.item.videoItem"));
+        let res = item.res.as_ref().expect("Expected a res element");
+        assert_eq!(res.uri, "http://example.com/sample.mp4");
+        assert_eq!(res.duration.as_deref(), Some("0:01:30.000"));
+        assert_eq!(
+            res.duration_or_estimate(None),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(res.protocol_info.content_format, "video/mp4");
+        assert_eq!(
+            res.protocol_info.dlna_profile.as_deref(),
+            Some("AVC_MP4_BL_CIF15_AAC_520")
+        );
+    }
+
+    #[test]
+    fn test_estimated_duration() {
+        let xml = r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/" xmlns:dc="http://purl.org/dc/elements/1.1/">
+            <item id="1" parentID="0" restricted="1">
+                <dc:title>Sample</dc:title>
+                <res protocolInfo="http-get:*:audio/mpeg:*" bitrate="40000">http://example.com/sample.mp3</res>
+            </item>
+        </DIDL-Lite>"#;
+        let didl: DidlLite = xml.parse().expect("Failed to parse DIDL-Lite");
+        let res = didl
+            .first_item()
+            .and_then(|item| item.res.as_ref())
+            .expect("Expected a res element");
+        // 320 kbps, without a size
+        assert_eq!(res.duration_or_estimate(None), None);
+        assert_eq!(
+            res.duration_or_estimate(Some(4_000_000)),
+            Some(Duration::from_secs(100))
+        );
+    }
+
+    #[test]
+    fn test_playlist() {
+        let xml = r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/">
+            <item id="1" parentID="0" restricted="1">
+                <dc:title>First</dc:title>
+                <res protocolInfo="http-get:*:audio/mpeg:*">http://example.com/1.mp3</res>
+            </item>
+            <item id="2" parentID="0" restricted="1">
+                <dc:title>Second</dc:title>
+                <res protocolInfo="http-get:*:audio/mpeg:*">http://example.com/2.mp3</res>
+            </item>
+            <item id="3" parentID="0" restricted="1">
+                <dc:title>Third</dc:title>
+                <res protocolInfo="http-get:*:audio/flac:*">http://example.com/3.flac</res>
+            </item>
+        </DIDL-Lite>"#;
+        let didl: DidlLite = xml.parse().expect("Failed to parse DIDL-Lite");
+        assert_eq!(didl.len(), 3);
+        let items: Vec<_> = didl
+            .items
+            .iter()
+            .map(|item| {
+                let res = item.res.as_ref().expect("Expected a res element");
+                (item.title.as_str(), res.uri.as_str())
+            })
+            .collect();
+        assert_eq!(
+            items,
+            [
+                ("First", "http://example.com/1.mp3"),
+                ("Second", "http://example.com/2.mp3"),
+                ("Third", "http://example.com/3.flac"),
+            ]
+        );
+        assert_eq!(didl.first_item(), didl.items.first());
+    }
+
+    #[test]
+    fn test_empty() {
+        let xml = r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/"></DIDL-Lite>"#;
+        let didl: DidlLite = xml.parse().expect("Failed to parse DIDL-Lite");
+        assert!(didl.is_empty());
+        assert_eq!(didl.first_item(), None);
+    }
+
+    #[test]
+    fn test_controller_samples() {
+        for (file, title, class, album_art, duration, content_format) in [
+            (
+                "Windows.xml",
+                "Sleep Away",
+                "object.item.audioItem.musicTrack",
+                "http://192.168.1.20:10243/WMPNSSv4/3847229410/0_ezlDNEQ0QzBCLTBCNEYtNEM1NS04QUY1LTVEMUMzQTZFNUYyMX0uMC40.jpg?albumArt=true",
+                Duration::from_secs(200),
+                "audio/mpeg",
+            ),
+            (
+                "BubbleUPnP.xml",
+                "Big Buck Bunny",
+                "object.item.videoItem",
+                "http://192.168.1.30:57645/external/video/thumbnails/1.jpg",
+                Duration::from_millis(596_467),
+                "video/mp4",
+            ),
+        ] {
+            let xml =
+                read_to_string(format!("tests/DIDL-Lite/{file}")).expect("Failed to read XML file");
+            let didl: DidlLite = xml.parse().expect("Failed to parse DIDL-Lite");
+            let item = didl.first_item().expect("Expected an item");
+            assert_eq!(item.title, title, "{file}");
+            assert_eq!(item.class.as_deref(), Some(class), "{file}");
+            assert_eq!(item.album_art_uri(), Some(album_art), "{file}");
+            let res = item.res.as_ref().expect("Expected a res element");
+            assert_eq!(res.duration_or_estimate(None), Some(duration), "{file}");
+            assert_eq!(res.protocol_info.content_format, content_format, "{file}");
+        }
+    }
+
+    #[test]
+    fn test_from_metadata() {
+        let xml =
+            read_to_string("tests/DIDL-Lite/BubbleUPnP.xml").expect("Failed to read XML file");
+        let expected: DidlLite = xml.parse().expect("Failed to parse DIDL-Lite");
+        let escaped = xml
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;");
+        for metadata in [xml.as_str(), escaped.as_str()] {
+            assert_eq!(DidlLite::from_metadata(metadata).as_ref(), Some(&expected));
+        }
+        for metadata in ["", "  ", "not DIDL-Lite", "&lt;DIDL-Lite"] {
+            assert_eq!(DidlLite::from_metadata(metadata), None, "{metadata}");
+        }
+    }
+}
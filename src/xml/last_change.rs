@@ -0,0 +1,242 @@
+//! Module for building `LastChange` event documents, which carry state variable changes to subscribers of `AVTransport` and `RenderingControl`.
+//!
+//! Each service has its own event namespace: `AVTransport` uses `urn:schemas-upnp-org:metadata-1-0/AVT/`, while `RenderingControl` uses `urn:schemas-upnp-org:metadata-1-0/RCS/`.
+
+use super::rendering_control::Channel;
+use crate::state::TransportState;
+use quick_xml::escape::escape;
+use std::fmt::{Display, Write};
+
+/// The service a `LastChange` event belongs to, determining its namespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventNamespace {
+    /// `urn:schemas-upnp-org:metadata-1-0/AVT/`, for `AVTransport` events.
+    AVTransport,
+    /// `urn:schemas-upnp-org:metadata-1-0/RCS/`, for `RenderingControl` events.
+    RenderingControl,
+}
+
+impl EventNamespace {
+    /// The namespace URI of the event document.
+    #[must_use]
+    pub const fn uri(self) -> &'static str {
+        match self {
+            Self::AVTransport => "urn:schemas-upnp-org:metadata-1-0/AVT/",
+            Self::RenderingControl => "urn:schemas-upnp-org:metadata-1-0/RCS/",
+        }
+    }
+}
+
+/// A single state variable change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Change {
+    name: String,
+    channel: Option<String>,
+    value: String,
+}
+
+/// A `LastChange` event document, listing changed state variables per instance.
+///
+/// Serialize it with [`to_string`](ToString::to_string) to get the `Event` document, or with [`property_set`](LastChange::property_set) to get the body of a GENA `NOTIFY` request.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::xml::{last_change::LastChange, rendering_control::Channel};
+///
+/// let event = LastChange::rendering_control()
+///     .volume(0, Channel::Master, 50)
+///     .mute(0, Channel::Master, false);
+/// assert_eq!(
+///     event.to_string(),
+///     r#"<Event xmlns="urn:schemas-upnp-org:metadata-1-0/RCS/"><InstanceID val="0"><Volume channel="Master" val="50"/><Mute channel="Master" val="0"/></InstanceID></Event>"#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastChange {
+    namespace: EventNamespace,
+    instances: Vec<(u32, Vec<Change>)>,
+}
+
+impl LastChange {
+    /// Creates an empty event in the given namespace.
+    #[must_use]
+    pub const fn new(namespace: EventNamespace) -> Self {
+        Self {
+            namespace,
+            instances: Vec::new(),
+        }
+    }
+
+    /// Creates an empty `AVTransport` event.
+    #[must_use]
+    pub const fn av_transport() -> Self {
+        Self::new(EventNamespace::AVTransport)
+    }
+
+    /// Creates an empty `RenderingControl` event.
+    #[must_use]
+    pub const fn rendering_control() -> Self {
+        Self::new(EventNamespace::RenderingControl)
+    }
+
+    /// The namespace of this event.
+    #[must_use]
+    pub const fn namespace(&self) -> EventNamespace {
+        self.namespace
+    }
+
+    /// Whether no change has been recorded.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    /// Records a change of state variable `name` on the given instance. A later change of the same variable replaces the earlier one.
+    #[must_use]
+    pub fn set(self, instance_id: u32, name: &str, value: impl Display) -> Self {
+        self.push(instance_id, name, None, value.to_string())
+    }
+
+    /// Records a change of the per-channel state variable `name` on the given instance, e.g. `Volume`.
+    #[must_use]
+    pub fn set_channel(
+        self,
+        instance_id: u32,
+        name: &str,
+        channel: Channel,
+        value: impl Display,
+    ) -> Self {
+        self.push(instance_id, name, Some(channel.into()), value.to_string())
+    }
+
+    /// Records a change of `TransportState`.
+    #[must_use]
+    pub fn transport_state(self, instance_id: u32, state: TransportState) -> Self {
+        self.set(instance_id, "TransportState", state)
+    }
+
+    /// Records a change of `Volume` on the given channel.
+    #[must_use]
+    pub fn volume(self, instance_id: u32, channel: Channel, volume: u16) -> Self {
+        self.set_channel(instance_id, "Volume", channel, volume)
+    }
+
+    /// Records a change of `Mute` on the given channel.
+    #[must_use]
+    pub fn mute(self, instance_id: u32, channel: Channel, mute: bool) -> Self {
+        self.set_channel(instance_id, "Mute", channel, u8::from(mute))
+    }
+
+    fn push(
+        mut self,
+        instance_id: u32,
+        name: &str,
+        channel: Option<String>,
+        value: String,
+    ) -> Self {
+        let index = self
+            .instances
+            .iter()
+            .position(|(id, _)| *id == instance_id)
+            .unwrap_or_else(|| {
+                self.instances.push((instance_id, Vec::new()));
+                self.instances.len() - 1
+            });
+        let changes = &mut self.instances[index].1;
+        changes.retain(|change| change.name != name || change.channel != channel);
+        changes.push(Change {
+            name: name.to_string(),
+            channel,
+            value,
+        });
+        self
+    }
+
+    /// Wraps the escaped event document into a GENA property set, as sent in the body of `NOTIFY` requests.
+    #[must_use]
+    pub fn property_set(&self) -> String {
+        property_set(&[("LastChange", &self.to_string())])
+    }
+}
+
+/// A GENA property set of evented state variables and their values, as sent in the body of `NOTIFY` requests, e.g. for `ConnectionManager` which doesn't use `LastChange`.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::xml::last_change::property_set;
+///
+/// assert!(property_set(&[("CurrentConnectionIDs", "0")])
+///     .ends_with("<e:property><CurrentConnectionIDs>0</CurrentConnectionIDs></e:property></e:propertyset>"));
+/// ```
+#[must_use]
+pub fn property_set(properties: &[(&str, &str)]) -> String {
+    let mut xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<e:propertyset xmlns:e="urn:schemas-upnp-org:event-1-0">"#
+        .to_string();
+    for (name, value) in properties {
+        write!(
+            xml,
+            "<e:property><{name}>{}</{name}></e:property>",
+            escape(*value)
+        )
+        .expect("Writing to a String never fails");
+    }
+    xml.push_str("</e:propertyset>");
+    xml
+}
+
+impl Display for LastChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, r#"<Event xmlns="{}">"#, self.namespace.uri())?;
+        for (instance_id, changes) in &self.instances {
+            write!(f, r#"<InstanceID val="{instance_id}">"#)?;
+            for change in changes {
+                write!(f, "<{}", change.name)?;
+                if let Some(channel) = &change.channel {
+                    write!(f, r#" channel="{}""#, escape(channel))?;
+                }
+                write!(f, r#" val="{}"/>"#, escape(&change.value))?;
+            }
+            f.write_str("</InstanceID>")?;
+        }
+        f.write_str("</Event>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rendering_control_namespace() {
+        let event = LastChange::rendering_control().volume(0, Channel::Master, 42);
+        let xml = event.to_string();
+        assert!(xml.starts_with(r#"<Event xmlns="urn:schemas-upnp-org:metadata-1-0/RCS/">"#));
+        assert!(
+            xml.contains(r#"<InstanceID val="0"><Volume channel="Master" val="42"/></InstanceID>"#)
+        );
+    }
+
+    #[test]
+    fn test_av_transport_namespace() {
+        let event = LastChange::av_transport()
+            .transport_state(0, TransportState::Stopped)
+            .transport_state(0, TransportState::Playing);
+        assert_eq!(
+            event.to_string(),
+            r#"<Event xmlns="urn:schemas-upnp-org:metadata-1-0/AVT/"><InstanceID val="0"><TransportState val="PLAYING"/></InstanceID></Event>"#
+        );
+    }
+
+    #[test]
+    fn test_property_set() {
+        let event = LastChange::rendering_control().mute(0, Channel::Master, true);
+        let body = event.property_set();
+        assert!(body.contains(
+            "<LastChange>&lt;Event xmlns=&quot;urn:schemas-upnp-org:metadata-1-0/RCS/&quot;&gt;"
+        ));
+        assert!(body.contains("&lt;Mute channel=&quot;Master&quot; val=&quot;1&quot;/&gt;"));
+    }
+}
@@ -0,0 +1,302 @@
+//! Module for extracting the gist of control messages without serde.
+//!
+//! The parser here is hand-rolled and minimal: it only extracts the action name, the `InstanceID` and the one or two arguments that matter for each action, ignoring namespaces and anything else. Use [`AVTransport`](super::AVTransport) and [`RenderingControl`](super::RenderingControl) for full parsing.
+//!
+//! Requires the `lite-parse` feature.
+
+use super::{boolean, volume};
+use std::{fmt::Display, str::FromStr};
+
+/// An action extracted from a control message by the lite parser.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::xml::lite::LiteAction;
+///
+/// let xml = r#"<?xml version="1.0"?>
+/// <s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+///     <s:Body>
+///         <u:Play xmlns:u="urn:schemas-upnp-org:service:AVTransport:1">
+///             <Speed>1</Speed>
+///             <InstanceID>0</InstanceID>
+///         </u:Play>
+///     </s:Body>
+/// </s:Envelope>"#;
+/// let action: LiteAction = xml.parse().expect("Failed to parse action");
+/// assert_eq!(action.name(), "Play");
+/// assert_eq!(action.instance_id(), 0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LiteAction {
+    /// `AVTransport::SetAVTransportURI`, with the current URI.
+    SetAVTransportURI {
+        /// The `InstanceID` argument.
+        instance_id: u32,
+        /// The `CurrentURI` argument.
+        uri: String,
+    },
+    /// `AVTransport::SetNextAVTransportURI`, with the next URI.
+    SetNextAVTransportURI {
+        /// The `InstanceID` argument.
+        instance_id: u32,
+        /// The `NextURI` argument.
+        uri: String,
+    },
+    /// `AVTransport::Play`, with the speed.
+    Play {
+        /// The `InstanceID` argument.
+        instance_id: u32,
+        /// The `Speed` argument.
+        speed: String,
+    },
+    /// `AVTransport::Seek`, with the unit and target.
+    Seek {
+        /// The `InstanceID` argument.
+        instance_id: u32,
+        /// The `Unit` argument.
+        unit: String,
+        /// The `Target` argument.
+        target: String,
+    },
+    /// `RenderingControl::SetVolume`, with the channel and desired volume.
+    SetVolume {
+        /// The `InstanceID` argument.
+        instance_id: u32,
+        /// The `Channel` argument.
+        channel: String,
+        /// The `DesiredVolume` argument.
+        volume: u16,
+    },
+    /// `RenderingControl::SetMute`, with the channel and desired mute state.
+    SetMute {
+        /// The `InstanceID` argument.
+        instance_id: u32,
+        /// The `Channel` argument.
+        channel: String,
+        /// The `DesiredMute` argument.
+        mute: bool,
+    },
+    /// Any other action, e.g. `Stop` or `GetVolume`.
+    Other {
+        /// The name of the action.
+        name: String,
+        /// The `InstanceID` argument.
+        instance_id: u32,
+    },
+}
+
+impl LiteAction {
+    /// The name of the action, e.g. `Play`.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            Self::SetAVTransportURI { .. } => "SetAVTransportURI",
+            Self::SetNextAVTransportURI { .. } => "SetNextAVTransportURI",
+            Self::Play { .. } => "Play",
+            Self::Seek { .. } => "Seek",
+            Self::SetVolume { .. } => "SetVolume",
+            Self::SetMute { .. } => "SetMute",
+            Self::Other { name, .. } => name,
+        }
+    }
+
+    /// The virtual instance the action applies to.
+    #[must_use]
+    pub const fn instance_id(&self) -> u32 {
+        match self {
+            Self::SetAVTransportURI { instance_id, .. }
+            | Self::SetNextAVTransportURI { instance_id, .. }
+            | Self::Play { instance_id, .. }
+            | Self::Seek { instance_id, .. }
+            | Self::SetVolume { instance_id, .. }
+            | Self::SetMute { instance_id, .. }
+            | Self::Other { instance_id, .. } => *instance_id,
+        }
+    }
+}
+
+impl FromStr for LiteAction {
+    type Err = LiteParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (_, body) = element(s, "Body").ok_or(LiteParseError("Missing `Body` element"))?;
+        let (name, action) = first_child(body).ok_or(LiteParseError("Missing action element"))?;
+        let arg = |name: &'static str| {
+            element(action, name)
+                .map(|(_, text)| unescape(text.trim()))
+                .ok_or(LiteParseError(name))
+        };
+        let instance_id = arg("InstanceID")?
+            .parse()
+            .map_err(|_| LiteParseError("InstanceID"))?;
+
+        Ok(match name {
+            "SetAVTransportURI" => Self::SetAVTransportURI {
+                instance_id,
+                uri: arg("CurrentURI")?,
+            },
+            "SetNextAVTransportURI" => Self::SetNextAVTransportURI {
+                instance_id,
+                uri: arg("NextURI")?,
+            },
+            "Play" => Self::Play {
+                instance_id,
+                speed: arg("Speed")?,
+            },
+            "Seek" => Self::Seek {
+                instance_id,
+                unit: arg("Unit")?,
+                target: arg("Target")?,
+            },
+            "SetVolume" => Self::SetVolume {
+                instance_id,
+                channel: arg("Channel")?,
+                volume: volume::parse(&arg("DesiredVolume")?)
+                    .map_err(|_| LiteParseError("DesiredVolume"))?,
+            },
+            "SetMute" => Self::SetMute {
+                instance_id,
+                channel: arg("Channel")?,
+                mute: boolean::parse(&arg("DesiredMute")?).ok_or(LiteParseError("DesiredMute"))?,
+            },
+            _ => Self::Other {
+                name: name.to_string(),
+                instance_id,
+            },
+        })
+    }
+}
+
+/// Error returned when the lite parser can't find or parse an element, containing the name of the offending element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiteParseError(&'static str);
+
+impl Display for LiteParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to extract `{}` from the message", self.0)
+    }
+}
+
+impl std::error::Error for LiteParseError {}
+
+/// Finds the first start tag in `xml`, returning its qualified name and the rest of `xml` right after the tag. Skips declarations, comments and end tags.
+fn next_start_tag(mut xml: &str) -> Option<(&str, &str)> {
+    loop {
+        let start = xml.find('<')?;
+        xml = &xml[start + 1..];
+        let end = xml.find('>')?;
+        if xml.starts_with(['?', '!', '/']) {
+            xml = &xml[end + 1..];
+            continue;
+        }
+        let name_end = xml.find(|c: char| c.is_whitespace() || c == '/' || c == '>')?;
+        return Some((&xml[..name_end], &xml[end + 1..]));
+    }
+}
+
+/// Strips the namespace prefix from a qualified name.
+fn local_name(name: &str) -> &str {
+    name.rsplit_once(':').map_or(name, |(_, local)| local)
+}
+
+/// Finds the first element with given local name in `xml`, returning its qualified name and content.
+fn element<'a>(mut xml: &'a str, local: &str) -> Option<(&'a str, &'a str)> {
+    loop {
+        let (name, rest) = next_start_tag(xml)?;
+        if local_name(name) == local {
+            let close = format!("</{name}>");
+            let end = rest.find(&close)?;
+            return Some((name, &rest[..end]));
+        }
+        xml = rest;
+    }
+}
+
+/// Finds the first child element in `xml`, returning its local name and content.
+fn first_child(xml: &str) -> Option<(&str, &str)> {
+    let (name, _) = next_start_tag(xml)?;
+    let local = local_name(name);
+    element(xml, local).map(|(_, content)| (local, content))
+}
+
+/// Replaces the predefined XML entities in `text`.
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml::{AVTransport, RenderingControl};
+    use std::fs::read_to_string;
+
+    fn read(path: &str) -> String {
+        read_to_string(format!("tests/{path}")).expect("Failed to read XML file")
+    }
+
+    #[test]
+    fn test_play() {
+        let xml = read("AVTransport/Play.xml");
+        let lite: LiteAction = xml.parse().expect("Failed to parse lite action");
+        let AVTransport::Play(full) = xml.parse().expect("Failed to parse AVTransport") else {
+            panic!("Expected Play variant")
+        };
+        assert_eq!(lite.name(), "Play");
+        assert_eq!(lite.instance_id(), full.instance_id);
+        assert_eq!(
+            lite,
+            LiteAction::Play {
+                instance_id: 0,
+                speed: full.speed.to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_set_volume() {
+        let xml = read("RenderingControl/SetVolume.xml");
+        let lite: LiteAction = xml.parse().expect("Failed to parse lite action");
+        let RenderingControl::SetVolume(full) =
+            xml.parse().expect("Failed to parse RenderingControl")
+        else {
+            panic!("Expected SetVolume variant")
+        };
+        assert_eq!(lite.name(), "SetVolume");
+        assert_eq!(lite.instance_id(), full.instance_id);
+        assert_eq!(
+            lite,
+            LiteAction::SetVolume {
+                instance_id: 0,
+                channel: full.channel.to_string(),
+                volume: full.desired_volume
+            }
+        );
+    }
+
+    #[test]
+    fn test_set_av_transport_uri() {
+        let lite: LiteAction = read("AVTransport/SetAVTransportURI.xml")
+            .parse()
+            .expect("Failed to parse lite action");
+        assert_eq!(
+            lite,
+            LiteAction::SetAVTransportURI {
+                instance_id: 0,
+                uri: "http://example.com/sample.mp4?param1=a&param2=b".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_other() {
+        let lite: LiteAction = read("AVTransport/GetTransportInfo.xml")
+            .parse()
+            .expect("Failed to parse lite action");
+        assert_eq!(lite.name(), "GetTransportInfo");
+        assert_eq!(lite.instance_id(), 0);
+    }
+}
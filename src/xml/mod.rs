@@ -1,8 +1,118 @@
-//! Module for deserializing and extracting information from XML messages.
+//! Module for deserializing and extracting information from XML messages, and building the device description and event documents.
+
+/// Defines the registry of actions of a service enum, whose variants are named after the actions, so that the names can't drift apart.
+///
+/// The variants must be listed in the order of the service's SCPD. This generates `ACTIONS`, listing the names, and `name`, whose exhaustive `match` fails to compile if a variant is missing. Since the names are the stringified variants, they also match the elements `FromStr` deserializes.
+macro_rules! actions {
+    ($service:ident { $($action:ident),+ $(,)? }) => {
+        impl $service {
+            /// Names of all actions, in the order of the SCPD.
+            pub const ACTIONS: &[&str] = &[$(stringify!($action)),+];
+
+            /// The name of the action, e.g. `"Play"`, which is one of [`Self::ACTIONS`].
+            #[must_use]
+            pub const fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$action(_) => stringify!($action),)+
+                }
+            }
+        }
+    };
+}
 
 // Schemas - Generated via [xml_schema_generator](https://thomblin.github.io/xml_schema_generator/)
 pub mod av_transport;
+pub mod connection_manager;
 pub mod rendering_control;
+// Formats embedded in action arguments
+pub mod didl;
+pub mod protocol_info;
+// Documents served
+pub mod device_description;
+// Event documents
+pub mod last_change;
+// Helpers
+mod boolean;
+pub mod soap;
+mod volume;
+// Minimal parser without serde
+#[cfg(feature = "lite-parse")]
+pub mod lite;
 
 pub use av_transport::AVTransport;
+pub use connection_manager::ConnectionManager;
 pub use rendering_control::RenderingControl;
+
+use crate::ParseError;
+use quick_xml::de;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::str::FromStr;
+
+/// The SOAP envelope of a control message, wrapping an action of a service, e.g. [`AVTransport`].
+///
+/// Each service module has an alias for its own, e.g. [`AVTransportEnvelope`](av_transport::AVTransportEnvelope). Usually, once deserialized, you'll call [`Envelope::into_inner`] to consume it and get the actual content of the message, which you could match against the action enum to determine the specific action type. For an even simpler usage, the action enums implement `FromStr`, allowing you to directly deserialize from a XML envelope string.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::xml::{Envelope, RenderingControl};
+///
+/// let xml = r#"<?xml version="1.0" ?>
+/// <SOAP-ENV:Envelope xmlns:SOAP-ENV="http://schemas.xmlsoap.org/soap/envelope/" SOAP-ENV:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+///     <SOAP-ENV:Body>
+///         <u:GetVolume xmlns:u="urn:schemas-upnp-org:service:RenderingControl:1">
+///             <InstanceID>0</InstanceID>
+///             <Channel>Master</Channel>
+///         </u:GetVolume>
+///     </SOAP-ENV:Body>
+/// </SOAP-ENV:Envelope>"#;
+/// let envelope: Envelope<RenderingControl> = xml.parse().expect("Failed to parse envelope");
+/// assert_eq!(envelope.prefix, "SOAP-ENV");
+/// assert_eq!(envelope.into_inner().name(), "GetVolume");
+/// ```
+#[allow(missing_docs, reason = "Wrapper struct")]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Envelope<T> {
+    #[serde(rename = "@encodingStyle")]
+    pub s_encoding_style: String,
+    #[serde(rename = "@xmlns:s", default)]
+    pub xmlns_s: String,
+    #[serde(rename = "Body")]
+    pub s_body: Body<T>,
+    /// The namespace prefix of the envelope, e.g. `s` or `SOAP-ENV`, to reuse in the response. Only detected when parsed with `FromStr`, [`soap::DEFAULT_PREFIX`] otherwise.
+    #[serde(skip, default = "default_prefix")]
+    pub prefix: String,
+}
+
+/// The prefix of envelopes not parsed with `FromStr`.
+fn default_prefix() -> String {
+    soap::DEFAULT_PREFIX.to_string()
+}
+
+impl<T> Envelope<T> {
+    /// Take ownership of the action contained in the envelope, consuming the envelope.
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        self.s_body.content
+    }
+}
+
+impl<T: DeserializeOwned> FromStr for Envelope<T> {
+    type Err = ParseError;
+    /// Deserialize the envelope, detecting its namespace prefix.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let envelope: Self = de::from_str(s).map_err(|e| ParseError::new(s, e))?;
+        Ok(Self {
+            prefix: soap::prefix(s),
+            ..envelope
+        })
+    }
+}
+
+/// Container structure of [`Envelope`].
+#[allow(missing_docs, reason = "Wrapper struct")]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Body<T> {
+    #[serde(rename = "$value")]
+    content: T,
+}
@@ -0,0 +1,369 @@
+//! Module for parsing `protocolInfo` strings, which describe how a resource is transported and what format it's in.
+//!
+//! A `protocolInfo` consists of four colon-separated fields: `<protocol>:<network>:<contentFormat>:<additionalInfo>`, e.g. `http-get:*:video/mp4:DLNA.ORG_PN=AVC_MP4_BL_CIF15_AAC_520;DLNA.ORG_OP=01`. Each field may be `*` as a wildcard.
+//!
+//! For DLNA, the additional info carries the parameters built by [`DlnaParams`], which tell servers how the renderer may seek and stream. Their meaning is defined by the DLNA guidelines (7.4.1.3).
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use std::{fmt::Display, str::FromStr};
+
+/// A parsed `protocolInfo` string.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::xml::protocol_info::ProtocolInfo;
+///
+/// let info: ProtocolInfo = "http-get:*:video/mp4:DLNA.ORG_PN=AVC_MP4_BL_CIF15_AAC_520;DLNA.ORG_OP=01"
+///     .parse()
+///     .expect("Failed to parse protocolInfo");
+/// assert_eq!(info.protocol, "http-get");
+/// assert_eq!(info.content_format, "video/mp4");
+/// assert_eq!(info.dlna_profile.as_deref(), Some("AVC_MP4_BL_CIF15_AAC_520"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolInfo {
+    /// The transport protocol, e.g. `http-get` or `rtsp-rtp-udp`.
+    pub protocol: String,
+    /// The network the resource is available on, usually `*`.
+    pub network: String,
+    /// The content format, usually a MIME type like `video/mp4`.
+    pub content_format: String,
+    /// Protocol-specific additional information, e.g. DLNA parameters separated by `;`.
+    pub additional_info: String,
+    /// The DLNA profile name (`DLNA.ORG_PN`) found in [`additional_info`](ProtocolInfo::additional_info), if any.
+    pub dlna_profile: Option<String>,
+}
+
+impl ProtocolInfo {
+    /// Creates the `protocolInfo` of a resource fetched via HTTP GET, with given content format and DLNA parameters.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use dlna_dmr::xml::protocol_info::{DlnaParams, ProtocolInfo};
+    ///
+    /// let info = ProtocolInfo::http_get("audio/flac", &DlnaParams::new().op_timeseek(true));
+    /// assert_eq!(
+    ///     info.to_string(),
+    ///     "http-get:*:audio/flac:DLNA.ORG_OP=11;DLNA.ORG_FLAGS=01700000000000000000000000000000"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn http_get(content_format: &str, dlna: &DlnaParams) -> Self {
+        Self {
+            protocol: "http-get".to_string(),
+            network: "*".to_string(),
+            content_format: content_format.to_string(),
+            additional_info: dlna.to_string(),
+            dlna_profile: dlna.profile.clone(),
+        }
+    }
+}
+
+impl FromStr for ProtocolInfo {
+    type Err = ProtocolInfoError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The additional info may contain colons itself, so only split thrice.
+        let mut fields = s.trim().splitn(4, ':');
+        let (Some(protocol), Some(network), Some(content_format), Some(additional_info)) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(ProtocolInfoError(s.to_string()));
+        };
+        let dlna_profile = additional_info
+            .split(';')
+            .find_map(|param| param.strip_prefix("DLNA.ORG_PN="))
+            .map(ToString::to_string);
+
+        Ok(Self {
+            protocol: protocol.to_string(),
+            network: network.to_string(),
+            content_format: content_format.to_string(),
+            additional_info: additional_info.to_string(),
+            dlna_profile,
+        })
+    }
+}
+
+impl Display for ProtocolInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}",
+            self.protocol, self.network, self.content_format, self.additional_info
+        )
+    }
+}
+
+impl Serialize for ProtocolInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProtocolInfo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/// DLNA parameters of a `protocolInfo`, i.e. the profile (`DLNA.ORG_PN`), supported operations (`DLNA.ORG_OP`) and flags (`DLNA.ORG_FLAGS`).
+///
+/// Defaults to byte-range seeking and streaming transfers, without a profile.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DlnaParams {
+    /// The DLNA profile name, e.g. `AVC_MP4_BL_CIF15_AAC_520`.
+    pub profile: Option<String>,
+    /// The supported seek operations.
+    pub op: DlnaOp,
+    /// The flags.
+    pub flags: DlnaFlags,
+}
+
+impl DlnaParams {
+    /// Creates the default parameters.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the DLNA profile name.
+    #[must_use]
+    pub fn profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Sets whether seeking by byte range (HTTP `Range`) is supported.
+    #[must_use]
+    pub const fn op_range(mut self, enabled: bool) -> Self {
+        self.op.range = enabled;
+        self
+    }
+
+    /// Sets whether seeking by time (`TimeSeekRange.dlna.org`) is supported.
+    #[must_use]
+    pub const fn op_timeseek(mut self, enabled: bool) -> Self {
+        self.op.time_seek = enabled;
+        self
+    }
+
+    /// Sets whether the streaming transfer mode is supported, for media rendered as it's received, e.g. audio and video.
+    #[must_use]
+    pub const fn streaming(self, enabled: bool) -> Self {
+        self.flag(DlnaFlags::STREAMING_TRANSFER_MODE, enabled)
+    }
+
+    /// Sets whether the interactive transfer mode is supported, for media rendered once received, e.g. images.
+    #[must_use]
+    pub const fn interactive(self, enabled: bool) -> Self {
+        self.flag(DlnaFlags::INTERACTIVE_TRANSFER_MODE, enabled)
+    }
+
+    /// Sets whether the background transfer mode is supported, for media not rendered right away.
+    #[must_use]
+    pub const fn background(self, enabled: bool) -> Self {
+        self.flag(DlnaFlags::BACKGROUND_TRANSFER_MODE, enabled)
+    }
+
+    /// Sets or clears given flag.
+    #[must_use]
+    pub const fn flag(mut self, flag: DlnaFlags, enabled: bool) -> Self {
+        self.flags = if enabled {
+            self.flags.union(flag)
+        } else {
+            self.flags.difference(flag)
+        };
+        self
+    }
+}
+
+impl Display for DlnaParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(profile) = &self.profile {
+            write!(f, "DLNA.ORG_PN={profile};")?;
+        }
+        write!(f, "DLNA.ORG_OP={};DLNA.ORG_FLAGS={}", self.op, self.flags)
+    }
+}
+
+/// Supported seek operations of a resource (`DLNA.ORG_OP`), formatted as two binary digits: time seek, then byte range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DlnaOp {
+    /// Whether seeking by time (`TimeSeekRange.dlna.org`) is supported.
+    pub time_seek: bool,
+    /// Whether seeking by byte range (HTTP `Range`) is supported.
+    pub range: bool,
+}
+
+impl Default for DlnaOp {
+    /// Byte-range seeking only.
+    fn default() -> Self {
+        Self {
+            time_seek: false,
+            range: true,
+        }
+    }
+}
+
+impl Display for DlnaOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", u8::from(self.time_seek), u8::from(self.range))
+    }
+}
+
+/// Flags of a resource (`DLNA.ORG_FLAGS`), formatted as 32 hexadecimal digits: the primary flags, then 24 reserved zeros.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DlnaFlags(u32);
+
+impl DlnaFlags {
+    /// The content source paces the transfer, e.g. a live stream.
+    pub const SENDER_PACED: Self = Self(1 << 31);
+    /// Limited seeking by time is supported.
+    pub const TIME_BASED_SEEK: Self = Self(1 << 30);
+    /// Limited seeking by byte range is supported.
+    pub const BYTE_BASED_SEEK: Self = Self(1 << 29);
+    /// The resource is a container that can be played.
+    pub const PLAY_CONTAINER: Self = Self(1 << 28);
+    /// The beginning of the available content increases over time.
+    pub const S0_INCREASE: Self = Self(1 << 27);
+    /// The end of the available content increases over time.
+    pub const SN_INCREASE: Self = Self(1 << 26);
+    /// Pausing is supported for RTSP.
+    pub const RTSP_PAUSE: Self = Self(1 << 25);
+    /// The streaming transfer mode is supported.
+    pub const STREAMING_TRANSFER_MODE: Self = Self(1 << 24);
+    /// The interactive transfer mode is supported.
+    pub const INTERACTIVE_TRANSFER_MODE: Self = Self(1 << 23);
+    /// The background transfer mode is supported.
+    pub const BACKGROUND_TRANSFER_MODE: Self = Self(1 << 22);
+    /// The connection may be stalled, e.g. while paused, without being closed.
+    pub const CONNECTION_STALL: Self = Self(1 << 21);
+    /// DLNA 1.5 is supported.
+    pub const DLNA_V15: Self = Self(1 << 20);
+
+    /// No flags.
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// The raw primary flags.
+    #[must_use]
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Whether all of `other` are set.
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// The flags set in either.
+    #[must_use]
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// The flags set in `self` but not in `other`.
+    #[must_use]
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl Default for DlnaFlags {
+    /// Streaming and background transfers with stalling, per DLNA 1.5, i.e. `01700000...`.
+    fn default() -> Self {
+        Self::STREAMING_TRANSFER_MODE
+            .union(Self::BACKGROUND_TRANSFER_MODE)
+            .union(Self::CONNECTION_STALL)
+            .union(Self::DLNA_V15)
+    }
+}
+
+impl Display for DlnaFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:08X}{:024}", self.0, 0)
+    }
+}
+
+/// Error returned when a `protocolInfo` string doesn't have four fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolInfoError(String);
+
+impl Display for ProtocolInfoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid protocolInfo `{}`: expected 4 fields", self.0)
+    }
+}
+
+impl std::error::Error for ProtocolInfoError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dlna_protocol_info() {
+        let s = "http-get:*:video/mp4:DLNA.ORG_PN=AVC_MP4_BL_CIF15_AAC_520;DLNA.ORG_OP=01;DLNA.ORG_FLAGS=01700000000000000000000000000000";
+        let info: ProtocolInfo = s.parse().expect("Failed to parse protocolInfo");
+        assert_eq!(info.protocol, "http-get");
+        assert_eq!(info.network, "*");
+        assert_eq!(info.content_format, "video/mp4");
+        assert_eq!(
+            info.additional_info,
+            "DLNA.ORG_PN=AVC_MP4_BL_CIF15_AAC_520;DLNA.ORG_OP=01;DLNA.ORG_FLAGS=01700000000000000000000000000000"
+        );
+        assert_eq!(
+            info.dlna_profile.as_deref(),
+            Some("AVC_MP4_BL_CIF15_AAC_520")
+        );
+        assert_eq!(info.to_string(), s);
+    }
+
+    #[test]
+    fn test_wildcard_protocol_info() {
+        let info: ProtocolInfo = "http-get:*:*:*"
+            .parse()
+            .expect("Failed to parse protocolInfo");
+        assert_eq!(info.protocol, "http-get");
+        assert_eq!(info.network, "*");
+        assert_eq!(info.content_format, "*");
+        assert_eq!(info.additional_info, "*");
+        assert_eq!(info.dlna_profile, None);
+    }
+
+    #[test]
+    fn test_dlna_params() {
+        let params = DlnaParams::new()
+            .profile("AVC_MP4_BL_CIF15_AAC_520")
+            .op_timeseek(true)
+            .interactive(true)
+            .background(false);
+        let info = ProtocolInfo::http_get("video/mp4", &params);
+        assert_eq!(
+            info.dlna_profile.as_deref(),
+            Some("AVC_MP4_BL_CIF15_AAC_520")
+        );
+        let flags = info
+            .additional_info
+            .split(';')
+            .find_map(|param| param.strip_prefix("DLNA.ORG_FLAGS="))
+            .expect("No DLNA.ORG_FLAGS");
+        assert_eq!(flags.len(), 32);
+        let primary = u32::from_str_radix(&flags[..8], 16).expect("Flags are not hexadecimal");
+        assert_eq!(primary, 0x01B0_0000);
+        assert!(flags[8..].chars().all(|c| c == '0'));
+        assert!(info.additional_info.contains("DLNA.ORG_OP=11"));
+        assert_eq!(info.to_string().parse::<ProtocolInfo>(), Ok(info));
+    }
+
+    #[test]
+    fn test_invalid_protocol_info() {
+        assert!("http-get:*:video/mp4".parse::<ProtocolInfo>().is_err());
+    }
+}
@@ -2,10 +2,38 @@
 //!
 //! Documentation on `RenderingControl` v1 can be found [here](http://upnp.org/specs/av/UPnP-av-RenderingControl-v1-Service.pdf).
 
-use quick_xml::{DeError, de};
+use super::{Body, Envelope};
+use crate::{ParseError, UpnpError};
 use serde::{Deserialize, Serialize};
 use std::{fmt::Display, str::FromStr};
 
+/// The service type of `RenderingControl`.
+pub const SERVICE_TYPE: &str = "urn:schemas-upnp-org:service:RenderingControl:1";
+
+/// The maximum of the `Volume` state variable, as seen by controllers.
+pub const VOLUME_MAX: u16 = 100;
+
+/// Scales a volume from the range `0..=from_max` to `0..=to_max`, rounding to the nearest integer. Volumes above `from_max` are clamped to it.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::xml::rendering_control::scale_volume;
+///
+/// assert_eq!(scale_volume(50, 100, 30), 15);
+/// assert_eq!(scale_volume(15, 30, 100), 50);
+/// ```
+#[must_use]
+pub fn scale_volume(volume: u16, from_max: u16, to_max: u16) -> u16 {
+    if from_max == 0 {
+        return 0;
+    }
+    let volume = u32::from(volume.min(from_max));
+    let (from_max, to_max) = (u32::from(from_max), u32::from(to_max));
+    // The result never exceeds `to_max`, so the conversion can't fail.
+    u16::try_from((volume * to_max + from_max / 2) / from_max).unwrap_or(u16::MAX)
+}
+
 /// The envelope structure for `RenderingControl` XML messages.
 ///
 /// Usually, once deserialized, you'll call [`RenderingControlEnvelope::into_inner`] to consume it and get the actual content of the message, which you could match against the [`RenderingControl`] enum to determine the specific action type. For an even simpler usage, [`RenderingControl`] implements `FromStr`, allowing you to directly deserialize from a XML envelope string.
@@ -35,32 +63,10 @@ use std::{fmt::Display, str::FromStr};
 /// assert_eq!(set_action.channel, Channel::Master);
 /// assert_eq!(set_action.desired_volume, 50);
 /// ```
-#[allow(missing_docs, reason = "Wrapper struct")]
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-pub struct RenderingControlEnvelope {
-    #[serde(rename = "@encodingStyle")]
-    pub s_encoding_style: String,
-    #[serde(rename = "@xmlns:s")]
-    pub xmlns_s: String,
-    #[serde(rename = "Body")]
-    pub s_body: SBody,
-}
-
-impl RenderingControlEnvelope {
-    /// Take ownership of the [`RenderingControl`] value contained in the envelope, consuming the envelope.
-    #[must_use]
-    pub fn into_inner(self) -> RenderingControl {
-        self.s_body.content
-    }
-}
+pub type RenderingControlEnvelope = Envelope<RenderingControl>;
 
-/// Container structure.
-#[allow(missing_docs, reason = "Wrapper struct")]
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-pub struct SBody {
-    #[serde(rename = "$value")]
-    content: RenderingControl,
-}
+/// Container structure of [`RenderingControlEnvelope`].
+pub type SBody = Body<RenderingControl>;
 
 /// Different types of action that can be invoked in the `RenderingControl` service. Can be directly parsed from an XML envelope string, IGNORING the outer envelope structure.
 ///
@@ -101,13 +107,66 @@ pub enum RenderingControl {
     GetVolume(GetVolume),
     /// Sets the Volume state variable of the specified Instance and Channel to the specified value.
     SetVolume(SetVolume),
+    /// Retrieves the current value of the `VolumeDB` state variable of the specified channel for the specified instance of this service.
+    GetVolumeDB(GetVolumeDB),
+    /// Sets the `VolumeDB` state variable of the specified Instance and Channel to the specified value.
+    SetVolumeDB(SetVolumeDB),
+    /// Retrieves the current value of the Loudness state variable of the specified channel for the specified instance of this service.
+    GetLoudness(GetLoudness),
+    /// Sets the Loudness state variable of the specified Instance and Channel to the specified value.
+    SetLoudness(SetLoudness),
+}
+
+actions!(RenderingControl {
+    ListPresets,
+    SelectPreset,
+    GetMute,
+    SetMute,
+    GetVolume,
+    SetVolume,
+    GetVolumeDB,
+    SetVolumeDB,
+    GetLoudness,
+    SetLoudness,
+});
+
+impl RenderingControl {
+    /// The virtual instance the action applies to.
+    #[must_use]
+    pub const fn instance_id(&self) -> u32 {
+        match self {
+            Self::ListPresets(ListPresets { instance_id, .. })
+            | Self::SelectPreset(SelectPreset { instance_id, .. })
+            | Self::GetMute(GetMute { instance_id, .. })
+            | Self::SetMute(SetMute { instance_id, .. })
+            | Self::GetVolume(GetVolume { instance_id, .. })
+            | Self::SetVolume(SetVolume { instance_id, .. })
+            | Self::GetVolumeDB(GetVolumeDB { instance_id, .. })
+            | Self::SetVolumeDB(SetVolumeDB { instance_id, .. })
+            | Self::GetLoudness(GetLoudness { instance_id, .. })
+            | Self::SetLoudness(SetLoudness { instance_id, .. }) => *instance_id,
+        }
+    }
+
+    /// Whether the action changes state, as opposed to only querying it.
+    #[must_use]
+    pub const fn is_command(&self) -> bool {
+        matches!(
+            self,
+            Self::SelectPreset(_)
+                | Self::SetMute(_)
+                | Self::SetVolume(_)
+                | Self::SetVolumeDB(_)
+                | Self::SetLoudness(_)
+        )
+    }
 }
 
 impl FromStr for RenderingControl {
-    type Err = DeError;
+    type Err = ParseError;
     /// Deserialize from an envelope, IGNORING the outer envelope structure.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let envelope: RenderingControlEnvelope = de::from_str(s)?;
+        let envelope: RenderingControlEnvelope = s.parse()?;
         Ok(envelope.into_inner())
     }
 }
@@ -169,17 +228,109 @@ pub struct GetMute {
 }
 
 /// Possible values for channels in `GetMute`, `SetMute`, `GetVolume`, and `SetVolume` actions.
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// The standard `UPnP` channels have their own variants, while unexpected ones are kept verbatim in [`Unknown`](Channel::Unknown).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
 pub enum Channel {
     /// The Master channel is a logical channel and, therefore, has no spatial position associated with it.
     Master,
-    // TODO: Other channels?
+    /// Left Front, `LF`.
+    LeftFront,
+    /// Right Front, `RF`.
+    RightFront,
+    /// Center Front, `CF`.
+    CenterFront,
+    /// Low Frequency Enhancement (subwoofer), `LFE`.
+    LowFrequencyEnhancement,
+    /// Left Surround, `LS`.
+    LeftSurround,
+    /// Right Surround, `RS`.
+    RightSurround,
+    /// Left of Center (in front), `LFC`.
+    LeftOfCenter,
+    /// Right of Center (in front), `RFC`.
+    RightOfCenter,
+    /// Surround (rear), `SD`.
+    Surround,
+    /// Side Left (left wall), `SL`.
+    SideLeft,
+    /// Side Right (right wall), `SR`.
+    SideRight,
+    /// Top (overhead), `T`.
+    Top,
+    /// Bottom, `B`.
+    Bottom,
+    /// Any other channel, e.g. a vendor-defined one.
+    Unknown(String),
+}
+
+impl Channel {
+    /// All channels with their own variant, i.e. other than [`Unknown`](Channel::Unknown).
+    pub const KNOWN: [Self; 14] = [
+        Self::Master,
+        Self::LeftFront,
+        Self::RightFront,
+        Self::CenterFront,
+        Self::LowFrequencyEnhancement,
+        Self::LeftSurround,
+        Self::RightSurround,
+        Self::LeftOfCenter,
+        Self::RightOfCenter,
+        Self::Surround,
+        Self::SideLeft,
+        Self::SideRight,
+        Self::Top,
+        Self::Bottom,
+    ];
+}
+
+impl From<String> for Channel {
+    fn from(channel: String) -> Self {
+        match channel.trim() {
+            "Master" => Self::Master,
+            "LF" => Self::LeftFront,
+            "RF" => Self::RightFront,
+            "CF" => Self::CenterFront,
+            "LFE" => Self::LowFrequencyEnhancement,
+            "LS" => Self::LeftSurround,
+            "RS" => Self::RightSurround,
+            "LFC" => Self::LeftOfCenter,
+            "RFC" => Self::RightOfCenter,
+            "SD" => Self::Surround,
+            "SL" => Self::SideLeft,
+            "SR" => Self::SideRight,
+            "T" => Self::Top,
+            "B" => Self::Bottom,
+            other => Self::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl From<Channel> for String {
+    fn from(channel: Channel) -> Self {
+        channel.to_string()
+    }
 }
 
 impl Display for Channel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Master => write!(f, "Master"),
+            Self::LeftFront => write!(f, "LF"),
+            Self::RightFront => write!(f, "RF"),
+            Self::CenterFront => write!(f, "CF"),
+            Self::LowFrequencyEnhancement => write!(f, "LFE"),
+            Self::LeftSurround => write!(f, "LS"),
+            Self::RightSurround => write!(f, "RS"),
+            Self::LeftOfCenter => write!(f, "LFC"),
+            Self::RightOfCenter => write!(f, "RFC"),
+            Self::Surround => write!(f, "SD"),
+            Self::SideLeft => write!(f, "SL"),
+            Self::SideRight => write!(f, "SR"),
+            Self::Top => write!(f, "T"),
+            Self::Bottom => write!(f, "B"),
+            Self::Unknown(channel) => write!(f, "{channel}"),
         }
     }
 }
@@ -191,7 +342,10 @@ pub struct SetMute {
     #[serde(rename = "@xmlns:u")]
     pub xmlns_u: String,
     /// Desired Mute state.
-    #[serde(rename = "DesiredMute")]
+    #[serde(
+        rename = "DesiredMute",
+        deserialize_with = "super::boolean::deserialize"
+    )]
     pub desired_mute: bool,
     /// A particular channel of an audio output stream.
     #[serde(rename = "Channel")]
@@ -221,8 +375,11 @@ pub struct SetVolume {
     /// The XML namespace for the `AVTransport` service.
     #[serde(rename = "@xmlns:u")]
     pub xmlns_u: String,
-    /// Desired volume level. Should be between 0 and 100, inclusive.
-    #[serde(rename = "DesiredVolume")]
+    /// Desired volume level. Should be between 0 and 100, inclusive. Floats with a zero fractional part like `50.0` are accepted.
+    #[serde(
+        rename = "DesiredVolume",
+        deserialize_with = "super::volume::deserialize"
+    )]
     pub desired_volume: u16,
     /// A particular channel of an audio output stream.
     #[serde(rename = "Channel")]
@@ -232,6 +389,97 @@ pub struct SetVolume {
     pub instance_id: u32,
 }
 
+impl SetVolume {
+    /// The desired volume scaled from `0..=100` ([`VOLUME_MAX`]) to the hardware range `0..=hardware_max`, rounding to the nearest integer.
+    #[must_use]
+    pub fn scaled(&self, hardware_max: u16) -> u16 {
+        scale_volume(self.desired_volume, VOLUME_MAX, hardware_max)
+    }
+
+    /// The desired volume, clamped to `0..=max`. Use [`VOLUME_MAX`] for the range seen by controllers.
+    #[must_use]
+    pub fn clamped_volume(&self, max: u16) -> u16 {
+        self.desired_volume.min(max)
+    }
+
+    /// Checks that the desired volume lies within `0..=100` ([`VOLUME_MAX`]), for handlers that prefer rejecting out-of-range volumes over clamping them with [`clamped_volume`](SetVolume::clamped_volume).
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`UpnpError::argument_value_out_of_range`] if the desired volume exceeds [`VOLUME_MAX`].
+    pub fn validate(&self) -> Result<(), UpnpError> {
+        if self.desired_volume > VOLUME_MAX {
+            return Err(UpnpError::argument_value_out_of_range());
+        }
+        Ok(())
+    }
+}
+
+/// Arguments for [`RenderingControl::GetVolumeDB`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GetVolumeDB {
+    /// The XML namespace for the `AVTransport` service.
+    #[serde(rename = "@xmlns:u")]
+    pub xmlns_u: String,
+    /// A particular channel of an audio output stream.
+    #[serde(rename = "Channel")]
+    pub channel: Channel,
+    /// The virtual instance of the `AVTransport` service to which the action applies.
+    #[serde(rename = "InstanceID")]
+    pub instance_id: u32,
+}
+
+/// Arguments for [`RenderingControl::SetVolumeDB`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SetVolumeDB {
+    /// The XML namespace for the `AVTransport` service.
+    #[serde(rename = "@xmlns:u")]
+    pub xmlns_u: String,
+    /// Desired volume in centibels, i.e. 1/100 dB, e.g. `-1500` for an attenuation of 15 dB.
+    #[serde(rename = "DesiredVolume")]
+    pub desired_volume: i16,
+    /// A particular channel of an audio output stream.
+    #[serde(rename = "Channel")]
+    pub channel: Channel,
+    /// The virtual instance of the `AVTransport` service to which the action applies.
+    #[serde(rename = "InstanceID")]
+    pub instance_id: u32,
+}
+
+/// Arguments for [`RenderingControl::GetLoudness`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GetLoudness {
+    /// The XML namespace for the `AVTransport` service.
+    #[serde(rename = "@xmlns:u")]
+    pub xmlns_u: String,
+    /// A particular channel of an audio output stream.
+    #[serde(rename = "Channel")]
+    pub channel: Channel,
+    /// The virtual instance of the `AVTransport` service to which the action applies.
+    #[serde(rename = "InstanceID")]
+    pub instance_id: u32,
+}
+
+/// Arguments for [`RenderingControl::SetLoudness`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SetLoudness {
+    /// The XML namespace for the `AVTransport` service.
+    #[serde(rename = "@xmlns:u")]
+    pub xmlns_u: String,
+    /// Desired Loudness state.
+    #[serde(
+        rename = "DesiredLoudness",
+        deserialize_with = "super::boolean::deserialize"
+    )]
+    pub desired_loudness: bool,
+    /// A particular channel of an audio output stream.
+    #[serde(rename = "Channel")]
+    pub channel: Channel,
+    /// The virtual instance of the `AVTransport` service to which the action applies.
+    #[serde(rename = "InstanceID")]
+    pub instance_id: u32,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -244,6 +492,18 @@ mod tests {
         av_transport
     }
 
+    #[test]
+    fn test_action_registry() {
+        use crate::template::{RENDERING_CONTROL_SCPD, scpd_actions};
+
+        assert_eq!(
+            scpd_actions(RENDERING_CONTROL_SCPD),
+            RenderingControl::ACTIONS
+        );
+        for name in RenderingControl::ACTIONS {
+            assert_eq!(get_xml(&format!("{name}.xml")).name(), *name);
+        }
+    }
     #[test]
     fn test_list_presets() {
         let list_presets = get_xml("ListPresets.xml");
@@ -284,6 +544,72 @@ mod tests {
         assert!(set.desired_mute);
     }
 
+    #[test]
+    fn test_set_mute_lenient() {
+        let xml =
+            read_to_string("tests/RenderingControl/SetMute.xml").expect("Failed to read XML file");
+        for (value, expected) in [
+            ("true", true),
+            ("Yes", true),
+            ("0", false),
+            ("FALSE", false),
+        ] {
+            let xml = xml.replace(
+                "<DesiredMute>1</DesiredMute>",
+                &format!("<DesiredMute>{value}</DesiredMute>"),
+            );
+            let RenderingControl::SetMute(set) = xml.parse().expect("Failed to parse SetMute")
+            else {
+                panic!("Expected SetMute variant");
+            };
+            assert_eq!(set.desired_mute, expected, "{value}");
+        }
+        let xml = xml.replace(
+            "<DesiredMute>1</DesiredMute>",
+            "<DesiredMute>on</DesiredMute>",
+        );
+        assert!(xml.parse::<RenderingControl>().is_err());
+    }
+
+    #[test]
+    fn test_set_volume_scaled() {
+        let RenderingControl::SetVolume(mut set) = get_xml("SetVolume.xml") else {
+            panic!("Expected SetVolume variant");
+        };
+        set.desired_volume = 50;
+        assert_eq!(set.scaled(30), 15);
+        set.desired_volume = 100;
+        assert_eq!(set.scaled(30), 30);
+        set.desired_volume = 1;
+        assert_eq!(set.scaled(30), 0);
+        set.desired_volume = 200;
+        assert_eq!(set.scaled(30), 30);
+    }
+
+    #[test]
+    fn test_set_volume_range() {
+        let RenderingControl::SetVolume(mut set) = get_xml("SetVolume.xml") else {
+            panic!("Expected SetVolume variant");
+        };
+        for (volume, clamped, valid) in [
+            (0, 0, true),
+            (50, 50, true),
+            (100, 100, true),
+            (101, 100, false),
+            (200, 100, false),
+        ] {
+            set.desired_volume = volume;
+            assert_eq!(set.clamped_volume(VOLUME_MAX), clamped, "{volume}");
+            assert_eq!(set.validate().is_ok(), valid, "{volume}");
+        }
+        set.desired_volume = 200;
+        assert_eq!(set.clamped_volume(30), 30);
+        assert_eq!(
+            set.validate(),
+            Err(UpnpError::argument_value_out_of_range())
+        );
+    }
+
     #[test]
     fn test_get_volume() {
         let get_volume = get_xml("GetVolume.xml");
@@ -304,4 +630,107 @@ mod tests {
         assert_eq!(set.channel, Channel::Master);
         assert_eq!(set.desired_volume, 50);
     }
+
+    #[test]
+    fn test_set_volume_channel() {
+        let xml = read_to_string("tests/RenderingControl/SetVolume.xml")
+            .expect("Failed to read XML file");
+        let xml = xml.replace("<Channel>Master</Channel>", "<Channel>LF</Channel>");
+        let Ok(RenderingControl::SetVolume(set)) = xml.parse() else {
+            panic!("Expected SetVolume variant");
+        };
+        assert_eq!(set.channel, Channel::LeftFront);
+        assert_eq!(set.desired_volume, 50);
+    }
+
+    #[test]
+    fn test_channels() {
+        for channel in Channel::KNOWN {
+            assert_eq!(Channel::from(channel.to_string()), channel);
+        }
+        assert_eq!(
+            Channel::from("Vendor_X".to_string()),
+            Channel::Unknown("Vendor_X".to_string())
+        );
+        assert_eq!(
+            Channel::Unknown("Vendor_X".to_string()).to_string(),
+            "Vendor_X"
+        );
+    }
+
+    #[test]
+    fn test_set_volume_float() {
+        let xml = read_to_string("tests/RenderingControl/SetVolume.xml")
+            .expect("Failed to read XML file");
+        for (volume, expected) in [("50", 50), ("50.0", 50)] {
+            let xml = xml.replace(
+                "<DesiredVolume>50</DesiredVolume>",
+                &format!("<DesiredVolume>{volume}</DesiredVolume>"),
+            );
+            let Ok(RenderingControl::SetVolume(set)) = xml.parse() else {
+                panic!("Expected SetVolume variant for {volume}");
+            };
+            assert_eq!(set.desired_volume, expected);
+        }
+        let xml = xml.replace(
+            "<DesiredVolume>50</DesiredVolume>",
+            "<DesiredVolume>50.5</DesiredVolume>",
+        );
+        let err = xml
+            .parse::<RenderingControl>()
+            .expect_err("Fractional volume should be rejected");
+        assert!(err.to_string().contains("not a whole number"), "{err}");
+    }
+
+    #[test]
+    fn test_get_volume_db() {
+        let RenderingControl::GetVolumeDB(get) = get_xml("GetVolumeDB.xml") else {
+            panic!("Expected GetVolumeDB variant");
+        };
+        assert_eq!(get.instance_id, 0);
+        assert_eq!(get.channel, Channel::Master);
+    }
+
+    #[test]
+    fn test_set_volume_db() {
+        let xml = read_to_string("tests/RenderingControl/SetVolumeDB.xml")
+            .expect("Failed to read XML file");
+        for (volume, expected) in [("-1500", -1500), ("0", 0), ("600", 600), ("-32768", -32768)] {
+            let xml = xml.replace(
+                "<DesiredVolume>-1500</DesiredVolume>",
+                &format!("<DesiredVolume>{volume}</DesiredVolume>"),
+            );
+            let Ok(RenderingControl::SetVolumeDB(set)) = xml.parse() else {
+                panic!("Expected SetVolumeDB variant for {volume}");
+            };
+            assert_eq!(set.instance_id, 0);
+            assert_eq!(set.channel, Channel::Master);
+            assert_eq!(set.desired_volume, expected);
+        }
+        let xml = xml.replace(
+            "<DesiredVolume>-1500</DesiredVolume>",
+            "<DesiredVolume>-40000</DesiredVolume>",
+        );
+        assert!(xml.parse::<RenderingControl>().is_err());
+    }
+
+    #[test]
+    fn test_get_loudness() {
+        let RenderingControl::GetLoudness(get) = get_xml("GetLoudness.xml") else {
+            panic!("Expected GetLoudness variant");
+        };
+        assert_eq!(get.instance_id, 0);
+        assert_eq!(get.channel, Channel::Master);
+    }
+
+    #[test]
+    fn test_set_loudness() {
+        let RenderingControl::SetLoudness(set) = get_xml("SetLoudness.xml") else {
+            panic!("Expected SetLoudness variant");
+        };
+        assert_eq!(set.instance_id, 0);
+        assert_eq!(set.channel, Channel::Master);
+        assert!(set.desired_loudness);
+        assert!(RenderingControl::SetLoudness(set).is_command());
+    }
 }
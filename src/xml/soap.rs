@@ -0,0 +1,260 @@
+//! Module for inspecting SOAP envelopes beyond their content.
+//!
+//! Controllers differ in the namespace prefix of their envelopes, e.g. `<s:Envelope>` or `<SOAP-ENV:Envelope>`, and some strict ones only accept responses using the same prefix.
+
+use crate::ParseError;
+use quick_xml::{DeError, Reader, de, events::Event};
+use serde::de::DeserializeOwned;
+use std::{fmt::Display, str::FromStr};
+
+/// The prefix used if the envelope isn't prefixed, e.g. `<Envelope xmlns="...">`.
+pub const DEFAULT_PREFIX: &str = "s";
+
+/// Wraps `body`, the already serialized content of the SOAP body, into an envelope using the namespace prefix `prefix`, e.g. [`DEFAULT_PREFIX`].
+#[must_use]
+pub fn envelope(prefix: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<{prefix}:Envelope xmlns:{prefix}="http://schemas.xmlsoap.org/soap/envelope/" {prefix}:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/"><{prefix}:Body>{body}</{prefix}:Body></{prefix}:Envelope>"#
+    )
+}
+
+/// Detects the namespace prefix of the SOAP envelope in `xml`, i.e. of its root element, defaulting to [`DEFAULT_PREFIX`] if it has none or the document is malformed.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::xml::soap::prefix;
+///
+/// let xml = r#"<?xml version="1.0"?>
+/// <SOAP-ENV:Envelope xmlns:SOAP-ENV="http://schemas.xmlsoap.org/soap/envelope/">
+///     <SOAP-ENV:Body/>
+/// </SOAP-ENV:Envelope>"#;
+/// assert_eq!(prefix(xml), "SOAP-ENV");
+/// ```
+#[must_use]
+pub fn prefix(xml: &str) -> String {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element) | Event::Empty(element)) => {
+                return element.name().prefix().map_or_else(
+                    || DEFAULT_PREFIX.into(),
+                    |prefix| String::from_utf8_lossy(prefix.as_ref()).into_owned(),
+                );
+            }
+            Ok(Event::Eof) | Err(_) => return DEFAULT_PREFIX.to_string(),
+            Ok(_) => {}
+        }
+    }
+}
+
+/// Detects the name of the action in `xml`, i.e. the local name of the first element in the SOAP body.
+///
+/// The envelope isn't deserialized, so this works even if its content is invalid. Returns `None` if there's no action or the document is malformed before it.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::xml::soap::action;
+///
+/// let xml = r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/">
+///     <s:Body><u:Play xmlns:u="urn:schemas-upnp-org:service:AVTransport:1"/></s:Body>
+/// </s:Envelope>"#;
+/// assert_eq!(action(xml).as_deref(), Some("Play"));
+/// ```
+#[must_use]
+pub fn action(xml: &str) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut in_body = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element) | Event::Empty(element)) if in_body => {
+                return Some(String::from_utf8_lossy(element.local_name().as_ref()).into_owned());
+            }
+            Ok(Event::Start(element)) => in_body = element.local_name().as_ref() == b"Body",
+            Ok(Event::End(_)) if in_body => return None,
+            Ok(Event::Eof) | Err(_) => return None,
+            Ok(_) => {}
+        }
+    }
+}
+
+/// The `SOAPACTION` header of a control request, naming the service type and the action invoked, e.g. `"urn:schemas-upnp-org:service:AVTransport:1#Play"`.
+///
+/// ## Example
+///
+/// ```rust
+/// use dlna_dmr::xml::soap::SoapAction;
+///
+/// let soap_action: SoapAction = r#""urn:schemas-upnp-org:service:AVTransport:1#Play""#
+///     .parse()
+///     .expect("Malformed SOAPACTION");
+/// assert_eq!(soap_action.service_type, "urn:schemas-upnp-org:service:AVTransport:1");
+/// assert_eq!(soap_action.action, "Play");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoapAction {
+    /// The service type, e.g. `urn:schemas-upnp-org:service:AVTransport:1`.
+    pub service_type: String,
+    /// The name of the action, e.g. `Play`.
+    pub action: String,
+}
+
+impl SoapAction {
+    /// The name of the header.
+    pub const HEADER: &str = "SOAPACTION";
+}
+
+impl FromStr for SoapAction {
+    type Err = ();
+    /// Parses the value of the header, with or without the surrounding quotes. Fails if there's no `#` or nothing after it.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .unwrap_or(s);
+        match s.rsplit_once('#') {
+            Some((service_type, action)) if !action.is_empty() => Ok(Self {
+                service_type: service_type.to_string(),
+                action: action.to_string(),
+            }),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for SoapAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, r#""{}#{}""#, self.service_type, self.action)
+    }
+}
+
+/// Deserializes `action`, one of `actions`, from `xml`, considering only the first element in the SOAP body instead of the whole envelope.
+///
+/// This is how the default routes parse requests carrying a [`SoapAction`] header: unknown actions are rejected before anything is deserialized, and envelopes whose shape the service enums don't expect, e.g. without `encodingStyle`, are accepted.
+///
+/// ## Errors
+///
+/// Returns a [`ParseError`] if `action` isn't one of `actions`, the body invokes another action, or its arguments fail to deserialize.
+pub fn parse_action<T: DeserializeOwned>(
+    action: &str,
+    actions: &[&str],
+    xml: &str,
+) -> Result<T, ParseError> {
+    let error = |message: String| ParseError {
+        action: Some(action.to_string()),
+        error: DeError::Custom(message),
+    };
+    if !actions.contains(&action) {
+        return Err(error(format!("Unknown action {action}")));
+    }
+    let Some((name, element)) = action_element(xml) else {
+        return Err(error("No action in the SOAP body".to_string()));
+    };
+    if name != action {
+        return Err(error(format!("The SOAP body invokes {name} instead")));
+    }
+    de::from_str(element).map_err(|e| ParseError::new(xml, e))
+}
+
+/// The local name and source of the first element in the SOAP body of `xml`.
+fn action_element(xml: &str) -> Option<(String, &str)> {
+    let mut reader = Reader::from_str(xml);
+    let mut in_body = false;
+    loop {
+        let start = usize::try_from(reader.buffer_position()).ok()?;
+        match reader.read_event() {
+            Ok(Event::Start(element)) if in_body => {
+                let name = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+                reader.read_to_end(element.name()).ok()?;
+                let end = usize::try_from(reader.buffer_position()).ok()?;
+                return Some((name, xml.get(start..end)?));
+            }
+            Ok(Event::Empty(element)) if in_body => {
+                let name = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+                let end = usize::try_from(reader.buffer_position()).ok()?;
+                return Some((name, xml.get(start..end)?));
+            }
+            Ok(Event::Start(element)) => in_body = element.local_name().as_ref() == b"Body",
+            Ok(Event::End(_)) if in_body => return None,
+            Ok(Event::Eof) | Err(_) => return None,
+            Ok(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix() {
+        let xml =
+            std::fs::read_to_string("tests/AVTransport/Play.xml").expect("Failed to read XML file");
+        assert_eq!(prefix(&xml), "s");
+        assert_eq!(prefix(r#"<Envelope xmlns="urn:x"><Body/></Envelope>"#), "s");
+        assert_eq!(prefix("not XML at all"), "s");
+    }
+
+    #[test]
+    fn test_action() {
+        let xml =
+            std::fs::read_to_string("tests/AVTransport/Play.xml").expect("Failed to read XML file");
+        assert_eq!(action(&xml).as_deref(), Some("Play"));
+        assert_eq!(
+            action(r#"<Envelope xmlns="urn:x"><Header/><Body></Body></Envelope>"#),
+            None
+        );
+        assert_eq!(action("not XML at all"), None);
+    }
+
+    #[test]
+    fn test_soap_action() {
+        let soap_action: SoapAction = r#""urn:schemas-upnp-org:service:AVTransport:1#Play""#
+            .parse()
+            .expect("Malformed SOAPACTION");
+        assert_eq!(soap_action.action, "Play");
+        assert_eq!(
+            soap_action.to_string(),
+            r#""urn:schemas-upnp-org:service:AVTransport:1#Play""#
+        );
+        // Unquoted, as some controllers send it
+        assert_eq!(
+            "urn:schemas-upnp-org:service:AVTransport:1#Play".parse(),
+            Ok(soap_action)
+        );
+        assert_eq!(
+            "urn:schemas-upnp-org:service:AVTransport:1".parse::<SoapAction>(),
+            Err(())
+        );
+        assert_eq!(r#""urn:x#""#.parse::<SoapAction>(), Err(()));
+    }
+
+    #[test]
+    fn test_parse_action() {
+        use crate::xml::AVTransport;
+
+        let xml =
+            std::fs::read_to_string("tests/AVTransport/Play.xml").expect("Failed to read XML file");
+        let play: AVTransport =
+            parse_action("Play", AVTransport::ACTIONS, &xml).expect("Failed to parse Play");
+        assert_eq!(play.name(), "Play");
+        // Without `encodingStyle`, which the envelope struct requires
+        let bare = xml.replace(
+            r#" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/""#,
+            "",
+        );
+        assert!(bare.parse::<AVTransport>().is_err());
+        assert_eq!(
+            parse_action("Play", AVTransport::ACTIONS, &bare).ok(),
+            Some(play)
+        );
+
+        let error = parse_action::<AVTransport>("Rewind", AVTransport::ACTIONS, &xml)
+            .expect_err("Rewind is unknown");
+        assert_eq!(error.action.as_deref(), Some("Rewind"));
+        assert!(parse_action::<AVTransport>("Stop", AVTransport::ACTIONS, &xml).is_err());
+    }
+}
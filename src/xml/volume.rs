@@ -0,0 +1,46 @@
+//! Lenient parsing of volume levels.
+//!
+//! Some controllers send volumes in float form, e.g. `50.0`, which fails to deserialize as an integer. Use [`deserialize`] via `#[serde(deserialize_with = "...")]` on volume fields.
+
+use serde::{Deserialize, Deserializer, de};
+use std::borrow::Cow;
+
+/// Parses a volume level, accepting integers and floats with a zero fractional part, ignoring surrounding whitespace.
+pub fn parse(s: &str) -> Result<u16, String> {
+    let s = s.trim();
+    if let Ok(volume) = s.parse() {
+        return Ok(volume);
+    }
+    let (integer, fraction) = s
+        .split_once('.')
+        .ok_or_else(|| format!("Invalid volume `{s}`"))?;
+    if fraction.is_empty() || !fraction.bytes().all(|b| b == b'0') {
+        return Err(format!("Volume `{s}` is not a whole number"));
+    }
+    integer.parse().map_err(|_| format!("Invalid volume `{s}`"))
+}
+
+/// Deserializes a volume level.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u16, D::Error> {
+    let s = Cow::<str>::deserialize(deserializer)?;
+    parse(&s).map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        for s in ["50", "50.0", "50.00", " 50 "] {
+            assert_eq!(parse(s), Ok(50), "{s}");
+        }
+        assert_eq!(
+            parse("50.5"),
+            Err("Volume `50.5` is not a whole number".to_string())
+        );
+        for s in ["", "50.", "-1", "abc", "1e2", "70000.0"] {
+            assert!(parse(s).is_err(), "{s}");
+        }
+    }
+}